@@ -0,0 +1,84 @@
+//! `cargo bench --bench filter`
+//! EasyListと同じくらいの数のルールを生成して読み込みと照合の時間を測る
+
+use std::{hint::black_box, time::Instant};
+
+use app_lib::view::ctrl::blocker::filter::{FilterEngine, Request, ResourceType, Verdict};
+
+const NETWORK: usize = 50_000;
+const COSMETIC: usize = 20_000;
+const REQUESTS: usize = 100_000;
+
+fn sample_list() -> String {
+  let mut list = String::from("[Adblock Plus 2.0]\n! Title: bench\n");
+  for i in 0..NETWORK {
+    let line = match i % 5 {
+      0 => format!("||ads{}.example{}.com^\n", i, i % 97),
+      1 => format!("||tracker{}.net^$third-party\n", i),
+      2 => format!("/banner{}/*/ad_$image\n", i),
+      3 => format!("||cdn{}.example.org^$script,domain=news{}.com|~sports.news{}.com\n", i, i, i),
+      _ => format!("@@||ads{}.example{}.com/allowed/\n", i - 4, (i - 4) % 97),
+    };
+    list.push_str(&line);
+  }
+  for i in 0..COSMETIC {
+    match i % 2 {
+      0 => list.push_str(&format!("##.ad-slot-{}\n", i)),
+      _ => list.push_str(&format!("news{}.com##.promo-{}\n", i, i)),
+    }
+  }
+
+  list
+}
+
+fn sample_urls() -> Vec<String> {
+  (0..REQUESTS)
+    .map(|i| match i % 4 {
+      0 => format!("https://ads{}.example{}.com/img/{}.png", i % NETWORK, i % 97, i),
+      1 => format!("https://site{}.com/banner{}/300x250/ad_{}.gif", i, i % NETWORK, i),
+      2 => format!("https://cdn.jsdelivr.net/npm/package@{}/dist/index.js", i),
+      _ => format!("https://www.news.com/articles/{}?ref=home&utm_source=feed", i),
+    })
+    .collect()
+}
+
+fn main() {
+  let list = sample_list();
+  let start = Instant::now();
+  let engine = FilterEngine::parse(&list);
+  let parse = start.elapsed();
+  let stats = engine.stats();
+  println!(
+    "parse: {:?} ({} network, {} cosmetic, {} bytes)",
+    parse,
+    stats.network,
+    stats.cosmetic,
+    list.len()
+  );
+
+  let urls = sample_urls();
+  let start = Instant::now();
+  let mut blocked = 0;
+  for url in &urls {
+    let request = Request {
+      url,
+      source_host: Some("www.news.com"),
+      kind: ResourceType::Image,
+    };
+    if black_box(engine.check(&request)) == Verdict::Block {
+      blocked += 1;
+    }
+  }
+  let check = start.elapsed();
+  println!(
+    "check: {:?} total, {:?}/request ({} requests)",
+    check,
+    check / urls.len() as u32,
+    urls.len()
+  );
+  black_box(blocked);
+
+  let start = Instant::now();
+  let css = black_box(engine.cosmetic_css("www.news.com"));
+  println!("cosmetic_css: {:?} ({} bytes)", start.elapsed(), css.len());
+}
@@ -0,0 +1,299 @@
+pub mod args;
+pub mod deep_link;
+pub mod instance;
+
+use std::{io, net::TcpListener, process, sync::atomic::Ordering, thread, time::Duration};
+
+use anyhow::Context;
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewUrl};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{
+  util::AppState,
+  view::{
+    ctrl::{self, pin, transparent, user_agent},
+    util::{to_ctrl_label, toggle_overlays_visible, window_focus},
+  },
+  MAIN_LABEL,
+};
+
+use args::{parse_args, CliCommand, OpenArgs, USAGE};
+use instance::{acquire, forward, key_path, Instance, Reply, INSTANCE_PORT};
+
+/// 起動中のインスタンスの鍵を確かめ直す回数と間隔
+const HANDSHAKE_RETRY: u32 = 10;
+const RETRY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 起動時の引数を処理する、Noneならアプリを起動せずに終わる
+/// 既に起動していれば引数を送って終わり、そうでなければ引数を受け付けるlistenerとその鍵を返す
+pub fn init(args: &[String]) -> Option<(CliCommand, Option<(TcpListener, String)>)> {
+  attach_console();
+
+  let command = match parse_args(args) {
+    Ok(v) => v,
+    Err(e) => {
+      eprintln!("{}\n\n{}", e, USAGE);
+      process::exit(2);
+    }
+  };
+  match command {
+    CliCommand::Help => {
+      println!("{}", USAGE);
+      return None;
+    }
+    CliCommand::Version => {
+      println!("relais {}", env!("CARGO_PKG_VERSION"));
+      return None;
+    }
+    _ => (),
+  }
+
+  // 起動したばかりのインスタンスはbindしてから鍵を書くので、鍵がないか違うときは少し待って確かめ直す
+  let mut retry = 0;
+  loop {
+    let last = retry == HANDSHAKE_RETRY;
+    match acquire(INSTANCE_PORT, &key_path()) {
+      Ok(Instance::Primary { listener, key }) => {
+        if command.needs_instance() {
+          eprintln!("relais is not running");
+          process::exit(1);
+        }
+        return Some((command, Some((listener, key))));
+      }
+      Ok(Instance::Secondary { stream, key }) => match forward(stream, &key, args) {
+        Ok(Reply::Ok(output)) => {
+          print!("{}", output);
+          return None;
+        }
+        Ok(Reply::Error(e)) => {
+          eprintln!("{}", e);
+          process::exit(1);
+        }
+        Ok(Reply::Rejected) if !last => (),
+        Ok(Reply::Rejected) => {
+          println!("the running instance rejected the key");
+          return start_alone(command);
+        }
+        // ポートを使っているのがrelaisでなければ普通に起動する
+        Err(e) => {
+          println!("failed to forward arguments to the running instance: {}", e);
+          return start_alone(command);
+        }
+      },
+      Err(e) if e.kind() == io::ErrorKind::NotFound && !last => (),
+      // 確認できなくても起動はする
+      Err(e) => {
+        println!("failed to check the running instance: {}", e);
+        return start_alone(command);
+      }
+    }
+    retry += 1;
+    thread::sleep(RETRY_INTERVAL);
+  }
+}
+
+/// 引数を受け付けずに起動する
+fn start_alone(command: CliCommand) -> Option<(CliCommand, Option<(TcpListener, String)>)> {
+  if command.needs_instance() {
+    eprintln!("relais is not running");
+    process::exit(1);
+  }
+  Some((command, None))
+}
+
+/// GUIのアプリなので、コマンドプロンプトから実行したときは親のコンソールに出力する
+fn attach_console() {
+  #[cfg(windows)]
+  unsafe {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+  }
+}
+
+/// HKEY_CURRENT_USER\Software\Classes\relaisに実行ファイルを登録する、インストーラーを使わなくてもリンクから開けるように
+pub fn register_scheme() -> anyhow::Result<()> {
+  #[cfg(windows)]
+  unsafe {
+    use windows::{
+      core::{HSTRING, PCWSTR},
+      Win32::System::Registry::{RegCloseKey, RegCreateKeyW, RegSetValueExW, HKEY, HKEY_CURRENT_USER, REG_SZ},
+    };
+
+    let key = format!("Software\\Classes\\{}", deep_link::SCHEME);
+    let command = format!("\"{}\" \"%1\"", std::env::current_exe()?.display());
+    let values = [
+      (key.clone(), None, "URL:Relais".to_string()),
+      (key.clone(), Some("URL Protocol"), String::new()),
+      (key + "\\shell\\open\\command", None, command),
+    ];
+    for (key, name, value) in values {
+      let mut hkey = HKEY::default();
+      RegCreateKeyW(HKEY_CURRENT_USER, &HSTRING::from(key), &mut hkey).ok()?;
+      let name = name.map(HSTRING::from);
+      let data = value.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect::<Vec<_>>();
+      let res = RegSetValueExW(
+        hkey,
+        name.as_ref().map_or(PCWSTR::null(), |v| PCWSTR(v.as_ptr())),
+        0,
+        REG_SZ,
+        Some(&data),
+      );
+      let _ = RegCloseKey(hkey);
+      res.ok()?;
+    }
+  }
+
+  Ok(())
+}
+
+/// 最初のインスタンスで実行する、戻り値は呼び出し元のコンソールに出力する
+pub fn execute(app: &AppHandle, command: CliCommand) -> anyhow::Result<String> {
+  let state = app.state::<AppState>();
+  let output = match command {
+    CliCommand::Run => {
+      if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+        window_focus(&window)?;
+      }
+      String::new()
+    }
+    CliCommand::Open(args) => open(app, args)? + "\n",
+    CliCommand::DeepLink(args) => {
+      if state.config.read().unwrap().deep_link.is_allowed(&args.url) {
+        open(app, args)? + "\n"
+      } else {
+        confirm_deep_link(app, args)?;
+        String::new()
+      }
+    }
+    CliCommand::List => state
+      .get_windows()
+      .iter()
+      .enumerate()
+      .map(|(i, v)| format!("{}\t{}\t{}\n", i + 1, v.label, v.url))
+      .collect(),
+    CliCommand::Close(id) => {
+      let label = find_overlay(&state, &id)?;
+      let ctrl = app
+        .get_webview_window(&to_ctrl_label(label.as_str()))
+        .context("ctrl is not found")?;
+      ctrl::command::view_close(app.clone(), state, ctrl).map_err(anyhow::Error::msg)?;
+      String::new()
+    }
+    CliCommand::Toggle => {
+      let visible = toggle_overlays_visible(app)?;
+      format!("{}\n", if visible { "shown" } else { "hidden" })
+    }
+    CliCommand::Help => USAGE.to_string() + "\n",
+    CliCommand::Version => format!("relais {}\n", env!("CARGO_PKG_VERSION")),
+  };
+
+  Ok(output)
+}
+
+/// 作ったオーバーレイのlabelを返す
+fn open(app: &AppHandle, args: OpenArgs) -> anyhow::Result<String> {
+  let state = app.state::<AppState>();
+  let label = ctrl::view_create(
+    app,
+    &state,
+    WebviewUrl::External(args.url.clone()),
+    args.profile.clone(),
+    None,
+    false,
+    None,
+  )?;
+  let window = app.get_webview_window(&label).context("window is not found")?;
+  let ctrl = app
+    .get_webview_window(&to_ctrl_label(label.as_str()))
+    .context("ctrl is not found")?;
+  let window_data = state.get_window_data(&label).context("failure to get window data")?;
+
+  if let Some((width, height)) = args.size {
+    window.set_size(PhysicalSize::new(width, height))?;
+  }
+  // コントロールはMovedで追従する
+  if let Some((x, y)) = args.position {
+    window.set_position(PhysicalPosition::new(x, y))?;
+  }
+  if args.pin {
+    pin::command::set_pin(ctrl.clone(), state.clone(), true).map_err(anyhow::Error::msg)?;
+  }
+  if let Some(alpha) = args.alpha() {
+    transparent::command::set_transparent(ctrl.clone(), state.clone(), alpha).map_err(anyhow::Error::msg)?;
+  }
+  if let Some(zoom) = args.zoom {
+    let diff = zoom as i32 - window_data.zoom.load(Ordering::Acquire) as i32;
+    ctrl::set_zoom(&window, state.clone(), diff)?;
+  }
+  if args.mobile {
+    user_agent::command::set_user_agent(ctrl, state.clone(), true).map_err(anyhow::Error::msg)?;
+  }
+  state.emit_windows(app);
+
+  Ok(label)
+}
+
+/// 許可リストにないリンクはメインウィンドウで確認してから開く
+fn confirm_deep_link(app: &AppHandle, args: OpenArgs) -> anyhow::Result<()> {
+  let main_window = app.get_webview_window(MAIN_LABEL).context("main window is not found")?;
+  window_focus(&main_window)?;
+  app
+    .dialog()
+    .message(format!("A link wants to open {} as an overlay.", args.url))
+    .title("Open link")
+    .kind(MessageDialogKind::Warning)
+    .buttons(MessageDialogButtons::OkCancelCustom("Open".into(), "Cancel".into()))
+    .parent(&main_window)
+    .show({
+      let app = app.clone();
+      move |allowed| {
+        if !allowed {
+          return;
+        }
+        if let Err(e) = open(&app, args) {
+          println!("failed to open the link: {}", e);
+        }
+      }
+    });
+
+  Ok(())
+}
+
+/// listで表示した番号かlabel
+fn find_overlay(state: &AppState, id: &str) -> anyhow::Result<String> {
+  let windows = state.get_windows();
+  let found = match id.parse::<usize>() {
+    Ok(i) => i.checked_sub(1).and_then(|i| windows.get(i)),
+    Err(_) => windows.iter().find(|v| &*v.label == id),
+  };
+
+  found
+    .map(|v| v.label.to_string())
+    .with_context(|| format!("overlay {} is not found", id))
+}
+
+pub mod command {
+  use configu::Configurable;
+  use specta::specta;
+  use tauri::{command, State};
+
+  use crate::util::{AppState, ErrToString};
+
+  use super::deep_link::DeepLinkSettings;
+
+  #[command]
+  #[specta]
+  pub fn get_deep_link_settings(state: State<'_, AppState>) -> Result<DeepLinkSettings, String> {
+    Ok(state.config.read().unwrap().deep_link.clone())
+  }
+
+  #[command]
+  #[specta]
+  pub fn set_deep_link_settings(state: State<'_, AppState>, settings: DeepLinkSettings) -> Result<(), String> {
+    state.config.write().unwrap().deep_link = settings;
+    state.config.save().err_to_string()?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,185 @@
+use anyhow::{bail, ensure, Context};
+use url::Url;
+
+use crate::{
+  remote::route::{MAX_ZOOM, MIN_ZOOM},
+  util::normalize_url,
+};
+
+use super::deep_link::{parse_deep_link, SCHEME};
+
+pub const USAGE: &str = "\
+Usage:
+  relais                      start or show the running instance
+  relais open <url> [options] open an overlay
+  relais list                 list open overlays with their numbers and labels
+  relais close <id>           close an overlay by its number or label
+  relais toggle               show or hide all overlays
+  relais <relais://link>      open an overlay from a link
+
+Options for open:
+  --pin                 keep the overlay on top
+  --opacity <0-100>     opacity in percent
+  --zoom <20-500>       zoom in percent
+  --mobile              use the mobile user agent
+  --at <x>,<y>          position in physical pixels
+  --size <w>x<h>        size in physical pixels
+  --profile <name>      use a profile instead of the shared data directory
+
+  -h, --help            show this message
+  -V, --version         show the version";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+  /// 引数なし
+  Run,
+  Open(OpenArgs),
+  /// `relais://`のリンクから開く、許可リストにないurlは確認する
+  DeepLink(OpenArgs),
+  List,
+  /// listで表示した番号かlabel
+  Close(String),
+  Toggle,
+  Help,
+  Version,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenArgs {
+  pub url: Url,
+  pub profile: Option<String>,
+  pub pin: bool,
+  /// %
+  pub opacity: Option<u8>,
+  /// %
+  pub zoom: Option<u32>,
+  pub mobile: bool,
+  pub position: Option<(i32, i32)>,
+  pub size: Option<(u32, u32)>,
+}
+
+impl OpenArgs {
+  pub fn new(url: Url) -> Self {
+    Self {
+      url,
+      profile: None,
+      pin: false,
+      opacity: None,
+      zoom: None,
+      mobile: false,
+      position: None,
+      size: None,
+    }
+  }
+
+  /// opacity(%)をウィンドウのalphaにする
+  pub fn alpha(&self) -> Option<u8> {
+    self.opacity.map(|v| (v as u32 * 255 / 100) as u8)
+  }
+}
+
+impl CliCommand {
+  /// 起動中のインスタンスがなければ実行できないか
+  pub fn needs_instance(&self) -> bool {
+    matches!(self, Self::List | Self::Close(_) | Self::Toggle)
+  }
+}
+
+/// プログラム名を除いた引数を解釈する
+/// 値は`--zoom 120`と`--zoom=120`のどちらでもよい
+pub fn parse_args<I, S>(args: I) -> anyhow::Result<CliCommand>
+where
+  I: IntoIterator<Item = S>,
+  S: AsRef<str>,
+{
+  let mut args = args.into_iter().map(|v| v.as_ref().to_string());
+  let Some(command) = args.next() else {
+    return Ok(CliCommand::Run);
+  };
+
+  let command = match command.as_str() {
+    v if is_deep_link(v) => CliCommand::DeepLink(parse_deep_link(v)?),
+    "-h" | "--help" | "help" => CliCommand::Help,
+    "-V" | "--version" => CliCommand::Version,
+    "open" => CliCommand::Open(parse_open(&mut args)?),
+    "list" => CliCommand::List,
+    "close" => CliCommand::Close(args.next().context("close requires an id")?),
+    "toggle" => CliCommand::Toggle,
+    v => bail!("unknown command {}", v),
+  };
+  if let Some(v) = args.next() {
+    bail!("unexpected argument {}", v);
+  }
+
+  Ok(command)
+}
+
+fn is_deep_link(arg: &str) -> bool {
+  arg.split_once(':').is_some_and(|v| v.0.eq_ignore_ascii_case(SCHEME))
+}
+
+/// `<url> [options]`
+fn parse_open(mut args: impl Iterator<Item = String>) -> anyhow::Result<OpenArgs> {
+  let url = args.next().context("open requires a url")?;
+  ensure!(!url.starts_with("--"), "open requires a url before {}", url);
+  let mut open = OpenArgs::new(normalize_url(&url)?);
+
+  while let Some(arg) = args.next() {
+    ensure!(arg.starts_with("--"), "unexpected argument {}", arg);
+
+    let (name, inline) = match arg.split_once('=') {
+      Some((name, value)) => (name.to_string(), Some(value.to_string())),
+      None => (arg, None),
+    };
+    let mut value = || {
+      inline
+        .clone()
+        .or_else(|| args.next())
+        .with_context(|| format!("{} requires a value", name))
+    };
+
+    match name.as_str() {
+      "--pin" => open.pin = true,
+      "--mobile" => open.mobile = true,
+      "--opacity" => open.opacity = Some(parse_opacity(&value()?)?),
+      "--zoom" => open.zoom = Some(parse_zoom(&value()?)?),
+      "--at" => open.position = Some(parse_position(&value()?)?),
+      "--size" => open.size = Some(parse_size(&value()?)?),
+      "--profile" => open.profile = Some(value()?),
+      _ => bail!("unknown option {}", name),
+    }
+  }
+
+  Ok(open)
+}
+
+/// %
+pub fn parse_opacity(value: &str) -> anyhow::Result<u8> {
+  value.parse::<u8>().ok().filter(|v| *v <= 100).context("opacity must be 0-100")
+}
+
+/// %
+pub fn parse_zoom(value: &str) -> anyhow::Result<u32> {
+  value
+    .parse::<u32>()
+    .ok()
+    .filter(|v| (MIN_ZOOM..=MAX_ZOOM).contains(v))
+    .with_context(|| format!("zoom must be {}-{}", MIN_ZOOM, MAX_ZOOM))
+}
+
+/// `<x>,<y>`
+pub fn parse_position(value: &str) -> anyhow::Result<(i32, i32)> {
+  parse_pair(value, ',').context("position must be <x>,<y>")
+}
+
+/// `<w>x<h>`
+pub fn parse_size(value: &str) -> anyhow::Result<(u32, u32)> {
+  parse_pair::<u32>(value, 'x')
+    .filter(|v| v.0 > 0 && v.1 > 0)
+    .context("size must be <w>x<h>")
+}
+
+fn parse_pair<T: std::str::FromStr>(value: &str, separator: char) -> Option<(T, T)> {
+  let (a, b) = value.split_once(separator)?;
+  Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+}
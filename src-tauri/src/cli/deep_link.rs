@@ -0,0 +1,61 @@
+use anyhow::{bail, ensure, Context};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use url::Url;
+
+use crate::{util::normalize_url, view::ctrl::navigation::policy::matches_pattern};
+
+use super::args::{parse_opacity, parse_position, parse_size, parse_zoom, OpenArgs};
+
+pub const SCHEME: &str = "relais";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct DeepLinkSettings {
+  /// 確認せずに開くurlのパターン、書き方はナビゲーションの許可リストと同じ
+  #[serde(default)]
+  pub allowlist: Vec<String>,
+}
+
+impl DeepLinkSettings {
+  pub fn is_allowed(&self, url: &Url) -> bool {
+    self.allowlist.iter().any(|v| matches_pattern(v, url))
+  }
+}
+
+/// `relais://open?url=https://example.com&opacity=70&pin=1&mobile=1`
+/// 他にzoom, at=<x>,<y>, size=<w>x<h>, profileを受け付ける
+pub fn parse_deep_link(link: &str) -> anyhow::Result<OpenArgs> {
+  let link = Url::parse(link).context("invalid link")?;
+  ensure!(link.scheme() == SCHEME, "scheme must be {}", SCHEME);
+  // relais://open, relais:open, relais:///openのどれでもよい
+  let action = link.host_str().filter(|v| !v.is_empty()).unwrap_or(link.path().trim_matches('/'));
+  ensure!(action.eq_ignore_ascii_case("open"), "unknown action {}", action);
+
+  let pairs = link.query_pairs().collect::<Vec<_>>();
+  let url = pairs.iter().find(|v| v.0 == "url").context("url is required")?;
+  let mut open = OpenArgs::new(normalize_url(&url.1)?);
+
+  for (key, value) in &pairs {
+    match &**key {
+      "url" => (),
+      "pin" => open.pin = parse_flag(key, value)?,
+      "mobile" => open.mobile = parse_flag(key, value)?,
+      "opacity" => open.opacity = Some(parse_opacity(value)?),
+      "zoom" => open.zoom = Some(parse_zoom(value)?),
+      "at" => open.position = Some(parse_position(value)?),
+      "size" => open.size = Some(parse_size(value)?),
+      "profile" => open.profile = Some(value.to_string()),
+      _ => bail!("unknown parameter {}", key),
+    }
+  }
+
+  Ok(open)
+}
+
+fn parse_flag(key: &str, value: &str) -> anyhow::Result<bool> {
+  match value {
+    "1" | "true" | "" => Ok(true),
+    "0" | "false" => Ok(false),
+    _ => bail!("{} must be 1 or 0", key),
+  }
+}
@@ -0,0 +1,124 @@
+use std::{
+  env, fs,
+  io::{self, BufRead, BufReader, Read, Write},
+  net::{Ipv4Addr, TcpListener, TcpStream},
+  path::{Path, PathBuf},
+  thread,
+  time::Duration,
+};
+
+use anyhow::{bail, ensure, Context};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 最初に起動したインスタンスがこのポートを確保する
+pub const INSTANCE_PORT: u16 = 38121;
+
+/// ブラウザ等からの接続と区別するため最初の行で送る
+const MAGIC: &str = "relais-cli 1";
+const KEY_FILE_NAME: &str = "relais-instance.key";
+const MAX_LINE_LEN: u64 = 64 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum Instance {
+  /// 引数を受け付ける側、keyを知っている接続だけ受け付ける
+  Primary { listener: TcpListener, key: String },
+  /// 起動中のインスタンスに接続した、keyは鍵ファイルから読んだもの
+  Secondary { stream: TcpStream, key: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reply {
+  /// 実行した出力
+  Ok(String),
+  /// 起動中のインスタンスで失敗した
+  Error(String),
+  /// 鍵が違うので実行しなかった、起動した直後なら鍵を書く前に読んだかもしれない
+  Rejected,
+}
+
+/// 鍵ファイルの場所、%TEMP%はユーザーごとなので他のユーザーからは読めない
+pub fn key_path() -> PathBuf {
+  env::temp_dir().join(KEY_FILE_NAME)
+}
+
+/// 127.0.0.1:portをbindできれば最初のインスタンス
+/// bindは同時に起動しても片方しか成功しないのでロックの代わりになる
+/// 最初のインスタンスは毎回新しい鍵をkey_pathに書き、後から起動した側はそれを読んで送る
+pub fn acquire(port: u16, key_path: &Path) -> io::Result<Instance> {
+  match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+    Ok(listener) => {
+      let key = Uuid::new_v4().to_string();
+      fs::write(key_path, &key)?;
+      Ok(Instance::Primary { listener, key })
+    }
+    Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+      let key = fs::read_to_string(key_path)?.trim().to_string();
+      let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+      Ok(Instance::Secondary { stream, key })
+    }
+    Err(e) => Err(e),
+  }
+}
+
+/// 引数を起動中のインスタンスに送り、その返事を返す
+/// Errは応答がなかったとき、ポートを使っているのがrelaisではないかもしれない
+pub fn forward(stream: TcpStream, key: &str, args: &[String]) -> anyhow::Result<Reply> {
+  stream.set_read_timeout(Some(TIMEOUT))?;
+  let mut writer = &stream;
+  writer.write_all(format!("{}\n{}\n{}\n", MAGIC, key, serde_json::to_string(args)?).as_bytes())?;
+
+  let mut line = String::new();
+  BufReader::new(&stream).take(MAX_LINE_LEN).read_line(&mut line)?;
+  serde_json::from_str::<Reply>(&line).context("the running instance did not respond")
+}
+
+/// 別のインスタンスから届いた引数を順にhandlerへ渡し、結果を送り返す
+/// keyが一致しない接続は実行せずにRejectedを返す
+pub fn serve<F>(listener: TcpListener, key: String, handler: F)
+where
+  F: Fn(Vec<String>) -> anyhow::Result<String> + Send + 'static,
+{
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let Ok(stream) = stream else { continue };
+      if let Err(e) = handle(stream, &key, &handler) {
+        println!("failed to handle forwarded arguments: {}", e);
+      }
+    }
+  });
+}
+
+fn handle(stream: TcpStream, key: &str, handler: &impl Fn(Vec<String>) -> anyhow::Result<String>) -> anyhow::Result<()> {
+  stream.set_read_timeout(Some(TIMEOUT))?;
+  let mut reader = BufReader::new(&stream);
+
+  let mut magic = String::new();
+  reader.by_ref().take(MAGIC.len() as u64 + 2).read_line(&mut magic)?;
+  ensure!(magic.trim_end() == MAGIC, "unknown client");
+  // 読み残したまま閉じると返事より先に接続が切れるので、引数まで読んでから確かめる
+  let mut client_key = String::new();
+  reader.by_ref().take(MAX_LINE_LEN).read_line(&mut client_key)?;
+  let mut line = String::new();
+  reader.take(MAX_LINE_LEN).read_line(&mut line)?;
+  if client_key.trim_end() != key {
+    write_reply(&stream, &Reply::Rejected)?;
+    bail!("wrong key");
+  }
+  let args = serde_json::from_str::<Vec<String>>(&line)?;
+
+  let reply = match handler(args) {
+    Ok(output) => Reply::Ok(output),
+    Err(e) => Reply::Error(e.to_string()),
+  };
+
+  write_reply(&stream, &reply)
+}
+
+fn write_reply(mut stream: &TcpStream, reply: &Reply) -> anyhow::Result<()> {
+  writeln!(stream, "{}", serde_json::to_string(reply)?)?;
+
+  Ok(())
+}
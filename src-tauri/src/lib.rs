@@ -3,22 +3,16 @@
 
 use configu::Configurable;
 use std::{env, panic, sync::Arc};
-use tauri::{
-  generate_context,
-  image::Image,
-  menu::{MenuBuilder, MenuItem},
-  tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-  App, Builder, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent,
-};
-use tauri_plugin_global_shortcut::ShortcutState;
+use tauri::{generate_context, App, Builder, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+use tauri_plugin_global_shortcut::{Shortcut, ShortcutState};
 use tauri_specta::{collect_commands, collect_events};
-use util::{exit_0, AppState, Conf, SerDeAppState, SerDeWindowData};
-use view::{
-  event::{UpdateState, UpdateWindows},
-  util::window_focus,
-};
+use util::{AppState, Conf, SerDeAppState, SerDeWindowData};
+use view::event::{UpdateState, UpdateWindows};
 
+pub mod cli;
 pub mod command;
+pub mod remote;
+pub mod tray;
 pub mod util;
 pub mod view;
 
@@ -40,36 +34,120 @@ hook!
   }));
   //
 
+  // command line
+  let args = env::args().skip(1).collect::<Vec<_>>();
+  let Some((command, listener)) = cli::init(&args) else {
+    return;
+  };
+  //
+
   // specta
   let specta = tauri_specta::Builder::new()
     .commands(collect_commands![
+      cli::command::get_deep_link_settings,
+      cli::command::set_deep_link_settings,
       command::exit,
       command::get_config,
       command::get_state,
       command::get_windows,
       command::log,
+      remote::command::get_remote,
+      remote::command::set_remote,
+      view::closed::command::clear_closed_windows,
+      view::closed::command::list_closed_windows,
+      view::closed::command::reopen_closed,
       view::command::view_create,
       view::command::window_focus,
       view::command::window_hide,
+      view::cookie::command::export_cookies,
+      view::cookie::command::import_cookies,
       view::ctrl::command::get_status,
       view::ctrl::command::sync_windows,
       view::ctrl::command::view_close,
       view::ctrl::command::view_drag,
       view::ctrl::command::view_minimize,
       view::ctrl::command::view_zoom,
+      view::ctrl::blocker::command::get_blocker,
+      view::ctrl::blocker::command::get_blocker_settings,
+      view::ctrl::blocker::command::reload_filter_lists,
+      view::ctrl::blocker::command::set_blocker,
+      view::ctrl::blocker::command::set_blocker_settings,
+      view::ctrl::blocker::command::toggle_blocker,
+      view::ctrl::blocker::command::toggle_blocker_allowlist,
+      view::ctrl::chroma::command::get_chroma,
+      view::ctrl::chroma::command::set_chroma,
+      view::ctrl::chroma::command::toggle_chroma,
+      view::ctrl::custom_css::command::get_custom_css,
+      view::ctrl::custom_css::command::set_custom_css,
+      view::ctrl::custom_script::command::get_custom_script,
+      view::ctrl::custom_script::command::set_custom_script,
+      view::ctrl::emulation::command::get_emulation,
+      view::ctrl::emulation::command::set_emulation,
+      view::ctrl::emulation::command::toggle_emulation,
       view::ctrl::ignore_cursor_events::command::get_ignore_cursor_events,
       view::ctrl::ignore_cursor_events::command::set_ignore_cursor_events,
       view::ctrl::ignore_cursor_events::command::toggle_ignore_cursor_events,
+      view::ctrl::incognito::command::get_incognito,
+      view::ctrl::incognito::command::set_incognito,
+      view::ctrl::incognito::command::toggle_incognito,
+      view::ctrl::navigation::command::get_navigation_policy,
+      view::ctrl::navigation::command::set_navigation_policy,
+      view::ctrl::page_transparent::command::get_page_transparent,
+      view::ctrl::page_transparent::command::set_page_transparent,
+      view::ctrl::page_transparent::command::toggle_page_transparent,
       view::ctrl::pin::command::get_pin,
       view::ctrl::pin::command::set_pin,
       view::ctrl::pin::command::toggle_pin,
+      view::ctrl::refresh::command::get_refresh_interval,
+      view::ctrl::refresh::command::get_watchdog,
+      view::ctrl::refresh::command::set_refresh_interval,
+      view::ctrl::refresh::command::set_watchdog,
+      view::ctrl::refresh::command::toggle_watchdog,
+      view::ctrl::request_rule::command::get_global_request_rules,
+      view::ctrl::request_rule::command::get_request_rules,
+      view::ctrl::request_rule::command::set_global_request_rules,
+      view::ctrl::request_rule::command::set_request_rules,
       view::ctrl::transparent::command::get_transparent,
       view::ctrl::transparent::command::set_transparent,
       view::ctrl::transparent::command::toggle_transparent,
+      view::ctrl::user_agent::command::get_agent_preset,
       view::ctrl::user_agent::command::get_user_agent,
+      view::ctrl::user_agent::command::list_agent_presets,
+      view::ctrl::user_agent::command::list_host_rewrites,
+      view::ctrl::user_agent::command::set_agent_preset,
+      view::ctrl::user_agent::command::set_custom_agent_presets,
+      view::ctrl::user_agent::command::set_custom_host_rewrites,
       view::ctrl::user_agent::command::set_user_agent,
       view::ctrl::user_agent::command::toggle_user_agent,
-      view::extension::command::test,
+      view::extension::command::install_extension,
+      view::extension::command::list_extensions,
+      view::extension::command::set_extension_enabled,
+      view::extension::command::uninstall_extension,
+      view::history::command::clear_history,
+      view::history::command::create_bookmark,
+      view::history::command::delete_bookmark,
+      view::history::command::list_bookmark_tags,
+      view::history::command::open_bookmark,
+      view::history::command::search_bookmarks,
+      view::history::command::search_history,
+      view::history::command::set_bookmark,
+      view::obs::command::import_obs_sources,
+      view::obs::command::pick_obs_scene_collection,
+      view::pack::command::export_pack,
+      view::pack::command::import_pack,
+      view::pack::command::preview_pack,
+      view::permission::command::get_permissions,
+      view::permission::command::reset_permissions,
+      view::permission::command::set_permission,
+      view::profile::command::clear_profile,
+      view::profile::command::create_profile,
+      view::profile::command::delete_profile,
+      view::profile::command::list_profiles,
+      view::proxy::command::get_proxy,
+      view::proxy::command::set_overlay_proxy,
+      view::proxy::command::set_proxy,
+      view::proxy::command::validate_proxy,
+      view::site_data::command::clear_site_data,
     ])
     .constant("CTRL_LABEL_PREFIX", view::util::CTRL_LABEL_PREFIX)
     .constant("WINDOW_LABEL_PREFIX", view::util::WINDOW_LABEL_PREFIX)
@@ -99,6 +177,8 @@ hook!
 
   //
   Builder::default()
+    .plugin(tauri_plugin_dialog::init())
+    .plugin(tauri_plugin_notification::init())
     .invoke_handler(specta.invoke_handler())
     .setup(move |app: &mut App| {
       let handle = app.handle();
@@ -110,6 +190,7 @@ hook!
           .title("Relais")
           .inner_size(400.0, 260.0)
           .min_inner_size(400.0, 260.0)
+          .data_directory(util::ui_data_dir(&state.dir))
           .build()
           .expect("failed to create main window"),
       );
@@ -132,61 +213,50 @@ hook!
       });
       //
 
-      // TODO:TOGGLEですべてのウィンドウをshow/hideする
-      // TODO:クリップボードのurlを開くメニューを追加
       // tray menu
-      {
-        const SHOW: &str = "show";
-        const TOGGLE: &str = "toggle";
-        const QUIT: &str = "quit";
-        let tray_icon = {
-          // Image::from_path("icons/icon.png").unwrap()
-          Image::from_bytes(include_bytes!("../icons/128x128.png")).unwrap()
-        };
-        let tray_menu = MenuBuilder::new(app)
-          .items(&[
-            &MenuItem::with_id(app, SHOW, "Show", true, None::<&str>)?,
-            &MenuItem::with_id(app, TOGGLE, "Toggle Overlay", true, None::<&str>)?,
-            &MenuItem::with_id(app, QUIT, "Quit", true, None::<&str>)?,
-          ])
-          .build()?;
-        let _tray_handle = TrayIconBuilder::with_id("tray")
-          .icon(tray_icon)
-          .menu(&tray_menu)
-          .tooltip("Relais")
-          .on_tray_icon_event({
-            let main_window = Arc::clone(&main_window);
-            move |_tray, e| {
-              if let TrayIconEvent::Click {
-                button: MouseButton::Left, ..
-              } = e
-              {
-                view::util::window_focus(&main_window).unwrap()
-              }
-            }
-          })
-          .on_menu_event({
-            let main_window = Arc::clone(&main_window);
-            move |app, e| match e.id().as_ref() {
-              SHOW => window_focus(&main_window).unwrap(),
-              TOGGLE => (),
-              QUIT => exit_0(app).unwrap(),
-              _ => (),
-            }
-          })
-          .build(app)?;
-      }
+      tray::create_tray(handle)?;
       //
 
       // shortcut
+      let (shortcut_key, clipboard_shortcut_key, reopen_shortcut_key) = {
+        let config = state.config.read().unwrap();
+        (
+          config.shortcut_key.clone(),
+          config.clipboard_shortcut_key.clone(),
+          config.reopen_shortcut_key.clone(),
+        )
+      };
+      let clipboard_shortcut = clipboard_shortcut_key.parse::<Shortcut>().ok();
+      let reopen_shortcut = reopen_shortcut_key.parse::<Shortcut>().ok();
       handle
         .plugin(
           tauri_plugin_global_shortcut::Builder::new()
-            .with_shortcut(state.config.read().unwrap().shortcut_key.as_str())
+            .with_shortcut(shortcut_key.as_str())
+            .unwrap()
+            .with_shortcut(clipboard_shortcut_key.as_str())
+            .unwrap()
+            .with_shortcut(reopen_shortcut_key.as_str())
             .unwrap()
-            .with_handler(move |_app, shortcut, e| {
+            .with_handler(move |app, shortcut, e| {
               if e.state == ShortcutState::Pressed {
                 println!("press:{}", shortcut.into_string());
+                // ウィンドウを作るのでショートカットのコールバックから外す
+                if clipboard_shortcut.as_ref() == Some(shortcut) {
+                  let app = app.clone();
+                  std::thread::spawn(move || {
+                    if let Err(e) = view::clipboard::open_from_clipboard(&app) {
+                      println!("failed to open url from clipboard: {}", e);
+                    }
+                  });
+                }
+                if reopen_shortcut.as_ref() == Some(shortcut) {
+                  let app = app.clone();
+                  std::thread::spawn(move || {
+                    if let Err(e) = view::closed::reopen_closed(&app, None) {
+                      println!("failed to reopen closed overlay: {}", e);
+                    }
+                  });
+                }
               }
             })
             .build(),
@@ -217,8 +287,37 @@ hook!
       }
       //
 
+      // filter lists
+      if let Err(e) = view::ctrl::blocker::load_filter_lists(&state) {
+        println!("failed to load filter lists: {}", e);
+      }
+      //
+
       // restore views from config
-      view::ctrl::view_restore(handle, &state).expect("failed to restore views");
+      if let Err(e) = view::ctrl::view_restore(handle, &state) {
+        println!("failed to restore views: {}", e);
+      }
+      //
+
+      // remote api
+      if let Err(e) = remote::start_remote(handle) {
+        println!("failed to start remote api: {}", e);
+      }
+      //
+
+      // command line
+      if let Err(e) = cli::register_scheme() {
+        println!("failed to register the url scheme: {}", e);
+      }
+      if let Some((listener, key)) = listener {
+        let handle = handle.clone();
+        cli::instance::serve(listener, key, move |args| cli::execute(&handle, cli::args::parse_args(&args)?));
+      }
+      if command != cli::args::CliCommand::Run {
+        if let Err(e) = cli::execute(handle, command) {
+          println!("failed to run the command: {}", e);
+        }
+      }
       //
 
       Ok(())
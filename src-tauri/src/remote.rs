@@ -0,0 +1,175 @@
+pub mod route;
+pub mod server;
+
+use std::{fmt::Display, sync::atomic::Ordering};
+
+use anyhow::Context;
+use configu::Configurable;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow};
+use uuid::Uuid;
+
+use crate::{
+  util::AppState,
+  view::{
+    ctrl::{self, ignore_cursor_events, pin, transparent},
+    util::{set_overlays_visible, to_ctrl_label, to_window},
+  },
+};
+
+use route::{Route, Zoom};
+use server::{ApiError, ApiRequest, ApiResult, RemoteServer};
+
+/// Conf.remoteに合わせてサーバーを起動し直す、無効なら止めるだけ
+/// トークンが空なら作って保存する
+pub fn start_remote(app: &AppHandle) -> anyhow::Result<()> {
+  let state = app.state::<AppState>();
+  let mut remote = state.remote.lock().unwrap();
+  // 同じポートで起動し直すので先に止める
+  if let Some(server) = remote.take() {
+    server.stop();
+  }
+
+  let settings = state.config.read().unwrap().remote.clone();
+  if !settings.enabled {
+    return Ok(());
+  }
+  let token = if settings.token.is_empty() {
+    let token = Uuid::new_v4().simple().to_string();
+    state.config.write().unwrap().remote.token = token.clone();
+    state.config.save()?;
+    token
+  } else {
+    settings.token
+  };
+
+  let server = RemoteServer::start(settings.port, &token, {
+    let app = app.clone();
+    move |request| handle(&app, request)
+  })
+  .with_context(|| format!("failed to listen on 127.0.0.1:{}", settings.port))?;
+  println!("remote api is listening on {}", server.local_addr());
+  *remote = Some(server);
+
+  Ok(())
+}
+
+fn handle(app: &AppHandle, request: &ApiRequest) -> ApiResult {
+  let route = Route::parse(request)?;
+  let is_mutation = route.is_mutation();
+  let value = execute(app, route)?;
+  // フロントエンドとWebSocketのクライアントに変更を伝える
+  if is_mutation {
+    app.state::<AppState>().emit_windows(app);
+  }
+
+  Ok(value)
+}
+
+/// コントロールから呼ぶコマンドをそのまま使う
+fn execute(app: &AppHandle, route: Route) -> ApiResult {
+  let state = app.state::<AppState>();
+  let value = match route {
+    Route::List => json!(state.get_windows()),
+    Route::Create { url, profile } => {
+      if let Some(profile) = &profile {
+        if !state.config.read().unwrap().profiles.contains(profile) {
+          return Err(ApiError::bad_request(format!("profile {} is not found", profile)));
+        }
+      }
+      let label = ctrl::view_create(app, &state, WebviewUrl::External(url), profile, None, false, None).map_err(internal)?;
+      json!(state.get_windows().iter().find(|v| *v.label == *label))
+    }
+    Route::Close(label) => {
+      ctrl::command::view_close(app.clone(), state, ctrl_of(app, &label)?).map_err(internal)?;
+      Value::Null
+    }
+    Route::Pin(label, value) => {
+      let ctrl = ctrl_of(app, &label)?;
+      let value = match value {
+        Some(v) => pin::command::set_pin(ctrl, state, v).map(|_| v),
+        None => pin::command::toggle_pin(ctrl, state),
+      }
+      .map_err(internal)?;
+      json!({ "value": value })
+    }
+    Route::Opacity(label, alpha) => {
+      transparent::command::set_transparent(ctrl_of(app, &label)?, state, alpha).map_err(internal)?;
+      json!({ "value": alpha })
+    }
+    Route::ClickThrough(label, value) => {
+      let ctrl = ctrl_of(app, &label)?;
+      let value = match value {
+        Some(v) => ignore_cursor_events::command::set_ignore_cursor_events(ctrl, state, v).map(|_| v),
+        None => ignore_cursor_events::command::toggle_ignore_cursor_events(ctrl, state),
+      }
+      .map_err(internal)?;
+      json!({ "value": value })
+    }
+    Route::Zoom(label, zoom) => {
+      let window = to_window(&ctrl_of(app, &label)?).map_err(internal)?;
+      let window_data = state.get_window_data(&label).ok_or_else(|| not_found(&label))?;
+      let diff = match zoom {
+        Zoom::Set(v) => v as i32 - window_data.zoom.load(Ordering::Acquire) as i32,
+        Zoom::Diff(v) => v,
+      };
+      ctrl::set_zoom(&window, state, diff).map_err(internal)?;
+      json!({ "value": window_data.zoom.load(Ordering::Acquire) })
+    }
+    Route::Navigate(label, url) => {
+      let window = to_window(&ctrl_of(app, &label)?).map_err(internal)?;
+      window.navigate(url).map_err(internal)?;
+      Value::Null
+    }
+    Route::SetVisible(visible) => {
+      set_overlays_visible(app, visible).map_err(internal)?;
+      Value::Null
+    }
+  };
+
+  Ok(value)
+}
+
+/// オーバーレイのlabelからコントロールのウィンドウを探す
+fn ctrl_of(app: &AppHandle, label: &str) -> Result<WebviewWindow, ApiError> {
+  let state = app.state::<AppState>();
+  state
+    .get_window_data(label)
+    .and_then(|_| app.get_webview_window(&to_ctrl_label(label)))
+    .ok_or_else(|| not_found(label))
+}
+
+fn not_found(label: &str) -> ApiError {
+  ApiError::not_found(format!("overlay {} is not found", label))
+}
+
+fn internal(e: impl Display) -> ApiError {
+  ApiError::new(500, e)
+}
+
+pub mod command {
+  use configu::Configurable;
+  use specta::specta;
+  use tauri::{command, AppHandle, State};
+
+  use crate::util::{AppState, ErrToString};
+
+  use super::{server::RemoteSettings, start_remote};
+
+  #[command]
+  #[specta]
+  pub fn get_remote(state: State<'_, AppState>) -> Result<RemoteSettings, String> {
+    Ok(state.config.read().unwrap().remote.clone())
+  }
+
+  /// 保存してサーバーを起動し直す、tokenを空にすると作り直す
+  #[command]
+  #[specta]
+  pub fn set_remote(app: AppHandle, state: State<'_, AppState>, settings: RemoteSettings) -> Result<RemoteSettings, String> {
+    state.config.write().unwrap().remote = settings;
+    state.config.save().err_to_string()?;
+    start_remote(&app).err_to_string()?;
+
+    Ok(state.config.read().unwrap().remote.clone())
+  }
+}
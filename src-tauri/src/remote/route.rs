@@ -0,0 +1,114 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use url::Url;
+
+use crate::util::normalize_url;
+
+use super::server::{ApiError, ApiRequest};
+
+pub const MIN_ZOOM: u32 = 20;
+pub const MAX_ZOOM: u32 = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Zoom {
+  /// %
+  Set(u32),
+  Diff(i32),
+}
+
+/// リモートAPIで呼べる操作、labelはオーバーレイのlabel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+  /// GET /overlays
+  List,
+  /// POST /overlays {"url", "profile"?}
+  Create { url: Url, profile: Option<String> },
+  /// DELETE /overlays/{label}
+  Close(String),
+  /// POST /overlays/{label}/pin {"value"?}、valueがなければ切り替える
+  Pin(String, Option<bool>),
+  /// POST /overlays/{label}/opacity {"value": 0-255}
+  Opacity(String, u8),
+  /// POST /overlays/{label}/click-through {"value"?}、valueがなければ切り替える
+  ClickThrough(String, Option<bool>),
+  /// POST /overlays/{label}/zoom {"value": %}か{"diff": %}
+  Zoom(String, Zoom),
+  /// POST /overlays/{label}/navigate {"url"}
+  Navigate(String, Url),
+  /// POST /overlays/show, POST /overlays/hide
+  SetVisible(bool),
+}
+
+impl Route {
+  pub fn parse(request: &ApiRequest) -> Result<Self, ApiError> {
+    let segments = request.path.trim_matches('/').split('/').collect::<Vec<_>>();
+    let method = request.method.as_str();
+    let body = &request.body;
+
+    let route = match (method, segments.as_slice()) {
+      ("GET", ["overlays"]) => Self::List,
+      ("POST", ["overlays"]) => Self::Create {
+        url: parse_url(&field::<String>(body, "url")?.ok_or_else(|| missing("url"))?)?,
+        profile: field(body, "profile")?,
+      },
+      ("POST", ["overlays", "show"]) => Self::SetVisible(true),
+      ("POST", ["overlays", "hide"]) => Self::SetVisible(false),
+      ("DELETE", ["overlays", label]) => Self::Close(label.to_string()),
+      ("POST", ["overlays", label, action]) => {
+        let label = label.to_string();
+        match *action {
+          "pin" => Self::Pin(label, field(body, "value")?),
+          "opacity" => Self::Opacity(label, field(body, "value")?.ok_or_else(|| missing("value"))?),
+          "click-through" => Self::ClickThrough(label, field(body, "value")?),
+          "zoom" => Self::Zoom(label, parse_zoom(body)?),
+          "navigate" => Self::Navigate(label, parse_url(&field::<String>(body, "url")?.ok_or_else(|| missing("url"))?)?),
+          _ => return Err(not_found(request)),
+        }
+      }
+      (_, ["overlays"] | ["overlays", _] | ["overlays", _, _]) => {
+        return Err(ApiError::new(405, format!("{} is not allowed for {}", method, request.path)))
+      }
+      _ => return Err(not_found(request)),
+    };
+
+    Ok(route)
+  }
+
+  /// 状態を変える操作か
+  pub fn is_mutation(&self) -> bool {
+    !matches!(self, Self::List)
+  }
+}
+
+fn parse_url(url: &str) -> Result<Url, ApiError> {
+  normalize_url(url).map_err(ApiError::bad_request)
+}
+
+fn parse_zoom(body: &Value) -> Result<Zoom, ApiError> {
+  if let Some(value) = field::<u32>(body, "value")? {
+    if !(MIN_ZOOM..=MAX_ZOOM).contains(&value) {
+      return Err(ApiError::bad_request(format!("zoom must be {}-{}", MIN_ZOOM, MAX_ZOOM)));
+    }
+    return Ok(Zoom::Set(value));
+  }
+
+  field(body, "diff")?.map(Zoom::Diff).ok_or_else(|| missing("value or diff"))
+}
+
+/// 本文がない、またはキーがなければNone
+fn field<T: DeserializeOwned>(body: &Value, key: &str) -> Result<Option<T>, ApiError> {
+  match body.get(key) {
+    None | Some(Value::Null) => Ok(None),
+    Some(value) => serde_json::from_value(value.clone())
+      .map(Some)
+      .map_err(|e| ApiError::bad_request(format!("invalid {}: {}", key, e))),
+  }
+}
+
+fn missing(key: &str) -> ApiError {
+  ApiError::bad_request(format!("{} is required", key))
+}
+
+fn not_found(request: &ApiRequest) -> ApiError {
+  ApiError::not_found(format!("{} is not found", request.path))
+}
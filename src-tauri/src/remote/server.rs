@@ -0,0 +1,434 @@
+use std::{
+  fmt::Display,
+  io::{self, Read, Write},
+  net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+  sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    mpsc, Arc, Mutex,
+  },
+  thread::{self, JoinHandle},
+  time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use specta::Type;
+
+pub const DEFAULT_PORT: u16 = 38120;
+/// WebSocketで変更を受け取るパス
+pub const EVENTS_PATH: &str = "/events";
+
+const MAX_HEAD_LEN: usize = 16 * 1024;
+const MAX_BODY_LEN: usize = 1024 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct RemoteSettings {
+  pub enabled: bool,
+  pub port: u16,
+  /// `Authorization: Bearer <token>`か`?token=<token>`で渡す
+  pub token: String,
+}
+
+impl Default for RemoteSettings {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      port: DEFAULT_PORT,
+      token: String::new(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiRequest {
+  pub method: String,
+  /// クエリを除いたパス
+  pub path: String,
+  /// 本文がなければNull
+  pub body: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+  pub status: u16,
+  pub message: String,
+}
+
+impl ApiError {
+  pub fn new(status: u16, message: impl Display) -> Self {
+    Self {
+      status,
+      message: message.to_string(),
+    }
+  }
+
+  pub fn bad_request(message: impl Display) -> Self {
+    Self::new(400, message)
+  }
+
+  pub fn not_found(message: impl Display) -> Self {
+    Self::new(404, message)
+  }
+}
+
+pub type ApiResult = Result<Value, ApiError>;
+
+type Handler = Box<dyn Fn(&ApiRequest) -> ApiResult + Send + Sync>;
+
+#[derive(Debug)]
+struct Client {
+  id: u64,
+  sender: mpsc::Sender<String>,
+  stream: TcpStream,
+}
+
+struct Context {
+  token: String,
+  handler: Handler,
+  clients: Arc<Mutex<Vec<Client>>>,
+  next_id: AtomicU64,
+}
+
+/// 127.0.0.1のみで待ち受けるJSONのHTTPサーバー
+/// 接続ごとにスレッドを作る、EVENTS_PATHはWebSocketにしてbroadcastした内容を流す
+#[derive(Debug)]
+pub struct RemoteServer {
+  addr: SocketAddr,
+  running: Arc<AtomicBool>,
+  clients: Arc<Mutex<Vec<Client>>>,
+  /// stopで終わるのを待ってからポートを空ける
+  accept: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl RemoteServer {
+  /// port -> 0なら空いているポートを使う
+  pub fn start<F>(port: u16, token: &str, handler: F) -> io::Result<Self>
+  where
+    F: Fn(&ApiRequest) -> ApiResult + Send + Sync + 'static,
+  {
+    if token.is_empty() {
+      return Err(io::Error::new(io::ErrorKind::InvalidInput, "token is empty"));
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let addr = listener.local_addr()?;
+    let running = Arc::new(AtomicBool::new(true));
+    let clients = Arc::new(Mutex::new(Vec::new()));
+    let context = Arc::new(Context {
+      token: token.to_string(),
+      handler: Box::new(handler),
+      clients: Arc::clone(&clients),
+      next_id: AtomicU64::new(0),
+    });
+
+    let accept = thread::spawn({
+      let running = Arc::clone(&running);
+      move || {
+        for stream in listener.incoming() {
+          if !running.load(Ordering::Acquire) {
+            break;
+          }
+          let Ok(stream) = stream else { continue };
+          let context = Arc::clone(&context);
+          // 切断等のエラーはクライアント側の問題なので無視する
+          thread::spawn(move || handle_connection(stream, &context));
+        }
+      }
+    });
+
+    Ok(Self {
+      addr,
+      running,
+      clients,
+      accept: Mutex::new(Some(accept)),
+    })
+  }
+
+  pub fn local_addr(&self) -> SocketAddr {
+    self.addr
+  }
+
+  /// WebSocketのクライアントすべてに`{"event": event, "payload": payload}`を送る
+  pub fn broadcast(&self, event: &str, payload: &impl Serialize) {
+    let message = json!({ "event": event, "payload": payload }).to_string();
+    self.clients.lock().unwrap().retain(|v| v.sender.send(message.clone()).is_ok());
+  }
+
+  /// 戻ったときにはポートが空いているので、すぐに同じポートで起動し直せる
+  pub fn stop(&self) {
+    if self.running.swap(false, Ordering::AcqRel) {
+      // acceptで止まっているスレッドを起こす、つながらなければ起こせないので待たない
+      let woke = TcpStream::connect(self.addr).is_ok();
+      for client in self.clients.lock().unwrap().drain(..) {
+        let _ = client.stream.shutdown(Shutdown::Both);
+      }
+      if let Some(accept) = self.accept.lock().unwrap().take().filter(|_| woke) {
+        let _ = accept.join();
+      }
+    }
+  }
+}
+
+impl Drop for RemoteServer {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+struct Head {
+  method: String,
+  path: String,
+  query: Vec<(String, String)>,
+  headers: Vec<(String, String)>,
+}
+
+impl Head {
+  fn header(&self, name: &str) -> Option<&str> {
+    self.headers.iter().find(|v| v.0.eq_ignore_ascii_case(name)).map(|v| v.1.as_str())
+  }
+
+  fn token(&self) -> Option<&str> {
+    self
+      .header("authorization")
+      .and_then(|v| v.strip_prefix("Bearer "))
+      .or_else(|| self.query.iter().find(|v| v.0 == "token").map(|v| v.1.as_str()))
+  }
+}
+
+fn handle_connection(mut stream: TcpStream, context: &Context) -> io::Result<()> {
+  stream.set_read_timeout(Some(READ_TIMEOUT))?;
+  let (head, body) = match read_request(&mut stream) {
+    Ok(v) => v,
+    Err(e) => return respond(&mut stream, Err(ApiError::bad_request(e))),
+  };
+
+  // DNS rebindingで他のサイトから呼ばれないようにする
+  if head.header("host").is_some_and(|v| !is_local_host(v)) {
+    return respond(&mut stream, Err(ApiError::new(403, "host is not allowed")));
+  }
+  if !head
+    .token()
+    .is_some_and(|v| constant_time_eq(v.as_bytes(), context.token.as_bytes()))
+  {
+    return respond(&mut stream, Err(ApiError::new(401, "invalid token")));
+  }
+
+  if head.path == EVENTS_PATH {
+    return upgrade(stream, &head, context);
+  }
+
+  let body = if body.is_empty() {
+    Value::Null
+  } else {
+    match serde_json::from_slice(&body) {
+      Ok(v) => v,
+      Err(e) => return respond(&mut stream, Err(ApiError::bad_request(e))),
+    }
+  };
+  let request = ApiRequest {
+    method: head.method,
+    path: head.path,
+    body,
+  };
+  let result = (context.handler)(&request);
+
+  respond(&mut stream, result)
+}
+
+fn read_request(stream: &mut TcpStream) -> anyhow::Result<(Head, Vec<u8>)> {
+  let mut buf = Vec::new();
+  let mut chunk = [0; 4096];
+  let head_len = loop {
+    if let Some(i) = buf.windows(4).position(|v| v == b"\r\n\r\n") {
+      break i + 4;
+    }
+    anyhow::ensure!(buf.len() <= MAX_HEAD_LEN, "request header is too large");
+    let len = stream.read(&mut chunk)?;
+    anyhow::ensure!(len > 0, "connection closed");
+    buf.extend_from_slice(&chunk[..len]);
+  };
+
+  let mut headers = [httparse::EMPTY_HEADER; 32];
+  let mut request = httparse::Request::new(&mut headers);
+  request.parse(&buf[..head_len])?;
+  let target = request.path.unwrap_or("/");
+  let (path, query) = target.split_once('?').unwrap_or((target, ""));
+  let head = Head {
+    method: request.method.unwrap_or("GET").to_string(),
+    path: path.to_string(),
+    query: url::form_urlencoded::parse(query.as_bytes()).into_owned().collect(),
+    headers: request
+      .headers
+      .iter()
+      .map(|v| (v.name.to_string(), String::from_utf8_lossy(v.value).into_owned()))
+      .collect(),
+  };
+
+  let len = head.header("content-length").map_or(Ok(0), str::parse::<usize>)?;
+  anyhow::ensure!(len <= MAX_BODY_LEN, "request body is too large");
+  let mut body = buf[head_len..].to_vec();
+  body.truncate(len);
+  if body.len() < len {
+    let start = body.len();
+    body.resize(len, 0);
+    stream.read_exact(&mut body[start..])?;
+  }
+
+  Ok((head, body))
+}
+
+fn respond(stream: &mut TcpStream, result: ApiResult) -> io::Result<()> {
+  let (status, body) = match result {
+    Ok(v) => (200, v),
+    Err(e) => (e.status, json!({ "error": e.message })),
+  };
+  let body = body.to_string();
+  write!(
+    stream,
+    "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+    status,
+    reason(status),
+    body.len(),
+    body
+  )?;
+  stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+  match status {
+    200 => "OK",
+    400 => "Bad Request",
+    401 => "Unauthorized",
+    403 => "Forbidden",
+    404 => "Not Found",
+    405 => "Method Not Allowed",
+    _ => "Internal Server Error",
+  }
+}
+
+fn is_local_host(host: &str) -> bool {
+  let host = match host.rsplit_once(':') {
+    Some((host, port)) if !host.is_empty() && port.bytes().all(|c| c.is_ascii_digit()) => host,
+    _ => host,
+  };
+  matches!(host, "127.0.0.1" | "localhost" | "[::1]")
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// 受信はcloseとpingのみ扱い、送信はbroadcastから受け取ったものを流す
+fn upgrade(mut stream: TcpStream, head: &Head, context: &Context) -> io::Result<()> {
+  let is_websocket = head.header("upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+  let Some(key) = head.header("sec-websocket-key").filter(|_| is_websocket) else {
+    return respond(&mut stream, Err(ApiError::bad_request("websocket upgrade is required")));
+  };
+
+  // 応答を返した時点でbroadcastを受け取れるように先に登録する
+  let id = context.next_id.fetch_add(1, Ordering::AcqRel);
+  let (sender, receiver) = mpsc::channel::<String>();
+  context.clients.lock().unwrap().push(Client {
+    id,
+    sender,
+    stream: stream.try_clone()?,
+  });
+
+  let accept = STANDARD.encode(Sha1::digest(format!("{}{}", key.trim(), WEBSOCKET_GUID)));
+  write!(
+    stream,
+    "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+    accept
+  )?;
+  stream.set_read_timeout(None)?;
+
+  let writer = Arc::new(Mutex::new(stream.try_clone()?));
+  thread::spawn({
+    let writer = Arc::clone(&writer);
+    move || {
+      for message in receiver {
+        if write_frame(&mut *writer.lock().unwrap(), OPCODE_TEXT, message.as_bytes()).is_err() {
+          break;
+        }
+      }
+    }
+  });
+
+  while let Ok((opcode, payload)) = read_frame(&mut stream) {
+    match opcode {
+      OPCODE_CLOSE => {
+        let _ = write_frame(&mut *writer.lock().unwrap(), OPCODE_CLOSE, &payload[..payload.len().min(2)]);
+        break;
+      }
+      OPCODE_PING => write_frame(&mut *writer.lock().unwrap(), OPCODE_PONG, &payload)?,
+      _ => (),
+    }
+  }
+
+  // senderを消すと送信用のスレッドも終わる
+  context.clients.lock().unwrap().retain(|v| v.id != id);
+  let _ = stream.shutdown(Shutdown::Both);
+
+  Ok(())
+}
+
+fn read_frame(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+  let mut head = [0; 2];
+  stream.read_exact(&mut head)?;
+  let opcode = head[0] & 0x0f;
+  let len = match head[1] & 0x7f {
+    126 => {
+      let mut len = [0; 2];
+      stream.read_exact(&mut len)?;
+      u16::from_be_bytes(len) as usize
+    }
+    127 => {
+      let mut len = [0; 8];
+      stream.read_exact(&mut len)?;
+      u64::from_be_bytes(len) as usize
+    }
+    len => len as usize,
+  };
+  if len > MAX_BODY_LEN {
+    return Err(io::Error::new(io::ErrorKind::InvalidData, "frame is too large"));
+  }
+
+  let mut mask = [0; 4];
+  if head[1] & 0x80 != 0 {
+    stream.read_exact(&mut mask)?;
+  }
+  let mut payload = vec![0; len];
+  stream.read_exact(&mut payload)?;
+  for (i, v) in payload.iter_mut().enumerate() {
+    *v ^= mask[i % 4];
+  }
+
+  Ok((opcode, payload))
+}
+
+/// サーバーからのフレームはマスクしない
+fn write_frame(stream: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+  let mut frame = vec![0x80 | opcode];
+  match payload.len() {
+    len @ 0..=125 => frame.push(len as u8),
+    len @ 126..=0xffff => {
+      frame.push(126);
+      frame.extend_from_slice(&(len as u16).to_be_bytes());
+    }
+    len => {
+      frame.push(127);
+      frame.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+  }
+  frame.extend_from_slice(payload);
+  stream.write_all(&frame)?;
+  stream.flush()
+}
@@ -0,0 +1,253 @@
+pub mod menu;
+
+use std::sync::atomic::Ordering;
+
+use anyhow::Context;
+use tauri::{
+  image::Image,
+  menu::{CheckMenuItem, Menu, MenuBuilder, MenuItem, Submenu, SubmenuBuilder},
+  tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
+  AppHandle, Manager, Wry,
+};
+
+use crate::{
+  util::{exit_0, AppState, WindowData},
+  view::{
+    self,
+    closed::reopen_closed,
+    ctrl::{
+      self, ignore_cursor_events, pin, refresh, transparent,
+      user_agent::{self, current_preset},
+    },
+    util::{set_overlays_visible, to_ctrl_label, toggle_overlays_visible, window_focus, window_hide},
+  },
+  MAIN_LABEL,
+};
+
+use menu::{opacity_to_alpha, overlay_title, OverlayAction, TrayAction, OPACITY_PRESETS};
+
+pub const TRAY_ID: &str = "tray";
+
+pub fn create_tray(app: &AppHandle) -> anyhow::Result<()> {
+  let tray_icon = {
+    // Image::from_path("icons/icon.png").unwrap()
+    Image::from_bytes(include_bytes!("../icons/128x128.png"))?
+  };
+  TrayIconBuilder::with_id(TRAY_ID)
+    .icon(tray_icon)
+    .menu(&build_menu(app)?)
+    .tooltip("Relais")
+    .on_tray_icon_event(|tray, e| {
+      if let TrayIconEvent::Click {
+        button: MouseButton::Left, ..
+      } = e
+      {
+        if let Some(main_window) = tray.app_handle().get_webview_window(MAIN_LABEL) {
+          window_focus(&main_window).unwrap();
+        }
+      }
+    })
+    .on_menu_event(|app, e| {
+      let Some(action) = TrayAction::parse(e.id().as_ref()) else {
+        return;
+      };
+      if let Err(e) = execute(app, action) {
+        println!("failed to execute tray menu: {}", e);
+      }
+    })
+    .build(app)?;
+
+  Ok(())
+}
+
+/// オーバーレイの一覧に合わせてメニューを作り直す、トレイがまだなければ何もしない
+pub fn update_tray(app: &AppHandle) -> anyhow::Result<()> {
+  if let Some(tray) = app.tray_by_id(TRAY_ID) {
+    tray.set_menu(Some(build_menu(app)?))?;
+  }
+
+  Ok(())
+}
+
+fn build_menu(app: &AppHandle) -> anyhow::Result<Menu<Wry>> {
+  let state = app.state::<AppState>();
+  let windows = state.windows.lock().unwrap().clone();
+  let submenus = windows
+    .iter()
+    .map(|v| overlay_menu(app, &state, v))
+    .collect::<anyhow::Result<Vec<_>>>()?;
+
+  let mut builder = MenuBuilder::new(app).items(&[
+    &item(app, TrayAction::Show, "Show")?,
+    &item(app, TrayAction::OpenClipboard, "Open URL from Clipboard")?,
+  ]);
+  if !submenus.is_empty() {
+    builder = builder.separator();
+    for submenu in &submenus {
+      builder = builder.item(submenu);
+    }
+    builder = builder.separator().items(&[
+      &item(app, TrayAction::Toggle, "Toggle Overlay")?,
+      &item(app, TrayAction::HideAll, "Hide All")?,
+      &item(app, TrayAction::CloseAll, "Close All")?,
+    ]);
+  }
+  // メニューを作る間はロックしない
+  let closed = state.closed.lock().unwrap().entries().to_vec();
+  if !closed.is_empty() {
+    let mut submenu = SubmenuBuilder::new(app, "Recently Closed");
+    for v in &closed {
+      submenu = submenu.item(&item(
+        app,
+        TrayAction::Reopen(v.id.clone()),
+        &overlay_title(&v.window.title, &v.window.url),
+      )?);
+    }
+    builder = builder.separator().item(&submenu.build()?);
+  }
+  let menu = builder
+    .separator()
+    .items(&[
+      &item(app, TrayAction::ClearCache, "Clear Cache")?,
+      &item(app, TrayAction::Quit, "Quit")?,
+    ])
+    .build()?;
+
+  Ok(menu)
+}
+
+fn overlay_menu(app: &AppHandle, state: &AppState, window_data: &WindowData) -> anyhow::Result<Submenu<Wry>> {
+  let label = &window_data.label;
+  let pin = window_data.pin.load(Ordering::Acquire);
+  let pointer_ignore = window_data.pointer_ignore.load(Ordering::Acquire);
+  let mobile = {
+    let preset_id = window_data.agent_preset.lock().unwrap().clone();
+    current_preset(state, &preset_id).is_some_and(|v| v.mobile)
+  };
+  // 閉じている途中のウィンドウもあるので失敗したら表示中として扱う
+  let hidden = app.get_webview_window(label).is_some_and(|v| !v.is_visible().unwrap_or(true));
+  let check = |action: OverlayAction, text: &str, checked: bool| {
+    CheckMenuItem::with_id(
+      app,
+      TrayAction::Overlay(label.clone(), action).id(),
+      text,
+      true,
+      checked,
+      None::<&str>,
+    )
+  };
+
+  let alpha = window_data.alpha();
+  let mut opacity = SubmenuBuilder::new(app, "Opacity");
+  for preset in OPACITY_PRESETS {
+    opacity = opacity.item(&check(
+      OverlayAction::Opacity(preset),
+      &format!("{}%", preset),
+      opacity_to_alpha(preset) == alpha,
+    )?);
+  }
+
+  let submenu = SubmenuBuilder::new(app, overlay_title(&window_data.title, &window_data.url.to_string()))
+    .items(&[
+      &check(OverlayAction::Pin, "Pin", pin)?,
+      &check(OverlayAction::ClickThrough, "Click-through", pointer_ignore)?,
+      &check(OverlayAction::Mobile, "Mobile", mobile)?,
+      &check(OverlayAction::Hidden, "Hidden", hidden)?,
+    ])
+    .item(&opacity.build()?)
+    .separator()
+    .items(&[
+      &item(app, TrayAction::Overlay(label.clone(), OverlayAction::Reload), "Reload")?,
+      &item(app, TrayAction::Overlay(label.clone(), OverlayAction::Focus), "Focus")?,
+      &item(app, TrayAction::Overlay(label.clone(), OverlayAction::Close), "Close")?,
+    ])
+    .build()?;
+
+  Ok(submenu)
+}
+
+fn item(app: &AppHandle, action: TrayAction, text: &str) -> tauri::Result<MenuItem<Wry>> {
+  MenuItem::with_id(app, action.id(), text, true, None::<&str>)
+}
+
+fn execute(app: &AppHandle, action: TrayAction) -> anyhow::Result<()> {
+  let state = app.state::<AppState>();
+  match action {
+    TrayAction::Show => {
+      if let Some(main_window) = app.get_webview_window(MAIN_LABEL) {
+        window_focus(&main_window)?;
+      }
+    }
+    TrayAction::Toggle => {
+      toggle_overlays_visible(app)?;
+      update_tray(app)?;
+    }
+    TrayAction::HideAll => {
+      set_overlays_visible(app, false)?;
+      update_tray(app)?;
+    }
+    TrayAction::CloseAll => {
+      let labels = state.windows.lock().unwrap().iter().map(|v| v.label.clone()).collect::<Vec<_>>();
+      for label in labels {
+        execute_overlay(app, &label, OverlayAction::Close)?;
+      }
+    }
+    // ウィンドウを作るのでメインスレッドから外す
+    TrayAction::OpenClipboard => {
+      let app = app.clone();
+      std::thread::spawn(move || {
+        if let Err(e) = view::clipboard::open_from_clipboard(&app) {
+          println!("failed to open url from clipboard: {}", e);
+        }
+      });
+    }
+    // webviewのコールバックを待つのでメインスレッドから外す
+    TrayAction::ClearCache => {
+      let app = app.clone();
+      std::thread::spawn(move || view::site_data::clear_cache_all(&app));
+    }
+    TrayAction::Quit => exit_0(app)?,
+    // ウィンドウを作るのでメインスレッドから外す
+    TrayAction::Reopen(id) => {
+      let app = app.clone();
+      std::thread::spawn(move || {
+        if let Err(e) = reopen_closed(&app, Some(&id)) {
+          println!("failed to reopen closed overlay: {}", e);
+        }
+      });
+    }
+    TrayAction::Overlay(label, action) => execute_overlay(app, &label, action)?,
+  }
+
+  Ok(())
+}
+
+/// コントロールから呼ぶコマンドをそのまま使う
+fn execute_overlay(app: &AppHandle, label: &str, action: OverlayAction) -> anyhow::Result<()> {
+  let state = app.state::<AppState>();
+  let window = app.get_webview_window(label).context("window is not found")?;
+  let ctrl = app.get_webview_window(&to_ctrl_label(label)).context("ctrl is not found")?;
+  match action {
+    OverlayAction::Pin => pin::command::toggle_pin(ctrl, state.clone()).map(drop),
+    OverlayAction::ClickThrough => ignore_cursor_events::command::toggle_ignore_cursor_events(ctrl, state.clone()).map(drop),
+    OverlayAction::Mobile => user_agent::command::toggle_user_agent(ctrl, state.clone()).map(drop),
+    OverlayAction::Hidden => {
+      if window.is_visible()? {
+        window_hide(&window)?;
+        window_hide(&ctrl)?;
+      } else {
+        window.show()?;
+        ctrl.show()?;
+      }
+      Ok(())
+    }
+    OverlayAction::Opacity(v) => transparent::command::set_transparent(ctrl, state.clone(), opacity_to_alpha(v)),
+    OverlayAction::Reload => return refresh::reload(&window),
+    OverlayAction::Focus => return window_focus(&window),
+    OverlayAction::Close => ctrl::command::view_close(app.clone(), state.clone(), ctrl),
+  }
+  .map_err(anyhow::Error::msg)?;
+  state.emit_windows(app);
+
+  Ok(())
+}
@@ -0,0 +1,139 @@
+use url::Url;
+
+/// 透明度のプリセット(%)
+pub const OPACITY_PRESETS: [u8; 5] = [100, 80, 60, 40, 20];
+
+const OVERLAY_PREFIX: &str = "overlay/";
+const REOPEN_PREFIX: &str = "reopen/";
+
+/// トレイメニューの項目、idとの変換だけを扱う
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrayAction {
+  Show,
+  Toggle,
+  HideAll,
+  CloseAll,
+  OpenClipboard,
+  ClearCache,
+  Quit,
+  /// 閉じたオーバーレイのid
+  Reopen(String),
+  /// オーバーレイのlabelと操作
+  Overlay(String, OverlayAction),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayAction {
+  Pin,
+  ClickThrough,
+  Mobile,
+  Hidden,
+  /// %
+  Opacity(u8),
+  Reload,
+  Focus,
+  Close,
+}
+
+impl TrayAction {
+  pub fn id(&self) -> String {
+    match self {
+      Self::Show => "show".into(),
+      Self::Toggle => "toggle".into(),
+      Self::HideAll => "hide_all".into(),
+      Self::CloseAll => "close_all".into(),
+      Self::OpenClipboard => "open_clipboard".into(),
+      Self::ClearCache => "clear_cache".into(),
+      Self::Quit => "quit".into(),
+      Self::Reopen(id) => format!("{}{}", REOPEN_PREFIX, id),
+      Self::Overlay(label, action) => format!("{}{}/{}", OVERLAY_PREFIX, label, action.id()),
+    }
+  }
+
+  pub fn parse(id: &str) -> Option<Self> {
+    let action = match id {
+      "show" => Self::Show,
+      "toggle" => Self::Toggle,
+      "hide_all" => Self::HideAll,
+      "close_all" => Self::CloseAll,
+      "open_clipboard" => Self::OpenClipboard,
+      "clear_cache" => Self::ClearCache,
+      "quit" => Self::Quit,
+      _ => {
+        if let Some(id) = id.strip_prefix(REOPEN_PREFIX) {
+          return (!id.is_empty()).then(|| Self::Reopen(id.into()));
+        }
+        let (label, action) = id.strip_prefix(OVERLAY_PREFIX)?.split_once('/')?;
+        if label.is_empty() {
+          return None;
+        }
+        Self::Overlay(label.into(), OverlayAction::parse(action)?)
+      }
+    };
+
+    Some(action)
+  }
+}
+
+impl OverlayAction {
+  fn id(&self) -> String {
+    match self {
+      Self::Pin => "pin".into(),
+      Self::ClickThrough => "click_through".into(),
+      Self::Mobile => "mobile".into(),
+      Self::Hidden => "hidden".into(),
+      Self::Opacity(v) => format!("opacity/{}", v),
+      Self::Reload => "reload".into(),
+      Self::Focus => "focus".into(),
+      Self::Close => "close".into(),
+    }
+  }
+
+  fn parse(id: &str) -> Option<Self> {
+    let action = match id {
+      "pin" => Self::Pin,
+      "click_through" => Self::ClickThrough,
+      "mobile" => Self::Mobile,
+      "hidden" => Self::Hidden,
+      "reload" => Self::Reload,
+      "focus" => Self::Focus,
+      "close" => Self::Close,
+      _ => {
+        let opacity = id.strip_prefix("opacity/")?.parse().ok()?;
+        if !OPACITY_PRESETS.contains(&opacity) {
+          return None;
+        }
+        Self::Opacity(opacity)
+      }
+    };
+
+    Some(action)
+  }
+}
+
+/// %を0-255にする
+pub fn opacity_to_alpha(opacity: u8) -> u8 {
+  (opacity.min(100) as u32 * 255 / 100) as u8
+}
+
+/// サブメニューに表示する名前、タイトルがなければホストにする
+pub fn overlay_title(title: &str, url: &str) -> String {
+  const MAX_CHARS: usize = 40;
+  let title = title.trim();
+  let title = if title.is_empty() || title == "no title" {
+    Url::parse(url)
+      .ok()
+      .and_then(|v| v.host_str().map(ToString::to_string))
+      .unwrap_or_else(|| url.to_string())
+  } else {
+    title.to_string()
+  };
+
+  let title = if title.chars().count() > MAX_CHARS {
+    title.chars().take(MAX_CHARS - 1).collect::<String>() + "…"
+  } else {
+    title
+  };
+  // &はアクセスキーとして扱われる
+  title.replace('&', "&&")
+}
@@ -1,4 +1,14 @@
+mod cookie;
+mod normalize;
 mod panic;
+mod profile;
+mod proxy;
+mod scheduler;
 mod state;
+pub use cookie::*;
+pub use normalize::*;
 pub use panic::*;
+pub use profile::*;
+pub use proxy::*;
+pub use scheduler::*;
 pub use state::*;
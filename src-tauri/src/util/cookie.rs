@@ -0,0 +1,190 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use serde::{de::IntoDeserializer, Deserialize, Deserializer, Serialize};
+use specta::Type;
+
+const NETSCAPE_HEADER: &str = "# Netscape HTTP Cookie File";
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SameSite {
+  #[serde(alias = "none")]
+  NoRestriction,
+  Lax,
+  Strict,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+  pub name: String,
+  #[serde(default)]
+  pub value: String,
+  /// 先頭が`.`ならサブドメインにも送られる
+  pub domain: String,
+  #[serde(default = "default_path")]
+  pub path: String,
+  /// unix時間(秒)、Noneはセッションcookie
+  #[serde(default, alias = "expirationDate")]
+  pub expires: Option<f64>,
+  #[serde(default)]
+  pub secure: bool,
+  #[serde(default)]
+  pub http_only: bool,
+  #[serde(default, deserialize_with = "deserialize_same_site")]
+  pub same_site: Option<SameSite>,
+}
+
+fn default_path() -> String {
+  "/".into()
+}
+
+/// chrome.cookiesの書き出しでは指定なしが"unspecified"になる
+fn deserialize_same_site<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<SameSite>, D::Error> {
+  match Option::<String>::deserialize(deserializer)?.as_deref() {
+    None | Some("unspecified") => Ok(None),
+    Some(v) => SameSite::deserialize(v.into_deserializer()).map(Some),
+  }
+}
+
+impl Cookie {
+  pub fn is_expired(&self, now: f64) -> bool {
+    self.expires.is_some_and(|v| v <= now)
+  }
+
+  /// domainかそのサブドメインのcookieならtrue
+  pub fn matches_domain(&self, domain: &str) -> bool {
+    let cookie_domain = self.domain.trim_start_matches('.').to_ascii_lowercase();
+    let domain = domain.trim().trim_start_matches('.').to_ascii_lowercase();
+
+    cookie_domain == domain || cookie_domain.ends_with(&format!(".{}", domain))
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum CookieFormat {
+  /// curlやブラウザ拡張で使われるcookies.txt
+  Netscape,
+  Json,
+}
+
+impl CookieFormat {
+  /// 拡張子が.jsonならJson、それ以外はNetscape
+  pub fn from_path(path: &Path) -> Self {
+    match path.extension().and_then(|v| v.to_str()) {
+      Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+      _ => Self::Netscape,
+    }
+  }
+
+  pub fn parse(self, text: &str) -> anyhow::Result<Vec<Cookie>> {
+    match self {
+      Self::Netscape => parse_netscape(text),
+      Self::Json => parse_json(text),
+    }
+  }
+
+  pub fn write(self, cookies: &[Cookie]) -> anyhow::Result<String> {
+    match self {
+      Self::Netscape => Ok(to_netscape(cookies)),
+      Self::Json => to_json(cookies),
+    }
+  }
+}
+
+/// domainsが空ならすべて残す
+pub fn filter_cookies(cookies: Vec<Cookie>, domains: &[String]) -> Vec<Cookie> {
+  if domains.is_empty() {
+    return cookies;
+  }
+
+  cookies
+    .into_iter()
+    .filter(|v| domains.iter().any(|d| v.matches_domain(d)))
+    .collect()
+}
+
+pub fn parse_netscape(text: &str) -> anyhow::Result<Vec<Cookie>> {
+  let mut cookies = Vec::new();
+
+  for (i, line) in text.lines().enumerate() {
+    let line = line.trim_end_matches('\r');
+    let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+      Some(v) => (v, true),
+      None => (line, false),
+    };
+    if line.trim().is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let fields = line.split('\t').collect::<Vec<_>>();
+    // 値が空の場合は最後のタブが省略されることがある
+    let [domain, include_subdomains, path, secure, expires, name, value @ ..] = fields.as_slice() else {
+      bail!("line {}: expected 7 tab separated fields", i + 1);
+    };
+    if value.len() > 1 {
+      bail!("line {}: expected 7 tab separated fields", i + 1);
+    }
+
+    let expires = expires
+      .trim()
+      .parse::<f64>()
+      .with_context(|| format!("line {}: invalid expiry {}", i + 1, expires))?;
+    let mut domain = domain.trim().to_string();
+    if parse_bool(include_subdomains).with_context(|| format!("line {}", i + 1))? && !domain.starts_with('.') {
+      domain.insert(0, '.');
+    }
+
+    cookies.push(Cookie {
+      name: name.to_string(),
+      value: value.first().map(|v| v.to_string()).unwrap_or_default(),
+      domain,
+      path: path.to_string(),
+      expires: (expires > 0.0).then_some(expires),
+      secure: parse_bool(secure).with_context(|| format!("line {}", i + 1))?,
+      http_only,
+      same_site: None,
+    });
+  }
+
+  Ok(cookies)
+}
+
+fn parse_bool(value: &str) -> anyhow::Result<bool> {
+  match value.trim() {
+    v if v.eq_ignore_ascii_case("true") => Ok(true),
+    v if v.eq_ignore_ascii_case("false") => Ok(false),
+    v => bail!("expected TRUE or FALSE, found {}", v),
+  }
+}
+
+pub fn to_netscape(cookies: &[Cookie]) -> String {
+  let mut text = format!("{}\n\n", NETSCAPE_HEADER);
+
+  for v in cookies {
+    let bool_str = |v: bool| if v { "TRUE" } else { "FALSE" };
+    text += &format!(
+      "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+      if v.http_only { HTTP_ONLY_PREFIX } else { "" },
+      v.domain,
+      bool_str(v.domain.starts_with('.')),
+      v.path,
+      bool_str(v.secure),
+      v.expires.unwrap_or(0.0) as i64,
+      v.name,
+      v.value,
+    );
+  }
+
+  text
+}
+
+pub fn parse_json(text: &str) -> anyhow::Result<Vec<Cookie>> {
+  Ok(serde_json::from_str(text)?)
+}
+
+pub fn to_json(cookies: &[Cookie]) -> anyhow::Result<String> {
+  Ok(serde_json::to_string_pretty(cookies)?)
+}
@@ -0,0 +1,59 @@
+use anyhow::{bail, Context};
+use url::Url;
+
+/// 入力されたurlを開ける形にする、スキームがなければhttpsにする
+/// http(s)以外は開かない
+pub fn normalize_url(url: &str) -> anyhow::Result<Url> {
+  let url = url.trim();
+  let has_scheme = url
+    .split_once("://")
+    .is_some_and(|v| !v.0.is_empty() && v.0.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')));
+  let url = if has_scheme {
+    url.to_string()
+  } else {
+    String::from("https://") + url
+  };
+  let url = Url::parse(&url).with_context(|| format!("invalid url: {}", url))?;
+  if !matches!(url.scheme(), "http" | "https") {
+    bail!("scheme {} is not allowed", url.scheme());
+  }
+
+  Ok(url)
+}
+
+/// 文章から最初のurlを探す
+/// `http(s)://`か`www.`で始まるものを探し、なければ全体が1語ならnormalize_urlと同じく補完する
+pub fn find_url(text: &str) -> Option<Url> {
+  let words = text.split(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '\'' | '`' | '「' | '」' | '、' | '。'));
+  for word in words {
+    // 小文字にしてもバイト位置は変わらない
+    let lower = word.to_ascii_lowercase();
+    let Some(start) = ["https://", "http://", "www."].iter().filter_map(|v| lower.find(v)).min() else {
+      continue;
+    };
+    if let Ok(url) = normalize_url(trim_url_end(&word[start..])) {
+      return Some(url);
+    }
+  }
+
+  let text = text.trim();
+  if text.contains('.') && !text.contains(char::is_whitespace) {
+    return normalize_url(trim_url_end(text)).ok();
+  }
+
+  None
+}
+
+/// 文末の句読点と対応していない閉じ括弧を取り除く
+fn trim_url_end(url: &str) -> &str {
+  let mut url = url;
+  while let Some(c) = url.chars().last() {
+    let unbalanced = |open: char, close: char| c == close && url.matches(open).count() < url.matches(close).count();
+    if !(matches!(c, '.' | ',' | ';' | ':' | '!' | '?') || unbalanced('(', ')') || unbalanced('[', ']')) {
+      break;
+    }
+    url = &url[..url.len() - c.len_utf8()];
+  }
+
+  url
+}
@@ -0,0 +1,84 @@
+use anyhow::{bail, ensure};
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+/// 設定ファイルと同じディレクトリに作る
+pub const PROFILES_DIR: &str = "profiles";
+/// プロファイルを指定しない場合に使われる共有のデータディレクトリ
+pub const DEFAULT_PROFILE: &str = "default";
+/// メインとコントロールのウィンドウのデータディレクトリ、設定ファイルと同じディレクトリに作る
+pub const UI_DATA_DIR: &str = "ui";
+
+/// 英数字と`-`,`_`のみ、ディレクトリ名にそのまま使う
+pub fn validate_profile_name(name: &str) -> anyhow::Result<()> {
+  ensure!(!name.is_empty() && name.len() <= 64, "profile name must be 1-64 characters");
+  ensure!(
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+    "profile name may only contain A-Z, a-z, 0-9, '-' and '_'"
+  );
+  ensure!(!name.eq_ignore_ascii_case(DEFAULT_PROFILE), "{} is reserved", DEFAULT_PROFILE);
+
+  Ok(())
+}
+
+pub fn profile_dir(root: &Path, name: &str) -> PathBuf {
+  root.join(PROFILES_DIR).join(name)
+}
+
+/// 拡張機能を読み込まないアプリのUIは、拡張機能を有効にしたオーバーレイとデータディレクトリを分ける
+/// 同じデータディレクトリのwebviewは同じ設定で作らないと失敗するため
+pub fn ui_data_dir(root: &Path) -> PathBuf {
+  root.join(UI_DATA_DIR)
+}
+
+/// NTFSは大文字と小文字を区別しないので、名前も区別せずに比べる
+fn find_profile<'a>(profiles: &'a [String], name: &str) -> Option<&'a String> {
+  profiles.iter().find(|v| v.eq_ignore_ascii_case(name))
+}
+
+pub fn create_profile(root: &Path, profiles: &mut Vec<String>, name: &str) -> anyhow::Result<PathBuf> {
+  validate_profile_name(name)?;
+  if find_profile(profiles, name).is_some() {
+    bail!("profile {} already exists", name);
+  }
+
+  let dir = profile_dir(root, name);
+  fs::create_dir_all(&dir)?;
+  profiles.push(name.to_string());
+
+  Ok(dir)
+}
+
+/// 登録していた名前を返す
+pub fn delete_profile(root: &Path, profiles: &mut Vec<String>, name: &str) -> anyhow::Result<String> {
+  validate_profile_name(name)?;
+  let Some(name) = find_profile(profiles, name).cloned() else {
+    bail!("profile {} is not found", name);
+  };
+  profiles.retain(|v| *v != name);
+
+  let dir = profile_dir(root, &name);
+  if dir.exists() {
+    fs::remove_dir_all(dir)?;
+  }
+
+  Ok(name)
+}
+
+/// データディレクトリを空にする(プロファイルは残す)
+pub fn clear_profile(root: &Path, profiles: &[String], name: &str) -> anyhow::Result<()> {
+  validate_profile_name(name)?;
+  let Some(name) = find_profile(profiles, name) else {
+    bail!("profile {} is not found", name);
+  };
+
+  let dir = profile_dir(root, name);
+  if dir.exists() {
+    fs::remove_dir_all(&dir)?;
+  }
+  fs::create_dir_all(dir)?;
+
+  Ok(())
+}
@@ -0,0 +1,188 @@
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use specta::Type;
+use url::Url;
+
+/// additional_browser_argsを指定すると既定の引数が消えるので付け直す
+pub const DEFAULT_BROWSER_ARGS: &str = "--disable-features=msWebOOUI,msPdfOOUI,msSmartScreenProtection";
+pub const PROXY_DATA_DIR: &str = "proxies";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum ProxyScheme {
+  Http,
+  Https,
+  Socks5,
+}
+
+impl ProxyScheme {
+  /// プロキシ自体に使える接続方式
+  fn allowed(self) -> &'static [&'static str] {
+    match self {
+      Self::Http | Self::Https => &["http", "https"],
+      Self::Socks5 => &["socks5"],
+    }
+  }
+
+  fn default_scheme(self) -> &'static str {
+    match self {
+      Self::Http | Self::Https => "http",
+      Self::Socks5 => "socks5",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct ProxyConfig {
+  pub enabled: bool,
+  /// http://のリクエストに使うプロキシ
+  #[serde(default)]
+  pub http: Option<String>,
+  /// https://のリクエストに使うプロキシ
+  #[serde(default)]
+  pub https: Option<String>,
+  /// http,httpsが指定されていない場合に使う
+  #[serde(default)]
+  pub socks5: Option<String>,
+  /// プロキシを通さないホスト(`*.example.com`,`localhost`,`<local>`等)
+  #[serde(default)]
+  pub bypass: Vec<String>,
+}
+
+/// Conf.proxyの中身、プロファイルごとの設定はグローバルの設定より優先される
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct ProxySettings {
+  #[serde(default)]
+  pub global: ProxyConfig,
+  #[serde(default)]
+  pub profiles: BTreeMap<String, ProxyConfig>,
+}
+
+impl ProxySettings {
+  /// profile -> Noneならグローバルの設定、無効ならNone
+  pub fn resolve(&self, profile: Option<&str>) -> Option<&ProxyConfig> {
+    let proxy = profile.and_then(|v| self.profiles.get(v)).unwrap_or(&self.global);
+    proxy.enabled.then_some(proxy)
+  }
+
+  /// overlay -> Someならオーバーレイの設定を優先する、無効なら直接つなぐ
+  pub fn resolve_overlay<'a>(&'a self, profile: Option<&str>, overlay: Option<&'a ProxyConfig>) -> Option<&'a ProxyConfig> {
+    match overlay {
+      Some(proxy) => proxy.enabled.then_some(proxy),
+      None => self.resolve(profile),
+    }
+  }
+}
+
+/// プロファイルのないオーバーレイにプロキシを使うときのデータディレクトリ
+/// 共有のデータディレクトリはメインとコントロールのウィンドウが引数なしで使っているので、引数ごとに分ける
+pub fn proxy_data_dir(root: &Path, browser_args: &str) -> PathBuf {
+  let hash = Sha1::digest(browser_args);
+  let name = hash.iter().take(8).map(|v| format!("{:02x}", v)).collect::<String>();
+  root.join(PROXY_DATA_DIR).join(name)
+}
+
+/// `host:port`のように接続方式が省略されていればschemeの既定のものを補う
+pub fn parse_proxy_url(value: &str, scheme: ProxyScheme) -> anyhow::Result<Url> {
+  let value = value.trim();
+  ensure!(!value.is_empty(), "proxy url is empty");
+  let url = if value.contains("://") {
+    Url::parse(value)
+  } else {
+    Url::parse(&format!("{}://{}", scheme.default_scheme(), value))
+  }
+  .with_context(|| format!("invalid proxy url {}", value))?;
+
+  ensure!(
+    scheme.allowed().contains(&url.scheme()),
+    "{} is not supported for {:?} proxy",
+    url.scheme(),
+    scheme
+  );
+  ensure!(url.host_str().is_some_and(|v| !v.is_empty()), "proxy url has no host");
+  ensure!(url.port_or_known_default().is_some(), "proxy url has no port");
+  // webviewはプロキシの認証情報を引数から受け取れない
+  ensure!(
+    url.username().is_empty() && url.password().is_none(),
+    "credentials in proxy url are not supported"
+  );
+  ensure!(
+    matches!(url.path(), "" | "/") && url.query().is_none() && url.fragment().is_none(),
+    "proxy url must not have a path"
+  );
+
+  Ok(url)
+}
+
+fn proxy_server(url: &Url) -> String {
+  format!(
+    "{}://{}:{}",
+    url.scheme(),
+    url.host_str().unwrap_or_default(),
+    url.port_or_known_default().unwrap_or_default()
+  )
+}
+
+impl ProxyConfig {
+  pub fn validate(&self) -> anyhow::Result<()> {
+    self.proxy_servers()?;
+    for v in &self.bypass {
+      if v.trim().is_empty() || v.contains(|c: char| c.is_whitespace() || c == ';' || c == '"') {
+        bail!("invalid bypass entry {:?}", v);
+      }
+    }
+
+    Ok(())
+  }
+
+  fn proxy_servers(&self) -> anyhow::Result<Vec<(ProxyScheme, Url)>> {
+    let entries = [
+      (ProxyScheme::Http, &self.http),
+      (ProxyScheme::Https, &self.https),
+      (ProxyScheme::Socks5, &self.socks5),
+    ];
+    let servers = entries
+      .into_iter()
+      .filter_map(|(scheme, value)| value.as_deref().filter(|v| !v.trim().is_empty()).map(|v| (scheme, v)))
+      .map(|(scheme, value)| Ok((scheme, parse_proxy_url(value, scheme)?)))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    ensure!(!self.enabled || !servers.is_empty(), "no proxy server is set");
+
+    Ok(servers)
+  }
+
+  /// webviewに渡す`--proxy-server`,`--proxy-bypass-list`
+  pub fn browser_args(&self) -> anyhow::Result<String> {
+    self.validate()?;
+    let servers = self.proxy_servers()?;
+
+    let proxy_server = match servers.as_slice() {
+      [(ProxyScheme::Socks5, url)] => proxy_server(url),
+      servers => servers
+        .iter()
+        .map(|(scheme, url)| {
+          let key = match scheme {
+            ProxyScheme::Http => "http",
+            ProxyScheme::Https => "https",
+            // 他に一致しなかったリクエストに使われる
+            ProxyScheme::Socks5 => "socks",
+          };
+          format!("{}={}", key, proxy_server(url))
+        })
+        .collect::<Vec<_>>()
+        .join(";"),
+    };
+
+    let mut args = format!("--proxy-server=\"{}\"", proxy_server);
+    if !self.bypass.is_empty() {
+      args += &format!(" --proxy-bypass-list=\"{}\"", self.bypass.join(";"));
+    }
+
+    Ok(args)
+  }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+  collections::HashMap,
+  sync::{
+    mpsc::{self, RecvTimeoutError, Sender},
+    Arc, Mutex,
+  },
+  thread,
+  time::{Duration, Instant},
+};
+
+/// ウィンドウごとのタイマーの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Type)]
+pub enum TaskKind {
+  Refresh,
+  Watchdog,
+  BlankCheck,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Type)]
+pub struct TaskStatus {
+  pub kind: TaskKind,
+  pub repeat: bool,
+  pub interval_ms: u32,
+  /// None -> 実行済み
+  pub next_in_ms: Option<u32>,
+}
+
+#[derive(Debug)]
+struct Task {
+  // dropするとスレッドが止まる
+  _cancel: Sender<()>,
+  repeat: bool,
+  interval: Duration,
+  next: Arc<Mutex<Option<Instant>>>,
+}
+
+/// ウィンドウのlabelとTaskKindごとにタイマーを1つだけ持つ
+/// 同じキーで登録し直すと前のタイマーはキャンセルされる
+#[derive(Debug, Default)]
+pub struct Scheduler {
+  tasks: Mutex<HashMap<(String, TaskKind), Task>>,
+}
+
+impl Scheduler {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// intervalごとにfを呼ぶ
+  pub fn repeat<F>(&self, label: &str, kind: TaskKind, interval: Duration, f: F)
+  where
+    F: FnMut() + Send + 'static,
+  {
+    self.spawn(label, kind, interval, true, f);
+  }
+
+  /// delay後に一度だけfを呼ぶ
+  pub fn once<F>(&self, label: &str, kind: TaskKind, delay: Duration, f: F)
+  where
+    F: FnMut() + Send + 'static,
+  {
+    self.spawn(label, kind, delay, false, f);
+  }
+
+  pub fn cancel(&self, label: &str, kind: TaskKind) {
+    self.tasks.lock().unwrap().remove(&(label.to_string(), kind));
+  }
+
+  /// labelのタイマーをすべてキャンセルする
+  pub fn cancel_all(&self, label: &str) {
+    self.tasks.lock().unwrap().retain(|(v, _), _| v != label);
+  }
+
+  pub fn status(&self, label: &str) -> Vec<TaskStatus> {
+    let now = Instant::now();
+    let mut vec: Vec<TaskStatus> = self
+      .tasks
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|((v, _), _)| v == label)
+      .map(|((_, kind), task)| TaskStatus {
+        kind: *kind,
+        repeat: task.repeat,
+        interval_ms: to_ms(task.interval),
+        next_in_ms: task.next.lock().unwrap().map(|next| to_ms(next.saturating_duration_since(now))),
+      })
+      .collect();
+    vec.sort_by_key(|v| v.kind as u8);
+    vec
+  }
+
+  fn spawn<F>(&self, label: &str, kind: TaskKind, interval: Duration, repeat: bool, mut f: F)
+  where
+    F: FnMut() + Send + 'static,
+  {
+    let (tx, rx) = mpsc::channel::<()>();
+    let next = Arc::new(Mutex::new(Some(Instant::now() + interval)));
+
+    thread::spawn({
+      let next = Arc::clone(&next);
+      // キャンセルされる(senderがdropされる)までループする
+      move || {
+        while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(interval) {
+          f();
+          let mut next = next.lock().unwrap();
+          if !repeat {
+            *next = None;
+            break;
+          }
+          *next = Some(Instant::now() + interval);
+        }
+      }
+    });
+
+    self.tasks.lock().unwrap().insert(
+      (label.to_string(), kind),
+      Task {
+        _cancel: tx,
+        repeat,
+        interval,
+        next,
+      },
+    );
+  }
+}
+
+/// attempt回目(1から)の再試行までの待ち時間、baseから倍々に増えてmaxで止まる
+pub fn backoff(attempt: u32, base: Duration, max: Duration) -> Duration {
+  let exp = attempt.saturating_sub(1).min(31);
+  base.saturating_mul(1 << exp).min(max)
+}
+
+fn to_ms(duration: Duration) -> u32 {
+  duration.as_millis().try_into().unwrap_or(u32::MAX)
+}
@@ -2,16 +2,40 @@ use configu::{AppConfig, AppConfigBuilder};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use std::{
-  path::Path,
+  path::{Path, PathBuf},
   sync::{
     atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
-    Arc, Mutex,
+    Arc, Mutex, RwLock,
   },
 };
 use tauri::{AppHandle, WebviewUrl};
 use tauri_specta::Event;
 
-use crate::view::event::UpdateState;
+use super::{ProxyConfig, ProxySettings, Scheduler};
+use crate::{
+  cli::deep_link::DeepLinkSettings,
+  remote::server::{RemoteServer, RemoteSettings},
+  tray,
+  view::{
+    closed::ClosedWindowStack,
+    ctrl::{
+      blocker::{
+        filter::{BlockerSettings, FilterEngine},
+        BlockerState,
+      },
+      chroma::DEFAULT_CHROMA_COLOR,
+      navigation::policy::NavigationPolicy,
+      request_rule::rules::RequestRule,
+      user_agent::{
+        mobile_host::HostRewrite,
+        preset::{default_preset, migrate_preset, UserAgentPreset, DESKTOP_PRESET},
+      },
+    },
+    event::UpdateState,
+    history::store::HistoryStore,
+    permission::table::PermissionTable,
+  },
+};
 
 pub const CONFIGFILE_NAME: &str = "relaisrc.toml";
 
@@ -22,6 +46,18 @@ where
 {
   pub config: AppConfig<T>,
   pub(crate) windows: Mutex<WindowDataList>,
+  /// 起動時に開けなかったオーバーレイ、設定ファイルから消さずに次の起動でまた開く
+  pub(crate) unrestored: Mutex<SerDeWindowList>,
+  pub(crate) scheduler: Scheduler,
+  /// Conf.blocker.listsから読み込んだフィルター
+  pub(crate) filters: RwLock<FilterEngine>,
+  /// 設定ファイルのあるディレクトリ
+  pub(crate) dir: PathBuf,
+  /// Conf.remoteが有効な間だけ起動している
+  pub(crate) remote: Mutex<Option<RemoteServer>>,
+  pub(crate) history: Mutex<HistoryStore>,
+  /// closed.tomlの内容、変えたらすぐに保存する
+  pub(crate) closed: Mutex<ClosedWindowStack>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Type)]
@@ -35,11 +71,37 @@ pub struct WindowData {
   pub title: String,
   pub label: String,
   pub url: WebviewUrl,
+  /// None -> 共有のデータディレクトリを使う
+  pub profile: Option<String>,
+  /// 作成時にwebviewへ渡したプロキシの引数
+  pub(crate) proxy_args: Option<String>,
+  /// このオーバーレイだけに使うプロキシ、変えても作り直すまで反映されない
+  pub(crate) proxy: Arc<Mutex<Option<ProxyConfig>>>,
   pub(crate) pointer_ignore: Arc<AtomicBool>,
-  pub(crate) mobile_mode: Arc<AtomicBool>,
+  /// UserAgentPresetのid
+  pub(crate) agent_preset: Arc<Mutex<String>>,
+  /// プリセットの画面サイズを再現するか
+  pub(crate) emulation: Arc<AtomicBool>,
   pub(crate) transparent: Arc<(AtomicBool, AtomicU8)>,
+  pub(crate) page_transparent: Arc<(AtomicBool, AtomicBool)>,
+  pub(crate) chroma: Arc<(AtomicBool, AtomicU32, AtomicBool)>,
   pub(crate) pin: Arc<AtomicBool>,
   pub(crate) zoom: Arc<AtomicU32>,
+  /// 自動更新の間隔(秒)、0なら更新しない
+  pub(crate) refresh: Arc<AtomicU32>,
+  /// (有効か, 連続して失敗した回数)
+  pub(crate) watchdog: Arc<(AtomicBool, AtomicU32)>,
+  pub(crate) navigation: Arc<Mutex<NavigationPolicy>>,
+  pub(crate) request_rules: Arc<Mutex<Vec<RequestRule>>>,
+  pub(crate) blocker: Arc<BlockerState>,
+  /// 履歴に残さない
+  pub(crate) incognito: Arc<AtomicBool>,
+  /// ページを読み込むたびに追加するcss
+  pub(crate) custom_css: Arc<Mutex<String>>,
+  /// ページを読み込むたびに実行するjs
+  pub(crate) custom_script: Arc<Mutex<String>>,
+  /// custom_scriptを許可したオリジン、他のオリジンのページでは実行しない
+  pub(crate) custom_script_origin: Arc<Mutex<Option<String>>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Type)]
@@ -47,11 +109,54 @@ pub struct SerDeWindowData {
   pub title: Box<str>,
   pub label: Box<str>,
   pub url: Box<str>,
+  #[serde(default)]
+  pub profile: Option<Box<str>>,
+  /// Noneならプロファイルかグローバルの設定を使う
+  #[serde(default)]
+  pub proxy: Option<ProxyConfig>,
   pub pointer_ignore: bool,
-  pub mobile_mode: bool,
+  /// 以前のmobile_modeはプリセットのidに変換する
+  #[serde(alias = "mobile_mode", default = "default_preset", deserialize_with = "migrate_preset")]
+  pub agent_preset: Box<str>,
+  #[serde(default)]
+  pub emulation: bool,
   pub transparent: (bool, u8),
+  #[serde(default)]
+  pub page_transparent: (bool, bool),
+  #[serde(default = "default_chroma")]
+  pub chroma: (bool, u32, bool),
   pub pin: bool,
   pub zoom: u32,
+  #[serde(default)]
+  pub refresh: u32,
+  #[serde(default)]
+  pub watchdog: bool,
+  #[serde(default)]
+  pub navigation: NavigationPolicy,
+  #[serde(default)]
+  pub request_rules: Vec<RequestRule>,
+  #[serde(default)]
+  pub blocker: bool,
+  #[serde(default)]
+  pub incognito: bool,
+  #[serde(default)]
+  pub custom_css: String,
+  #[serde(default)]
+  pub custom_script: String,
+  #[serde(default)]
+  pub custom_script_origin: Option<String>,
+}
+
+fn default_chroma() -> (bool, u32, bool) {
+  (false, DEFAULT_CHROMA_COLOR, false)
+}
+
+fn default_clipboard_shortcut_key() -> String {
+  "ctrl+alt+v".into()
+}
+
+fn default_reopen_shortcut_key() -> String {
+  "ctrl+alt+shift+t".into()
 }
 
 pub type WindowDataList = Vec<WindowData>;
@@ -61,8 +166,38 @@ pub type SerDeWindowList = Vec<SerDeWindowData>;
 pub struct Conf {
   pub agent_desktop: String,
   pub agent_mobile: String,
+  /// 組み込み以外のUAのプリセット
+  #[serde(default)]
+  pub agent_presets: Vec<UserAgentPreset>,
+  /// 組み込み以外のモバイル用サイトのホストの対応
+  #[serde(default)]
+  pub host_rewrites: Vec<HostRewrite>,
   pub shortcut_key: String,
+  /// クリップボードのurlを開く
+  #[serde(default = "default_clipboard_shortcut_key")]
+  pub clipboard_shortcut_key: String,
+  /// 最後に閉じたオーバーレイを開き直す
+  #[serde(default = "default_reopen_shortcut_key")]
+  pub reopen_shortcut_key: String,
   pub windows: SerDeWindowList,
+  #[serde(default)]
+  pub permissions: PermissionTable,
+  #[serde(default)]
+  pub profiles: Vec<String>,
+  #[serde(default)]
+  pub proxy: ProxySettings,
+  /// すべてのオーバーレイに適用するリクエストヘッダーのルール
+  #[serde(default)]
+  pub request_rules: Vec<RequestRule>,
+  /// コンテンツブロックのフィルターリストと許可リスト
+  #[serde(default)]
+  pub blocker: BlockerSettings,
+  /// localhostで受け付けるリモート操作のAPI
+  #[serde(default)]
+  pub remote: RemoteSettings,
+  /// `relais://`のリンクを確認せずに開くurl
+  #[serde(default)]
+  pub deep_link: DeepLinkSettings,
 }
 
 impl Default for Conf {
@@ -76,8 +211,19 @@ impl Conf {
     Self {
       agent_desktop: String::new(),
       agent_mobile: String::new(),
+      agent_presets: Vec::new(),
+      host_rewrites: Vec::new(),
       shortcut_key: "ctrl+alt+r".into(),
+      clipboard_shortcut_key: default_clipboard_shortcut_key(),
+      reopen_shortcut_key: default_reopen_shortcut_key(),
       windows: Vec::new(),
+      permissions: PermissionTable::default(),
+      profiles: Vec::new(),
+      proxy: ProxySettings::default(),
+      request_rules: Vec::new(),
+      blocker: BlockerSettings::default(),
+      remote: RemoteSettings::default(),
+      deep_link: DeepLinkSettings::default(),
     }
   }
 }
@@ -91,9 +237,21 @@ where
     P: AsRef<Path>,
     F: Fn(AppConfigBuilder) -> AppConfigBuilder<T>,
   {
+    let dir = config_path.as_ref().parent().map(Path::to_path_buf).unwrap_or_default();
     Ok(Self {
       config: f(AppConfig::<T>::open(config_path)).build()?,
       windows: Mutex::new(Vec::new()),
+      unrestored: Mutex::new(Vec::new()),
+      scheduler: Scheduler::new(),
+      filters: RwLock::new(FilterEngine::default()),
+      history: Mutex::new(HistoryStore::open(&dir)?),
+      // 読めなくても起動はする
+      closed: Mutex::new(ClosedWindowStack::load(&dir).unwrap_or_else(|e| {
+        println!("failed to load closed overlays: {}", e);
+        ClosedWindowStack::default()
+      })),
+      dir,
+      remote: Mutex::new(None),
     })
   }
 
@@ -110,15 +268,22 @@ where
   pub fn remove_window(&self, label: &str) -> anyhow::Result<()> {
     let mut lock = self.windows.lock().unwrap();
     lock.retain(|v| v.label.as_str() != label);
+    self.scheduler.cancel_all(label);
     // dbg!(&lock);
 
     Ok(())
   }
 
   pub fn emit_windows(&self, handle: &AppHandle) {
-    let windows = self.windows.lock().unwrap();
-    let vec = windows.iter().map(|v| v.into()).collect();
+    let vec = self.get_windows();
+    if let Some(server) = self.remote.lock().unwrap().as_ref() {
+      server.broadcast("UpdateState", &vec);
+    }
     UpdateState(vec).emit(handle).unwrap();
+    // windowsのlockを外してから作り直す
+    if let Err(e) = tray::update_tray(handle) {
+      println!("failed to update tray menu: {}", e);
+    }
   }
 
   // TODO:ResultではなくOptionの方がいい
@@ -147,18 +312,48 @@ impl TryFrom<&AppState> for SerDeAppState {
 }
 
 impl WindowData {
-  pub fn new(title: String, label: String, url: WebviewUrl) -> Self {
+  pub fn new(title: String, label: String, url: WebviewUrl, profile: Option<String>, incognito: bool) -> Self {
     Self {
       title,
       label,
       url,
+      profile,
+      proxy_args: None,
+      proxy: Arc::new(Mutex::new(None)),
       pointer_ignore: Arc::new(AtomicBool::new(false)),
-      mobile_mode: Arc::new(AtomicBool::new(false)),
+      agent_preset: Arc::new(Mutex::new(DESKTOP_PRESET.into())),
+      emulation: Arc::new(AtomicBool::new(false)),
       transparent: Arc::new((AtomicBool::new(false), AtomicU8::new(127))),
+      page_transparent: Arc::new((AtomicBool::new(false), AtomicBool::new(true))),
+      chroma: Arc::new((AtomicBool::new(false), AtomicU32::new(DEFAULT_CHROMA_COLOR), AtomicBool::new(false))),
       pin: Arc::new(AtomicBool::new(false)),
       zoom: Arc::new(AtomicU32::new(100)),
+      refresh: Arc::new(AtomicU32::new(0)),
+      watchdog: Arc::new((AtomicBool::new(false), AtomicU32::new(0))),
+      navigation: Arc::new(Mutex::new(NavigationPolicy::default())),
+      request_rules: Arc::new(Mutex::new(Vec::new())),
+      blocker: Arc::new(BlockerState::default()),
+      incognito: Arc::new(AtomicBool::new(incognito)),
+      custom_css: Arc::new(Mutex::new(String::new())),
+      custom_script: Arc::new(Mutex::new(String::new())),
+      custom_script_origin: Arc::new(Mutex::new(None)),
+    }
+  }
+
+  /// 現在のウィンドウ全体のalpha
+  pub fn alpha(&self) -> u8 {
+    if self.transparent.0.load(Ordering::Acquire) {
+      self.transparent.1.load(Ordering::Acquire)
+    } else {
+      255
     }
   }
+
+  /// クロマキーが有効でカラーキーも使う場合のみその色を返す
+  pub fn color_key(&self) -> Option<u32> {
+    let chroma = Arc::clone(&self.chroma);
+    (chroma.0.load(Ordering::Acquire) && chroma.2.load(Ordering::Acquire)).then(|| chroma.1.load(Ordering::Acquire))
+  }
 }
 
 impl From<&WindowData> for SerDeWindowData {
@@ -167,14 +362,38 @@ impl From<&WindowData> for SerDeWindowData {
       title: v.title.as_str().into(),
       label: v.label.as_str().into(),
       url: v.url.to_string().into(),
+      profile: v.profile.as_deref().map(Into::into),
+      proxy: v.proxy.lock().unwrap().clone(),
       pointer_ignore: Arc::clone(&v.pointer_ignore).load(Ordering::Acquire),
-      mobile_mode: Arc::clone(&v.mobile_mode).load(Ordering::Acquire),
+      agent_preset: v.agent_preset.lock().unwrap().as_str().into(),
+      emulation: v.emulation.load(Ordering::Acquire),
       transparent: {
         let arc = Arc::clone(&v.transparent);
         (arc.0.load(Ordering::Acquire), arc.1.load(Ordering::Acquire))
       },
+      page_transparent: {
+        let arc = Arc::clone(&v.page_transparent);
+        (arc.0.load(Ordering::Acquire), arc.1.load(Ordering::Acquire))
+      },
+      chroma: {
+        let arc = Arc::clone(&v.chroma);
+        (
+          arc.0.load(Ordering::Acquire),
+          arc.1.load(Ordering::Acquire),
+          arc.2.load(Ordering::Acquire),
+        )
+      },
       pin: Arc::clone(&v.pin).load(Ordering::Acquire),
       zoom: v.zoom.load(Ordering::Acquire),
+      refresh: v.refresh.load(Ordering::Acquire),
+      watchdog: v.watchdog.0.load(Ordering::Acquire),
+      navigation: v.navigation.lock().unwrap().clone(),
+      request_rules: v.request_rules.lock().unwrap().clone(),
+      blocker: v.blocker.enabled.load(Ordering::Acquire),
+      incognito: v.incognito.load(Ordering::Acquire),
+      custom_css: v.custom_css.lock().unwrap().clone(),
+      custom_script: v.custom_script.lock().unwrap().clone(),
+      custom_script_origin: v.custom_script_origin.lock().unwrap().clone(),
     }
   }
 }
@@ -1,5 +1,15 @@
+pub mod clipboard;
+pub mod closed;
 pub mod command;
+pub mod cookie;
 pub mod ctrl;
 pub mod event;
 pub mod extension;
+pub mod history;
+pub mod obs;
+pub mod pack;
+pub mod permission;
+pub mod profile;
+pub mod proxy;
+pub mod site_data;
 pub mod util;
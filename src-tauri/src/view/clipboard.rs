@@ -0,0 +1,61 @@
+use tauri::{AppHandle, Manager, WebviewUrl};
+use tauri_plugin_notification::NotificationExt;
+use windows::{
+  core::PCWSTR,
+  Win32::{
+    Foundation::{HGLOBAL, HWND},
+    System::{
+      DataExchange::{CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard},
+      Memory::{GlobalLock, GlobalUnlock},
+      Ole::CF_UNICODETEXT,
+    },
+  },
+};
+
+use crate::util::{find_url, AppState};
+
+use super::ctrl;
+
+/// クリップボードのテキスト、テキストでなければNone
+pub fn read_clipboard_text() -> anyhow::Result<Option<String>> {
+  unsafe {
+    if IsClipboardFormatAvailable(CF_UNICODETEXT.0 as u32).is_err() {
+      return Ok(None);
+    }
+    OpenClipboard(HWND::default())?;
+    let text = (|| -> anyhow::Result<Option<String>> {
+      let hglobal = HGLOBAL(GetClipboardData(CF_UNICODETEXT.0 as u32)?.0);
+      let ptr = GlobalLock(hglobal) as *const u16;
+      if ptr.is_null() {
+        return Ok(None);
+      }
+      let text = PCWSTR(ptr).to_string();
+      let _ = GlobalUnlock(hglobal);
+
+      Ok(Some(text?))
+    })();
+    let _ = CloseClipboard();
+
+    text
+  }
+}
+
+/// クリップボードの最初のurlをオーバーレイで開く、urlがなければ通知で伝える
+/// ウィンドウを作るのでメインスレッドから呼ばないこと
+pub fn open_from_clipboard(app: &AppHandle) -> anyhow::Result<()> {
+  let text = read_clipboard_text()?;
+  let Some(url) = text.as_deref().and_then(find_url) else {
+    app
+      .notification()
+      .builder()
+      .title("Open from clipboard")
+      .body("The clipboard does not contain a URL.")
+      .show()?;
+    return Ok(());
+  };
+
+  let state = app.state::<AppState>();
+  ctrl::view_create(app, &state, WebviewUrl::External(url), None, None, false, None)?;
+
+  Ok(())
+}
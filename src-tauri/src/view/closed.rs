@@ -0,0 +1,139 @@
+pub mod stack;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow};
+use uuid::Uuid;
+
+use crate::util::{AppState, SerDeWindowData};
+
+use super::ctrl;
+
+use stack::ClosedStack;
+
+#[derive(Debug, Clone, Deserialize, Serialize, Type)]
+pub struct ClosedWindow {
+  /// 履歴の位置は変わるので開き直すときはこれで指定する
+  #[serde(default = "new_closed_id")]
+  pub id: String,
+  pub window: SerDeWindowData,
+  pub position: (i32, i32),
+  pub size: (u32, u32),
+}
+
+pub type ClosedWindowStack = ClosedStack<ClosedWindow>;
+
+fn new_closed_id() -> String {
+  Uuid::new_v4().to_string()
+}
+
+/// 閉じる直前の設定と位置を残す、remove_windowより先に呼ぶ
+pub fn record_closed(window: &WebviewWindow, state: &AppState) -> anyhow::Result<()> {
+  let window_data = state.get_window_data(window.label()).context("failure to get window data")?;
+  let position = window.outer_position()?;
+  let size = window.inner_size()?;
+
+  let mut stack = state.closed.lock().unwrap();
+  stack.push(ClosedWindow {
+    id: new_closed_id(),
+    window: (&window_data).into(),
+    position: (position.x, position.y),
+    size: (size.width, size.height),
+  });
+  stack.save(&state.dir)?;
+
+  Ok(())
+}
+
+/// id -> Noneなら最後に閉じたもの
+/// ウィンドウを作るのでメインスレッドから呼ばないこと
+/// 作ったオーバーレイのlabelを返す
+pub fn reopen_closed(app: &AppHandle, id: Option<&str>) -> anyhow::Result<String> {
+  let state = app.state::<AppState>();
+  // ウィンドウを作る間はロックしない、閉じるときにメインスレッドで使うので
+  let (index, closed) = {
+    let mut stack = state.closed.lock().unwrap();
+    let index = match id {
+      Some(id) => stack
+        .entries()
+        .iter()
+        .position(|v| v.id == id)
+        .context("closed overlay is not found")?,
+      None => 0,
+    };
+    let closed = stack.take(index).context("closed overlay is not found")?;
+    stack.save(&state.dir)?;
+    (index, closed)
+  };
+
+  let url = WebviewUrl::External(closed.window.url.parse()?);
+  let label = match ctrl::view_create(
+    app,
+    &state,
+    url,
+    closed.window.profile.as_deref().map(Into::into),
+    closed.window.proxy.clone(),
+    closed.window.incognito,
+    Some(closed.window.agent_preset.to_string()),
+  ) {
+    Ok(v) => v,
+    Err(e) => {
+      let mut stack = state.closed.lock().unwrap();
+      stack.restore(index, closed);
+      stack.save(&state.dir)?;
+      return Err(e);
+    }
+  };
+
+  // emulationで縦横比が変わるので位置と大きさを先に戻す
+  let window = app.get_webview_window(&label).context("window is not found")?;
+  window.set_size(PhysicalSize::new(closed.size.0, closed.size.1))?;
+  window.set_position(PhysicalPosition::new(closed.position.0, closed.position.1))?;
+  // オーバーレイはもう開いているので設定の失敗では止めない
+  if let Err(e) = ctrl::apply_window_data(app, &label, &closed.window) {
+    println!("failed to restore overlay settings: {}", e);
+  }
+  state.emit_windows(app);
+
+  Ok(label)
+}
+
+pub mod command {
+  use specta::specta;
+  use tauri::{command, AppHandle, State};
+
+  use crate::{
+    tray,
+    util::{AppState, ErrToString},
+  };
+
+  use super::ClosedWindow;
+
+  /// 新しいものが先頭
+  #[command]
+  #[specta]
+  pub fn list_closed_windows(state: State<'_, AppState>) -> Result<Vec<ClosedWindow>, String> {
+    Ok(state.closed.lock().unwrap().entries().to_vec())
+  }
+
+  /// id -> list_closed_windowsのid、Noneなら最後に閉じたもの
+  #[command]
+  #[specta]
+  pub async fn reopen_closed(app: AppHandle, id: Option<String>) -> Result<String, String> {
+    super::reopen_closed(&app, id.as_deref()).err_to_string()
+  }
+
+  #[command]
+  #[specta]
+  pub fn clear_closed_windows(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    {
+      let mut stack = state.closed.lock().unwrap();
+      stack.clear();
+      stack.save(&state.dir).err_to_string()?;
+    }
+    tray::update_tray(&app).err_to_string()?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,74 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// 設定ファイルと同じディレクトリに作る
+pub const CLOSED_FILE: &str = "closed.toml";
+pub const MAX_CLOSED: usize = 20;
+
+/// 閉じたオーバーレイの履歴、新しいものが先頭
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ClosedStack<T> {
+  #[serde(default = "Vec::new")]
+  entries: Vec<T>,
+}
+
+impl<T> Default for ClosedStack<T> {
+  fn default() -> Self {
+    Self { entries: Vec::new() }
+  }
+}
+
+impl<T> ClosedStack<T>
+where
+  T: Serialize + DeserializeOwned,
+{
+  /// ファイルがなければ空にする
+  pub fn load(root: &Path) -> anyhow::Result<Self> {
+    let path = root.join(CLOSED_FILE);
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+    let text = fs::read_to_string(&path)?;
+
+    toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+  }
+
+  pub fn save(&self, root: &Path) -> anyhow::Result<()> {
+    fs::write(root.join(CLOSED_FILE), toml::to_string(self)?)?;
+
+    Ok(())
+  }
+}
+
+impl<T> ClosedStack<T> {
+  pub fn entries(&self) -> &[T] {
+    &self.entries
+  }
+
+  /// MAX_CLOSEDを超えたら古いものから捨てる
+  pub fn push(&mut self, entry: T) {
+    self.entries.insert(0, entry);
+    self.entries.truncate(MAX_CLOSED);
+  }
+
+  /// 最後に閉じたものを取り出す
+  pub fn pop(&mut self) -> Option<T> {
+    self.take(0)
+  }
+
+  pub fn take(&mut self, index: usize) -> Option<T> {
+    (index < self.entries.len()).then(|| self.entries.remove(index))
+  }
+
+  /// 開き直せなかったものを元の位置に戻す
+  pub fn restore(&mut self, index: usize, entry: T) {
+    self.entries.insert(index.min(self.entries.len()), entry);
+    self.entries.truncate(MAX_CLOSED);
+  }
+
+  pub fn clear(&mut self) {
+    self.entries.clear();
+  }
+}
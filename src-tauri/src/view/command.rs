@@ -1,19 +1,30 @@
 use super::{ctrl, util};
-use crate::util::{AppState, ErrToString};
+use crate::util::{normalize_url, AppState, ErrToString};
 
 use specta::specta;
 use tauri::{command, AppHandle, State, WebviewUrl, WebviewWindow};
 
 #[command]
 #[specta]
-pub async fn view_create(app: AppHandle, state: State<'_, AppState>, url: String) -> Result<(), String> {
-  let url = if !url.starts_with("http") {
-    String::from("https://") + &url
-  } else {
-    url
-  };
-  let parse_url = url::Url::parse(&url).err_to_string()?;
-  ctrl::view_create(&app, &state, WebviewUrl::External(parse_url)).unwrap();
+/// incognito -> trueなら履歴に残さない
+pub async fn view_create(
+  app: AppHandle,
+  state: State<'_, AppState>,
+  url: String,
+  profile: Option<String>,
+  incognito: Option<bool>,
+) -> Result<(), String> {
+  let url = normalize_url(&url).err_to_string()?;
+  ctrl::view_create(
+    &app,
+    &state,
+    WebviewUrl::External(url),
+    profile,
+    None,
+    incognito.unwrap_or(false),
+    None,
+  )
+  .err_to_string()?;
 
   Ok(())
 }
@@ -0,0 +1,209 @@
+use std::{sync::mpsc, time::Duration};
+
+use anyhow::Context;
+use tauri::WebviewWindow;
+use webview2_com::{
+  take_pwstr, GetCookiesCompletedHandler,
+  Microsoft::Web::WebView2::Win32::{
+    ICoreWebView2Cookie, ICoreWebView2_2, COREWEBVIEW2_COOKIE_SAME_SITE_KIND, COREWEBVIEW2_COOKIE_SAME_SITE_KIND_LAX,
+    COREWEBVIEW2_COOKIE_SAME_SITE_KIND_NONE, COREWEBVIEW2_COOKIE_SAME_SITE_KIND_STRICT,
+  },
+};
+use windows::{
+  core::{Interface, HSTRING, PWSTR},
+  Win32::Foundation::BOOL,
+};
+
+use crate::util::{Cookie, SameSite};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+unsafe fn from_webview2(cookie: &ICoreWebView2Cookie) -> windows::core::Result<Cookie> {
+  let text = |f: &dyn Fn(*mut PWSTR) -> windows::core::Result<()>| {
+    let mut value = PWSTR::null();
+    f(&mut value).map(|_| take_pwstr(value))
+  };
+
+  let mut expires = 0.0;
+  cookie.Expires(&mut expires)?;
+  let mut session = BOOL::default();
+  cookie.IsSession(&mut session)?;
+  let mut secure = BOOL::default();
+  cookie.IsSecure(&mut secure)?;
+  let mut http_only = BOOL::default();
+  cookie.IsHttpOnly(&mut http_only)?;
+  let mut same_site = COREWEBVIEW2_COOKIE_SAME_SITE_KIND::default();
+  cookie.SameSite(&mut same_site)?;
+
+  Ok(Cookie {
+    name: text(&|v| cookie.Name(v))?,
+    value: text(&|v| cookie.Value(v))?,
+    domain: text(&|v| cookie.Domain(v))?,
+    path: text(&|v| cookie.Path(v))?,
+    expires: (!session.as_bool()).then_some(expires),
+    secure: secure.as_bool(),
+    http_only: http_only.as_bool(),
+    same_site: match same_site {
+      COREWEBVIEW2_COOKIE_SAME_SITE_KIND_NONE => Some(SameSite::NoRestriction),
+      COREWEBVIEW2_COOKIE_SAME_SITE_KIND_LAX => Some(SameSite::Lax),
+      COREWEBVIEW2_COOKIE_SAME_SITE_KIND_STRICT => Some(SameSite::Strict),
+      _ => None,
+    },
+  })
+}
+
+/// オーバーレイのプロファイルにあるcookieをすべて取得する
+/// 完了するまで待つのでメインスレッドから呼ばないこと
+pub fn get_cookies(window: &WebviewWindow) -> anyhow::Result<Vec<Cookie>> {
+  let (tx, rx) = mpsc::channel::<windows::core::Result<Vec<Cookie>>>();
+
+  window.with_webview(move |webview| {
+    #[cfg(windows)]
+    unsafe {
+      let res = (|| {
+        let controller = webview.controller();
+        let webview = controller.CoreWebView2()?;
+        let manager = webview.cast::<ICoreWebView2_2>()?.CookieManager()?;
+        // uriが空ならすべてのcookie
+        manager.GetCookies(
+          &HSTRING::new(),
+          &GetCookiesCompletedHandler::create(Box::new({
+            let tx = tx.clone();
+            move |res, list| {
+              let cookies = res.and_then(|_| {
+                let Some(list) = list else { return Ok(Vec::new()) };
+                let mut count = 0;
+                list.Count(&mut count)?;
+                (0..count).map(|i| from_webview2(&list.GetValueAtIndex(i)?)).collect()
+              });
+              let _ = tx.send(cookies);
+              Ok(())
+            }
+          })),
+        )
+      })();
+      if let Err(e) = res {
+        let _ = tx.send(Err(e));
+      }
+    }
+  })?;
+
+  Ok(rx.recv_timeout(TIMEOUT).context("timed out while getting cookies")??)
+}
+
+/// 追加か上書きしたcookieの数を返す
+/// 完了するまで待つのでメインスレッドから呼ばないこと
+pub fn set_cookies(window: &WebviewWindow, cookies: Vec<Cookie>) -> anyhow::Result<u32> {
+  let (tx, rx) = mpsc::channel::<windows::core::Result<u32>>();
+
+  window.with_webview(move |webview| {
+    #[cfg(windows)]
+    unsafe {
+      let res = (|| {
+        let controller = webview.controller();
+        let webview = controller.CoreWebView2()?;
+        let manager = webview.cast::<ICoreWebView2_2>()?.CookieManager()?;
+
+        let mut count = 0;
+        for v in cookies {
+          let cookie = manager.CreateCookie(
+            &HSTRING::from(v.name),
+            &HSTRING::from(v.value),
+            &HSTRING::from(v.domain),
+            &HSTRING::from(v.path),
+          )?;
+          if let Some(expires) = v.expires {
+            cookie.SetExpires(expires)?;
+          }
+          cookie.SetIsSecure(BOOL::from(v.secure))?;
+          cookie.SetIsHttpOnly(BOOL::from(v.http_only))?;
+          if let Some(same_site) = v.same_site {
+            cookie.SetSameSite(match same_site {
+              SameSite::NoRestriction => COREWEBVIEW2_COOKIE_SAME_SITE_KIND_NONE,
+              SameSite::Lax => COREWEBVIEW2_COOKIE_SAME_SITE_KIND_LAX,
+              SameSite::Strict => COREWEBVIEW2_COOKIE_SAME_SITE_KIND_STRICT,
+            })?;
+          }
+          manager.AddOrUpdateCookie(&cookie)?;
+          count += 1;
+        }
+
+        Ok(count)
+      })();
+      let _ = tx.send(res);
+    }
+  })?;
+
+  Ok(rx.recv_timeout(TIMEOUT).context("timed out while setting cookies")??)
+}
+
+pub mod command {
+  use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+  };
+
+  use anyhow::Context;
+  use specta::specta;
+  use tauri::{command, AppHandle, Manager};
+  use tauri_plugin_dialog::DialogExt;
+
+  use crate::util::{filter_cookies, CookieFormat, ErrToString};
+
+  const FILTER_NAME: &str = "Cookies";
+  const FILTER_EXTENSIONS: &[&str] = &["txt", "json"];
+
+  /// domainsが空ならすべてのcookieを書き出す
+  /// キャンセルされたらNone、それ以外は書き出した数を返す
+  #[command]
+  #[specta]
+  pub async fn export_cookies(app: AppHandle, label: String, domains: Vec<String>) -> Result<Option<u32>, String> {
+    let window = app.get_webview_window(&label).context("window is not found").err_to_string()?;
+    let Some(path) = app
+      .dialog()
+      .file()
+      .set_parent(&window)
+      .add_filter(FILTER_NAME, FILTER_EXTENSIONS)
+      .set_file_name("cookies.txt")
+      .blocking_save_file()
+    else {
+      return Ok(None);
+    };
+    let path = path.into_path().err_to_string()?;
+
+    let cookies = filter_cookies(super::get_cookies(&window).err_to_string()?, &domains);
+    let text = CookieFormat::from_path(&path).write(&cookies).err_to_string()?;
+    fs::write(path, text).err_to_string()?;
+
+    Ok(Some(cookies.len() as u32))
+  }
+
+  /// 期限切れのcookieは読み飛ばす
+  /// キャンセルされたらNone、それ以外は読み込んだ数を返す
+  #[command]
+  #[specta]
+  pub async fn import_cookies(app: AppHandle, label: String) -> Result<Option<u32>, String> {
+    let window = app.get_webview_window(&label).context("window is not found").err_to_string()?;
+    let Some(path) = app
+      .dialog()
+      .file()
+      .set_parent(&window)
+      .add_filter(FILTER_NAME, FILTER_EXTENSIONS)
+      .blocking_pick_file()
+    else {
+      return Ok(None);
+    };
+    let path = path.into_path().err_to_string()?;
+
+    let text = fs::read_to_string(&path).err_to_string()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).err_to_string()?.as_secs_f64();
+    let cookies = CookieFormat::from_path(&path)
+      .parse(&text)
+      .err_to_string()?
+      .into_iter()
+      .filter(|v| !v.is_expired(now))
+      .collect();
+
+    super::set_cookies(&window, cookies).map(Some).err_to_string()
+  }
+}
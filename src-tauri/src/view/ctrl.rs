@@ -1,12 +1,25 @@
+pub mod blocker;
+pub mod chroma;
+pub mod custom_css;
+pub mod custom_script;
+pub mod emulation;
 pub mod ignore_cursor_events;
+pub mod incognito;
+pub mod navigation;
+pub mod page_transparent;
 pub mod pin;
+pub mod refresh;
+pub mod request_rule;
 pub mod transparent;
 pub mod user_agent;
+pub mod web_resource;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use configu::Configurable;
 use std::sync::{atomic::Ordering, Arc};
-use tauri::{AppHandle, Manager, PhysicalPosition, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+use tauri::{
+  webview::PageLoadEvent, AppHandle, Manager, PhysicalPosition, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent,
+};
 use uuid::Uuid;
 use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2Settings2;
 use windows::{
@@ -20,14 +33,30 @@ use windows::{
   },
 };
 
-use crate::util::{AppState, WindowData};
+use crate::util::{profile_dir, proxy_data_dir, ui_data_dir, AppState, ProxyConfig, SerDeWindowData, WindowData, DEFAULT_BROWSER_ARGS};
 
-use super::util::{to_ctrl_label, window_pos, WINDOW_LABEL_PREFIX};
+use super::{
+  closed, extension, history, permission,
+  util::{to_ctrl_label, window_pos, WINDOW_LABEL_PREFIX},
+};
 
 pub const WINDOW_MIN_INNER_SIZE: (f64, f64) = (360.0, 200.0);
 pub const CTRL_SIZE: (f64, f64) = (40.0, 360.0);
 
-pub fn view_create(app: &AppHandle, state: &State<'_, AppState>, url: WebviewUrl) -> anyhow::Result<()> {
+/// profile -> Noneなら共有のデータディレクトリを使い、プロキシを使う場合はプロキシごとのデータディレクトリを使う
+/// proxy -> Someならプロファイルとグローバルの設定より優先する
+/// incognito -> trueなら最初のページから履歴に残さない
+/// agent_preset -> 最初のページからこのプリセットのUAで読み込む、再読み込みもモバイル用のホストへの移動もしない
+/// 作ったオーバーレイのlabelを返す
+pub fn view_create(
+  app: &AppHandle,
+  state: &State<'_, AppState>,
+  url: WebviewUrl,
+  profile: Option<String>,
+  proxy: Option<ProxyConfig>,
+  incognito: bool,
+  agent_preset: Option<String>,
+) -> anyhow::Result<String> {
   dbg!("create");
   let app = app.clone();
   let skip_taskbar = cfg!(not(debug_assertions));
@@ -35,28 +64,95 @@ pub fn view_create(app: &AppHandle, state: &State<'_, AppState>, url: WebviewUrl
   let title = "no title".to_string();
   let label = WINDOW_LABEL_PREFIX.to_string() + Uuid::new_v4().to_string().as_str();
 
-  let window = WebviewWindowBuilder::new(&app, &label, url.clone())
+  let mut builder = WebviewWindowBuilder::new(&app, &label, url.clone())
     .decorations(false)
     .focused(true)
     .maximizable(false)
     .min_inner_size(WINDOW_MIN_INNER_SIZE.0, WINDOW_MIN_INNER_SIZE.1)
     .minimizable(true)
     .title(&title)
+    .transparent(true)
     .zoom_hotkeys_enabled(true)
-    .build()?;
-
-  let ctrl_window = WebviewWindowBuilder::new(&app, to_ctrl_label(&*label), WebviewUrl::App("/ctrl".into()))
-    .parent(&window)?
-    .inner_size(CTRL_SIZE.0, CTRL_SIZE.1)
-    .decorations(false)
-    .maximizable(false)
-    .minimizable(false)
-    .resizable(false)
-    .skip_taskbar(skip_taskbar)
-    .title("ctrl")
-    .build()?;
+    .browser_extensions_enabled(true)
+    .on_navigation({
+      let app = app.clone();
+      let label = label.clone();
+      move |url| {
+        let allowed = navigation::on_navigation(&app, &label, url);
+        if allowed {
+          blocker::on_navigation(&app, &label, url);
+        }
+        allowed
+      }
+    })
+    .on_page_load(|window, payload| {
+      if let PageLoadEvent::Finished = payload.event() {
+        if let Err(e) = page_loaded(&window) {
+          println!("failed to apply settings on page load: {}", e);
+        }
+      }
+    });
+  if let Some(profile) = &profile {
+    if !state.config.read().unwrap().profiles.contains(profile) {
+      bail!("profile {} is not found", profile);
+    }
+  }
+  let proxy_args = proxy_args(state, profile.as_deref(), proxy.as_ref())?;
+  let data_directory = match (&profile, &proxy_args) {
+    (Some(profile), _) => Some(profile_dir(&state.dir, profile)),
+    (None, Some(args)) => Some(proxy_data_dir(&state.dir, args)),
+    (None, None) => None,
+  };
+  if let Some(dir) = data_directory {
+    builder = builder.data_directory(dir);
+  }
+  if let Some(args) = &proxy_args {
+    builder = builder.additional_browser_args(&format!("{} {}", DEFAULT_BROWSER_ARGS, args));
+  }
+  // 消したプリセットなら既定のまま開く
+  let agent = agent_preset.and_then(|id| match user_agent::preset_user_agent(state, &id) {
+    Ok((_, user_agent)) => Some((id, user_agent)),
+    Err(e) => {
+      println!("failed to find preset: {}", e);
+      None
+    }
+  });
+  if let Some((_, user_agent)) = agent.as_ref().filter(|v| !v.1.is_empty()) {
+    builder = builder.user_agent(user_agent);
+  }
 
-  state.add_window(WindowData::new(title, label, url))?;
+  // 最初のページの読み込みより先に設定を参照できるように、作る前に登録しておく
+  let mut window_data = WindowData::new(title, label.clone(), url, profile, incognito);
+  window_data.proxy_args = proxy_args;
+  *window_data.proxy.lock().unwrap() = proxy;
+  if let Some((id, _)) = &agent {
+    *window_data.agent_preset.lock().unwrap() = id.clone();
+  }
+  state.add_window(window_data)?;
+
+  let windows = (|| -> anyhow::Result<_> {
+    let window = builder.build()?;
+    let ctrl_window = WebviewWindowBuilder::new(&app, to_ctrl_label(&*label), WebviewUrl::App("/ctrl".into()))
+      .parent(&window)?
+      .inner_size(CTRL_SIZE.0, CTRL_SIZE.1)
+      .decorations(false)
+      .maximizable(false)
+      .minimizable(false)
+      .resizable(false)
+      .skip_taskbar(skip_taskbar)
+      .title("ctrl")
+      .data_directory(ui_data_dir(&state.dir))
+      .build()?;
+
+    Ok((window, ctrl_window))
+  })();
+  let (window, ctrl_window) = match windows {
+    Ok(v) => v,
+    Err(e) => {
+      state.remove_window(&label)?;
+      return Err(e);
+    }
+  };
   state.emit_windows(&app);
   sync_windows(state)?;
 
@@ -76,9 +172,17 @@ pub fn view_create(app: &AppHandle, state: &State<'_, AppState>, url: WebviewUrl
       let arc = Arc::clone(&arc);
       move |e| match e {
         WindowEvent::Moved(pos) => arc.1.set_position(window_pos(*pos)).unwrap(),
+        WindowEvent::Resized(size) => {
+          if let Err(e) = emulation::on_resized(&arc.0, *size) {
+            println!("failed to keep aspect ratio: {}", e);
+          }
+        }
         WindowEvent::CloseRequested { .. } => {
           println!("close");
           let state = arc.2.state::<AppState>();
+          if let Err(e) = closed::record_closed(&arc.0, &state) {
+            println!("failed to record closed overlay: {}", e);
+          }
           state.remove_window(arc.0.label()).unwrap();
           state.emit_windows(&arc.2);
           sync_windows(&state).unwrap();
@@ -87,8 +191,23 @@ pub fn view_create(app: &AppHandle, state: &State<'_, AppState>, url: WebviewUrl
       }
     });
 
-    if state.config.read().unwrap().agent_mobile.is_empty() {
-      user_agent(app, window)
+    refresh::attach_watchdog(app, window)?;
+    navigation::attach_new_window_handler(app, window)?;
+    web_resource::attach_web_resource_handler(app, window)?;
+    permission::attach_permission_handler(app, window)?;
+    if let Err(e) = extension::load_extensions(app, window) {
+      println!("failed to load extensions: {}", e);
+    }
+
+    match &agent {
+      // UAは作るときに渡したので、クライアントヒントだけ合わせる
+      Some((id, _)) => {
+        if let Err(e) = user_agent::override_user_agent(window, state, id) {
+          println!("failed to override user agent: {}", e);
+        }
+      }
+      None if state.config.read().unwrap().agent_mobile.is_empty() => user_agent(app, window),
+      None => (),
     }
 
     unsafe {
@@ -100,7 +219,32 @@ pub fn view_create(app: &AppHandle, state: &State<'_, AppState>, url: WebviewUrl
     }
   }
 
-  Ok(())
+  Ok(label)
+}
+
+/// 同じデータディレクトリのwebviewは同じ引数で作らないと失敗するため
+/// 同じプロファイルの開いているオーバーレイと設定が食い違う場合はErrにする
+/// プロファイルがなければデータディレクトリを引数ごとに分けるので食い違わない
+fn proxy_args(state: &AppState, profile: Option<&str>, overlay: Option<&ProxyConfig>) -> anyhow::Result<Option<String>> {
+  let args = match state.config.read().unwrap().proxy.resolve_overlay(profile, overlay) {
+    Some(proxy) => Some(proxy.browser_args()?),
+    None => None,
+  };
+  let Some(profile) = profile else {
+    return Ok(args);
+  };
+
+  let conflict = state
+    .windows
+    .lock()
+    .unwrap()
+    .iter()
+    .any(|v| v.profile.as_deref() == Some(profile) && v.proxy_args != args);
+  if conflict {
+    bail!("proxy settings were changed, close the overlays using the same profile to apply them");
+  }
+
+  Ok(args)
 }
 
 extern "system" fn ctrl_proc(hwnd: HWND, umsg: u32, wparam: WPARAM, lparam: LPARAM, _uidsubclass: usize, _dwrefdata: usize) -> LRESULT {
@@ -120,23 +264,133 @@ extern "system" fn ctrl_proc(hwnd: HWND, umsg: u32, wparam: WPARAM, lparam: LPAR
   }
 }
 
+/// ページの読み込みが終わるたびにウィンドウごとの設定をページへ反映する
+fn page_loaded(window: &WebviewWindow) -> anyhow::Result<()> {
+  let state = window.state::<AppState>();
+  let window_data = state.get_window_data(window.label()).context("failure to get window data")?;
+
+  apply_background(window, &window_data)?;
+  blocker::apply_cosmetic(window, &state, &window_data)?;
+  let css = window_data.custom_css.lock().unwrap().clone();
+  if !css.is_empty() {
+    custom_css::set_custom_css(window, &css)?;
+  }
+  custom_script::run_custom_script(window, &window_data)?;
+  history::record_visit(window)?;
+
+  Ok(())
+}
+
+/// chromaが有効ならそちらを優先してwebviewの背景を設定する
+pub fn apply_background(window: &WebviewWindow, window_data: &WindowData) -> anyhow::Result<()> {
+  let chroma = Arc::clone(&window_data.chroma);
+  if chroma.0.load(Ordering::Acquire) {
+    return chroma::set_chroma(window, chroma.1.load(Ordering::Acquire));
+  }
+
+  let page_transparent = Arc::clone(&window_data.page_transparent);
+  page_transparent::set_page_transparent(
+    window,
+    page_transparent.0.load(Ordering::Acquire),
+    page_transparent.1.load(Ordering::Acquire),
+  )
+}
+
+/// 設定ファイルに残したオーバーレイを設定ごと開き直す
 pub fn view_restore(app: &AppHandle, state: &State<'_, AppState>) -> anyhow::Result<()> {
   let windows = state.config.read().unwrap().windows.clone();
+  // 途中で保存しても消えないように、開く間だけ取り除いて開けなければ戻す
+  *state.unrestored.lock().unwrap() = windows.clone();
+  let mut failed = 0;
   for window in windows {
-    view_create(app, state, WebviewUrl::External(window.url.parse()?))?;
+    state.unrestored.lock().unwrap().remove(failed);
+    // 消したプロファイルなどで開けないものは飛ばして残りを開く
+    let created = window.url.parse::<url::Url>().map_err(anyhow::Error::from).and_then(|url| {
+      let profile = window.profile.as_deref().map(Into::into);
+      view_create(
+        app,
+        state,
+        WebviewUrl::External(url),
+        profile,
+        window.proxy.clone(),
+        window.incognito,
+        Some(window.agent_preset.to_string()),
+      )
+    });
+    let label = match created {
+      Ok(v) => v,
+      Err(e) => {
+        println!("failed to restore {}: {}", window.url, e);
+        state.unrestored.lock().unwrap().insert(failed, window);
+        failed += 1;
+        continue;
+      }
+    };
+
+    // オーバーレイはもう開いているので設定の失敗では止めない
+    if let Err(e) = apply_window_data(app, &label, &window) {
+      println!("failed to restore overlay settings: {}", e);
+    }
   }
+  sync_windows(state)?;
+
+  Ok(())
+}
+
+/// 保存していた設定を作ったばかりのオーバーレイに反映する
+/// プリセットは読み込み直さないようにview_createで渡しておくこと
+/// 先にchromaを設定しないとtransparentでカラーキーが使われない
+pub fn apply_window_data(app: &AppHandle, label: &str, data: &SerDeWindowData) -> anyhow::Result<()> {
+  let state = app.state::<AppState>();
+  let window = app.get_webview_window(label).context("window is not found")?;
+  let ctrl = app.get_webview_window(&to_ctrl_label(label)).context("ctrl is not found")?;
+  let window_data = state.get_window_data(label).context("failure to get window data")?;
+  let msg = anyhow::Error::msg;
+
+  incognito::command::set_incognito(ctrl.clone(), state.clone(), data.incognito).map_err(msg)?;
+  emulation::command::set_emulation(ctrl.clone(), state.clone(), data.emulation).map_err(msg)?;
+  let diff = data.zoom as i32 - window_data.zoom.load(Ordering::Acquire) as i32;
+  set_zoom(&window, state.clone(), diff)?;
+
+  let (chroma, color, color_key) = data.chroma;
+  chroma::command::set_chroma(ctrl.clone(), state.clone(), chroma, color, color_key).map_err(msg)?;
+  let (page_transparent, force_css) = data.page_transparent;
+  page_transparent::command::set_page_transparent(ctrl.clone(), state.clone(), page_transparent, force_css).map_err(msg)?;
+  custom_css::command::set_custom_css(ctrl.clone(), state.clone(), data.custom_css.clone()).map_err(msg)?;
+  // まだページを開いていないので、許可したオリジンは保存していたものを使う
+  *window_data.custom_script.lock().unwrap() = data.custom_script.clone();
+  *window_data.custom_script_origin.lock().unwrap() = data.custom_script_origin.clone();
+  // 無効でも次に有効にしたときのalphaは引き継ぐ
+  let (transparent, alpha) = data.transparent;
+  window_data.transparent.1.store(alpha, Ordering::Release);
+  transparent::command::set_transparent(ctrl.clone(), state.clone(), if transparent { alpha } else { 255 }).map_err(msg)?;
+
+  pin::command::set_pin(ctrl.clone(), state.clone(), data.pin).map_err(msg)?;
+  ignore_cursor_events::command::set_ignore_cursor_events(ctrl.clone(), state.clone(), data.pointer_ignore).map_err(msg)?;
+  refresh::command::set_refresh_interval(app.clone(), ctrl.clone(), state.clone(), data.refresh).map_err(msg)?;
+  refresh::command::set_watchdog(ctrl.clone(), state.clone(), data.watchdog).map_err(msg)?;
+  navigation::command::set_navigation_policy(ctrl.clone(), state.clone(), data.navigation.clone()).map_err(msg)?;
+  request_rule::command::set_request_rules(ctrl.clone(), state.clone(), data.request_rules.clone()).map_err(msg)?;
+  blocker::command::set_blocker(ctrl, state, data.blocker).map_err(msg)?;
 
   Ok(())
 }
 
 pub fn set_zoom(window: &WebviewWindow, state: State<'_, AppState>, diff: i32) -> anyhow::Result<()> {
   let window_data = state.get_window_data(window.label()).context("failure to get window data")?;
-  let zoom = Arc::clone(&window_data.zoom);
-  let val = zoom.load(Ordering::Acquire).saturating_add_signed(diff).clamp(20, 500);
+  let val = window_data.zoom.load(Ordering::Acquire).saturating_add_signed(diff).clamp(20, 500);
+
+  apply_zoom(window, &window_data, val)?;
+  emulation::resize_to_zoom(window, &state, &window_data)?;
+
+  Ok(())
+}
 
+/// zoom(%)をwebviewに設定して保存する
+pub fn apply_zoom(window: &WebviewWindow, window_data: &WindowData, val: u32) -> anyhow::Result<()> {
   let scale = val as f64 / 100.0;
   window.set_zoom(scale)?;
-  zoom.store(val, Ordering::Release);
+  window_data.zoom.store(val, Ordering::Release);
 
   Ok(())
 }
@@ -158,9 +412,11 @@ pub fn user_agent(app: &AppHandle, window: &WebviewWindow) {
     .unwrap();
 }
 
-// TODO:pin,mobile_mode等の設定も保存する
+/// 起動時に開けなかったオーバーレイも残す
 pub(super) fn sync_windows(state: &State<'_, AppState>) -> anyhow::Result<()> {
-  state.config.write().unwrap().windows = state.get_windows();
+  let mut windows = state.get_windows();
+  windows.extend(state.unrestored.lock().unwrap().iter().cloned());
+  state.config.write().unwrap().windows = windows;
   state.config.save()?;
 
   Ok(())
@@ -176,7 +432,7 @@ pub mod command {
     view::util::{self, ctrl_to_window_and_data, to_window},
   };
 
-  use super::set_zoom;
+  use super::{blocker::BlockerStatus, refresh::RefreshStatus, set_zoom, user_agent::current_preset};
 
   #[command]
   #[specta]
@@ -214,7 +470,10 @@ pub mod command {
 
   #[command]
   #[specta]
-  pub fn get_status(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<((bool, u8), bool, bool, bool), String> {
+  pub fn get_status(
+    ctrl: WebviewWindow,
+    state: State<'_, AppState>,
+  ) -> Result<((bool, u8), bool, bool, bool, RefreshStatus, BlockerStatus), String> {
     let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
 
     let status = (
@@ -224,7 +483,12 @@ pub mod command {
       ),
       window_data.pin.load(std::sync::atomic::Ordering::Acquire),
       window_data.pointer_ignore.load(std::sync::atomic::Ordering::Acquire),
-      window_data.mobile_mode.load(std::sync::atomic::Ordering::Acquire),
+      {
+        let preset_id = window_data.agent_preset.lock().unwrap().clone();
+        current_preset(&state, &preset_id).is_some_and(|v| v.mobile)
+      },
+      RefreshStatus::new(&state, &window_data),
+      BlockerStatus::new(&state, &window_data),
     );
 
     Ok(status)
@@ -0,0 +1,273 @@
+pub mod filter;
+
+use std::{
+  fs,
+  sync::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Mutex,
+  },
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{AppHandle, Manager, Url, WebviewUrl, WebviewWindow};
+use webview2_com::Microsoft::Web::WebView2::Win32::{
+  ICoreWebView2Environment, ICoreWebView2WebResourceRequestedEventArgs, COREWEBVIEW2_WEB_RESOURCE_CONTEXT,
+  COREWEBVIEW2_WEB_RESOURCE_CONTEXT_CSP_VIOLATION_REPORT, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT,
+  COREWEBVIEW2_WEB_RESOURCE_CONTEXT_EVENT_SOURCE, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FETCH, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FONT,
+  COREWEBVIEW2_WEB_RESOURCE_CONTEXT_IMAGE, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_MEDIA, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_PING,
+  COREWEBVIEW2_WEB_RESOURCE_CONTEXT_SCRIPT, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_STYLESHEET, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_TEXT_TRACK,
+  COREWEBVIEW2_WEB_RESOURCE_CONTEXT_WEBSOCKET, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_XML_HTTP_REQUEST,
+};
+use windows::{core::HSTRING, Win32::System::Com::IStream};
+
+use crate::{
+  util::{AppState, WindowData},
+  view::util::{inject_style, remove_style},
+};
+
+use filter::{FilterEngine, FilterStats, Request, ResourceType, Verdict};
+
+const STYLE_ID: &str = "blocker";
+
+/// オーバーレイごとのブロックの状態
+#[derive(Debug, Default)]
+pub struct BlockerState {
+  pub enabled: AtomicBool,
+  /// 今のページでブロックしたリクエストの数
+  pub blocked: AtomicU32,
+  /// 今のページで非表示にしたセレクターの数
+  pub hidden: AtomicU32,
+  /// 読み込み中のページ、リクエスト元とサブフレームの区別に使う
+  document: Mutex<Option<Url>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Type)]
+pub struct BlockerStatus {
+  pub enabled: bool,
+  /// 今のサイトが許可リストに入っているか
+  pub allowed: bool,
+  pub blocked: u32,
+  pub hidden: u32,
+}
+
+impl BlockerStatus {
+  pub fn new(state: &AppState, window_data: &WindowData) -> Self {
+    let blocker = &window_data.blocker;
+    Self {
+      enabled: blocker.enabled.load(Ordering::Acquire),
+      allowed: page_url(window_data)
+        .and_then(|v| v.host_str().map(|host| state.config.read().unwrap().blocker.is_allowed(host)))
+        .unwrap_or(false),
+      blocked: blocker.blocked.load(Ordering::Acquire),
+      hidden: blocker.hidden.load(Ordering::Acquire),
+    }
+  }
+}
+
+/// Conf.blocker.listsを読み直してすべてのオーバーレイで使うフィルターを入れ替える
+pub fn load_filter_lists(state: &AppState) -> anyhow::Result<FilterStats> {
+  let lists = state.config.read().unwrap().blocker.lists.clone();
+  let mut engine = FilterEngine::default();
+  for list in lists {
+    let path = state.dir.join(&list);
+    let text = fs::read_to_string(&path).with_context(|| format!("failed to read filter list {}", path.display()))?;
+    engine.add_list(&text);
+  }
+
+  let stats = engine.stats();
+  *state.filters.write().unwrap() = engine;
+
+  Ok(stats)
+}
+
+/// メインフレームの移動が始まったら呼ぶ
+pub fn on_navigation(app: &AppHandle, label: &str, url: &Url) {
+  let state = app.state::<AppState>();
+  if let Some(window_data) = state.get_window_data(label) {
+    let blocker = &window_data.blocker;
+    *blocker.document.lock().unwrap() = Some(url.clone());
+    blocker.blocked.store(0, Ordering::Release);
+    blocker.hidden.store(0, Ordering::Release);
+  }
+}
+
+/// 最初の読み込みはウィンドウの情報を登録する前に始まるので作成時のurlを使う
+fn page_url(window_data: &WindowData) -> Option<Url> {
+  window_data
+    .blocker
+    .document
+    .lock()
+    .unwrap()
+    .clone()
+    .or_else(|| match &window_data.url {
+      WebviewUrl::External(url) => Some(url.clone()),
+      _ => None,
+    })
+}
+
+fn resource_type(context: COREWEBVIEW2_WEB_RESOURCE_CONTEXT, main_frame: bool) -> ResourceType {
+  match context {
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT if main_frame => ResourceType::Document,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_DOCUMENT => ResourceType::Subdocument,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_SCRIPT => ResourceType::Script,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_IMAGE => ResourceType::Image,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_STYLESHEET => ResourceType::Stylesheet,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FONT => ResourceType::Font,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_MEDIA | COREWEBVIEW2_WEB_RESOURCE_CONTEXT_TEXT_TRACK => ResourceType::Media,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_XML_HTTP_REQUEST
+    | COREWEBVIEW2_WEB_RESOURCE_CONTEXT_FETCH
+    | COREWEBVIEW2_WEB_RESOURCE_CONTEXT_EVENT_SOURCE => ResourceType::Xhr,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_WEBSOCKET => ResourceType::Websocket,
+    COREWEBVIEW2_WEB_RESOURCE_CONTEXT_PING | COREWEBVIEW2_WEB_RESOURCE_CONTEXT_CSP_VIOLATION_REPORT => ResourceType::Ping,
+    _ => ResourceType::Other,
+  }
+}
+
+/// フィルターに一致したリクエストに空の403を返す、返したらtrue
+pub(super) unsafe fn block_request(
+  state: &AppState,
+  window_data: &WindowData,
+  args: &ICoreWebView2WebResourceRequestedEventArgs,
+  environment: &ICoreWebView2Environment,
+  url: &str,
+) -> windows::core::Result<bool> {
+  if !window_data.blocker.enabled.load(Ordering::Acquire) {
+    return Ok(false);
+  }
+
+  let mut context = COREWEBVIEW2_WEB_RESOURCE_CONTEXT::default();
+  args.ResourceContext(&mut context)?;
+
+  let page = page_url(window_data);
+  let source_host = page.as_ref().and_then(Url::host_str);
+  if source_host.is_some_and(|v| state.config.read().unwrap().blocker.is_allowed(v)) {
+    return Ok(false);
+  }
+
+  let request = Request {
+    url,
+    source_host,
+    kind: resource_type(context, page.as_ref().is_some_and(|v| v.as_str() == url)),
+  };
+  if state.filters.read().unwrap().check(&request) != Verdict::Block {
+    return Ok(false);
+  }
+  let response = environment.CreateWebResourceResponse(None::<&IStream>, 403, &HSTRING::from("Blocked"), &HSTRING::new())?;
+  args.SetResponse(&response)?;
+  window_data.blocker.blocked.fetch_add(1, Ordering::AcqRel);
+
+  Ok(true)
+}
+
+/// 要素を隠すフィルターを`<style>`で挿入する、無効か許可しているサイトなら取り除く
+pub fn apply_cosmetic(window: &WebviewWindow, state: &AppState, window_data: &WindowData) -> anyhow::Result<()> {
+  let host = page_url(window_data).and_then(|v| v.host_str().map(str::to_string));
+  let css = match host {
+    Some(host) if window_data.blocker.enabled.load(Ordering::Acquire) && !state.config.read().unwrap().blocker.is_allowed(&host) => {
+      state.filters.read().unwrap().cosmetic_css(&host)
+    }
+    _ => String::new(),
+  };
+
+  if css.is_empty() {
+    remove_style(window, STYLE_ID)?;
+  } else {
+    inject_style(window, STYLE_ID, &css)?;
+  }
+  window_data.blocker.hidden.store(css.lines().count() as u32, Ordering::Release);
+
+  Ok(())
+}
+
+/// リクエストのブロックは次の読み込みから反映されるので再読み込みする
+pub fn set_blocker(window: &WebviewWindow, state: &AppState, window_data: &WindowData, value: bool) -> anyhow::Result<()> {
+  window_data.blocker.enabled.store(value, Ordering::Release);
+  apply_cosmetic(window, state, window_data)?;
+  super::refresh::reload(window)?;
+
+  Ok(())
+}
+
+pub mod command {
+  use std::sync::atomic::Ordering;
+
+  use configu::Configurable;
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{
+    util::{AppState, ErrToString},
+    view::{ctrl::refresh::reload, util::ctrl_to_window_and_data},
+  };
+
+  use super::{
+    filter::{BlockerSettings, FilterStats},
+    load_filter_lists, page_url, BlockerStatus,
+  };
+
+  #[command]
+  #[specta]
+  pub fn set_blocker(ctrl: WebviewWindow, state: State<'_, AppState>, value: bool) -> Result<(), String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    super::set_blocker(&window, &state, &window_data, value).err_to_string()?;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn toggle_blocker(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let value = !window_data.blocker.enabled.load(Ordering::Acquire);
+    super::set_blocker(&window, &state, &window_data, value).err_to_string()?;
+
+    Ok(value)
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_blocker(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<BlockerStatus, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    Ok(BlockerStatus::new(&state, &window_data))
+  }
+
+  /// 開いているサイトを許可リストに加える、既にあれば取り除く
+  #[command]
+  #[specta]
+  pub fn toggle_blocker_allowlist(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let url = page_url(&window_data).ok_or("page is not loaded")?;
+    let host = url.host_str().ok_or("page has no host")?;
+
+    let allowed = state.config.write().unwrap().blocker.toggle_allowed(host);
+    state.config.save().err_to_string()?;
+    reload(&window).err_to_string()?;
+
+    Ok(allowed)
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_blocker_settings(state: State<'_, AppState>) -> BlockerSettings {
+    state.config.read().unwrap().blocker.clone()
+  }
+
+  /// 保存してフィルターリストを読み直す
+  #[command]
+  #[specta]
+  pub async fn set_blocker_settings(state: State<'_, AppState>, settings: BlockerSettings) -> Result<FilterStats, String> {
+    state.config.write().unwrap().blocker = settings;
+    state.config.save().err_to_string()?;
+
+    load_filter_lists(&state).err_to_string()
+  }
+
+  #[command]
+  #[specta]
+  pub async fn reload_filter_lists(state: State<'_, AppState>) -> Result<FilterStats, String> {
+    load_filter_lists(&state).err_to_string()
+  }
+}
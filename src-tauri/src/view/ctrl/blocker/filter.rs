@@ -0,0 +1,575 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::view::ctrl::navigation::policy::site_of;
+
+/// リクエストの種類、webviewのリソースの種類から変換する
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum ResourceType {
+  Document,
+  Subdocument,
+  Script,
+  Image,
+  Stylesheet,
+  Font,
+  Media,
+  Xhr,
+  Websocket,
+  Ping,
+  Other,
+}
+
+impl ResourceType {
+  fn bit(self) -> u16 {
+    1 << self as u16
+  }
+
+  fn from_option(name: &str) -> Option<Self> {
+    Some(match name {
+      "document" => Self::Document,
+      "subdocument" | "frame" => Self::Subdocument,
+      "script" => Self::Script,
+      "image" => Self::Image,
+      "stylesheet" | "css" => Self::Stylesheet,
+      "font" => Self::Font,
+      "media" => Self::Media,
+      "xmlhttprequest" | "xhr" => Self::Xhr,
+      "websocket" => Self::Websocket,
+      "ping" => Self::Ping,
+      "other" => Self::Other,
+      _ => return None,
+    })
+  }
+}
+
+/// 種類の指定がないルールはページ本体以外のすべてに適用する
+const DEFAULT_TYPES: u16 = !(1 << ResourceType::Document as u16);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+  None,
+  /// `|`
+  Start,
+  /// `||`
+  Host,
+}
+
+#[derive(Debug, Clone)]
+struct NetworkFilter {
+  pattern: Box<[u8]>,
+  anchor: Anchor,
+  /// 末尾の`|`
+  end: bool,
+  exception: bool,
+  important: bool,
+  match_case: bool,
+  /// Some(true) -> サードパーティのみ、Some(false) -> ファーストパーティのみ
+  third_party: Option<bool>,
+  /// (ドメイン, 含めるか)
+  domains: Box<[(Box<str>, bool)]>,
+  types: u16,
+}
+
+#[derive(Debug, Clone)]
+struct CosmeticFilter {
+  selector: Box<str>,
+  include: Box<[Box<str>]>,
+  exclude: Box<[Box<str>]>,
+}
+
+impl CosmeticFilter {
+  fn applies(&self, host: &str) -> bool {
+    (self.include.is_empty() || self.include.iter().any(|v| domain_matches(host, v)))
+      && !self.exclude.iter().any(|v| domain_matches(host, v))
+  }
+}
+
+pub struct Request<'a> {
+  pub url: &'a str,
+  /// リクエスト元のページのホスト
+  pub source_host: Option<&'a str>,
+  pub kind: ResourceType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+  NoMatch,
+  Block,
+  /// ブロックするルールに一致したが`@@`の例外にも一致した
+  Allow,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct FilterStats {
+  pub network: u32,
+  pub cosmetic: u32,
+  /// 対応していない書き方のため読み飛ばしたルール
+  pub skipped: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct BlockerSettings {
+  /// フィルターリストのパス、相対パスは設定ファイルのディレクトリから
+  #[serde(default)]
+  pub lists: Vec<String>,
+  /// ブロックしないサイト、サブドメインも含む
+  #[serde(default)]
+  pub allowlist: Vec<String>,
+}
+
+impl BlockerSettings {
+  pub fn is_allowed(&self, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    self.allowlist.iter().any(|v| domain_matches(&host, &allowlist_domain(v)))
+  }
+
+  /// hostを許可リストに加える、既にあれば取り除く
+  /// 戻り値 -> 許可リストに入っているか
+  pub fn toggle_allowed(&mut self, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    let len = self.allowlist.len();
+    self.allowlist.retain(|v| !domain_matches(&host, &allowlist_domain(v)));
+    if self.allowlist.len() != len {
+      return false;
+    }
+    self.allowlist.push(host);
+
+    true
+  }
+}
+
+/// EasyList/uBlock Origin形式のフィルターリストをまとめたもの
+/// ネットワークフィルターはurlに含まれるトークンで引けるようにしておく
+#[derive(Debug, Default)]
+pub struct FilterEngine {
+  filters: Vec<NetworkFilter>,
+  by_token: HashMap<Box<str>, Vec<u32>>,
+  /// トークンを取り出せなかったルール、毎回すべて確かめる
+  untokened: Vec<u32>,
+  cosmetic: Vec<CosmeticFilter>,
+  cosmetic_exceptions: Vec<CosmeticFilter>,
+  stats: FilterStats,
+}
+
+impl FilterEngine {
+  pub fn parse(text: &str) -> Self {
+    let mut engine = Self::default();
+    engine.add_list(text);
+    engine
+  }
+
+  pub fn add_list(&mut self, text: &str) {
+    for line in text.lines() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('!') || line.starts_with('[') {
+        continue;
+      }
+
+      if let Some((domains, exception, selector)) = split_cosmetic(line) {
+        match parse_cosmetic(domains, selector) {
+          Some(filter) if exception => self.cosmetic_exceptions.push(filter),
+          Some(filter) => {
+            self.cosmetic.push(filter);
+            self.stats.cosmetic += 1;
+          }
+          None => self.stats.skipped += 1,
+        }
+        continue;
+      }
+
+      match parse_network(line) {
+        Some(filter) => self.add_network(filter),
+        None => self.stats.skipped += 1,
+      }
+    }
+  }
+
+  fn add_network(&mut self, filter: NetworkFilter) {
+    let index = self.filters.len() as u32;
+    // 他のルールと共有していないトークンほど照合する数が減る
+    let token = pattern_tokens(&filter)
+      .into_iter()
+      .min_by_key(|v| (self.by_token.get(v.as_str()).map_or(0, Vec::len), usize::MAX - v.len()));
+    match token {
+      Some(token) => self.by_token.entry(token.into()).or_default().push(index),
+      None => self.untokened.push(index),
+    }
+    self.filters.push(filter);
+    self.stats.network += 1;
+  }
+
+  pub fn stats(&self) -> FilterStats {
+    self.stats
+  }
+
+  pub fn check(&self, request: &Request) -> Verdict {
+    let lower = request.url.to_ascii_lowercase();
+    let host = host_range(&lower);
+    let request_host = &lower[host.0..host.1];
+    let third_party = request
+      .source_host
+      .map(|v| site_of(&v.to_ascii_lowercase()) != site_of(request_host));
+
+    let mut tokens = tokens(lower.as_bytes());
+    tokens.sort_unstable();
+    tokens.dedup();
+    let candidates = tokens
+      .into_iter()
+      .filter_map(|v| self.by_token.get(v))
+      .flatten()
+      .chain(self.untokened.iter());
+
+    let (mut blocked, mut excepted) = (false, false);
+    for &index in candidates {
+      let filter = &self.filters[index as usize];
+      if (filter.exception && excepted) || (!filter.exception && blocked && !filter.important) {
+        continue;
+      }
+      let url = if filter.match_case { request.url } else { &lower };
+      if !filter.matches(url.as_bytes(), host, request, third_party) {
+        continue;
+      }
+
+      match (filter.exception, filter.important) {
+        (false, true) => return Verdict::Block,
+        (false, false) => blocked = true,
+        (true, _) => excepted = true,
+      }
+    }
+
+    match (blocked, excepted) {
+      (true, false) => Verdict::Block,
+      (true, true) => Verdict::Allow,
+      _ => Verdict::NoMatch,
+    }
+  }
+
+  /// ホストで非表示にするセレクター
+  pub fn cosmetic_selectors(&self, host: &str) -> Vec<&str> {
+    let host = host.to_ascii_lowercase();
+    let excepted = self
+      .cosmetic_exceptions
+      .iter()
+      .filter(|v| v.applies(&host))
+      .map(|v| &*v.selector)
+      .collect::<Vec<_>>();
+
+    self
+      .cosmetic
+      .iter()
+      .filter(|v| v.applies(&host) && !excepted.contains(&&*v.selector))
+      .map(|v| &*v.selector)
+      .collect()
+  }
+
+  /// 不正なセレクターが他を巻き込まないように1つずつルールにする
+  pub fn cosmetic_css(&self, host: &str) -> String {
+    self
+      .cosmetic_selectors(host)
+      .into_iter()
+      .map(|v| format!("{} {{ display: none !important; }}\n", v))
+      .collect()
+  }
+}
+
+impl NetworkFilter {
+  fn matches(&self, url: &[u8], host: (usize, usize), request: &Request, third_party: Option<bool>) -> bool {
+    if self.types & request.kind.bit() == 0 {
+      return false;
+    }
+    if let Some(value) = self.third_party {
+      if third_party != Some(value) {
+        return false;
+      }
+    }
+    if !self.domains.is_empty() {
+      let Some(source) = request.source_host else { return false };
+      let source = source.to_ascii_lowercase();
+      let mut include = self.domains.iter().filter(|v| v.1).peekable();
+      if include.peek().is_some() && !include.any(|v| domain_matches(&source, &v.0)) {
+        return false;
+      }
+      if self.domains.iter().any(|v| !v.1 && domain_matches(&source, &v.0)) {
+        return false;
+      }
+    }
+
+    match self.anchor {
+      Anchor::Start => match_here(&self.pattern, url, self.end),
+      Anchor::Host => {
+        // ホストの先頭か`.`の直後から
+        let starts = std::iter::once(host.0).chain((host.0..host.1).filter(|&i| url[i] == b'.').map(|i| i + 1));
+        starts.into_iter().any(|i| match_here(&self.pattern, &url[i..], self.end))
+      }
+      Anchor::None => match self.pattern.first() {
+        Some(&first) if first != b'*' && first != b'^' => (0..url.len())
+          .filter(|&i| url[i] == first)
+          .any(|i| match_here(&self.pattern, &url[i..], self.end)),
+        _ => (0..=url.len()).any(|i| match_here(&self.pattern, &url[i..], self.end)),
+      },
+    }
+  }
+}
+
+/// `*.example.com`も`example.com`と同じ扱いにする
+fn allowlist_domain(value: &str) -> String {
+  value.trim().trim_start_matches("*.").to_ascii_lowercase()
+}
+
+/// host -> example.comならexample.comとそのサブドメイン
+fn domain_matches(host: &str, domain: &str) -> bool {
+  host == domain || (host.len() > domain.len() && host.ends_with(domain) && host.as_bytes()[host.len() - domain.len() - 1] == b'.')
+}
+
+/// 英数字と`_-.%`以外、urlの終わりも区切りとして扱う
+fn is_separator(c: u8) -> bool {
+  !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.' | b'%'))
+}
+
+fn is_token_char(c: u8) -> bool {
+  c.is_ascii_alphanumeric() || c == b'%'
+}
+
+/// patternがtextの先頭から一致するか、endならtextの最後まで一致する必要がある
+/// 続いた`*`はまとめて、`*`の間の部分は前から順に最も左で一致する位置を使うので戻らない
+fn match_here(pattern: &[u8], text: &[u8], end: bool) -> bool {
+  let mut pieces = pattern.split(|c| *c == b'*');
+  let Some(mut pos) = match_piece(pieces.next().unwrap_or_default(), text, 0) else {
+    return false;
+  };
+  if !pattern.contains(&b'*') {
+    return !end || pos == text.len();
+  }
+
+  let mut pieces = pieces.filter(|v| !v.is_empty()).collect::<Vec<_>>();
+  // 最後が`*`でなければ最後の部分はtextの終わりに合わせる必要がある
+  let last = if pattern.ends_with(b"*") { None } else { pieces.pop() };
+  for piece in pieces {
+    match (pos..=text.len()).find_map(|i| match_piece(piece, text, i)) {
+      Some(v) => pos = v,
+      None => return false,
+    }
+  }
+
+  match last {
+    Some(piece) if end => (pos..=text.len()).any(|i| match_piece(piece, text, i) == Some(text.len())),
+    Some(piece) => (pos..=text.len()).any(|i| match_piece(piece, text, i).is_some()),
+    None => true,
+  }
+}
+
+/// `*`を含まないpieceがtextのstartから一致すれば、一致した範囲の終わりを返す
+fn match_piece(piece: &[u8], text: &[u8], start: usize) -> Option<usize> {
+  let mut i = start;
+  for &c in piece {
+    match text.get(i) {
+      Some(&t) if c == b'^' && is_separator(t) => i += 1,
+      // `^`はurlの終わりにも一致する
+      None if c == b'^' => {}
+      Some(&t) if c != b'^' && t == c => i += 1,
+      _ => return None,
+    }
+  }
+
+  Some(i)
+}
+
+/// `scheme://`の後からホストの終わりまでの範囲
+fn host_range(url: &str) -> (usize, usize) {
+  let start = url.find("://").map_or(0, |i| i + 3);
+  let rest = &url[start..];
+  let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+  // ユーザー情報とポートを除く
+  let authority = &rest[..end];
+  let host_start = authority.rfind('@').map_or(0, |i| i + 1);
+  let host_end = authority[host_start..].find(':').map_or(end, |i| host_start + i);
+
+  (start + host_start, start + host_end)
+}
+
+fn tokens(url: &[u8]) -> Vec<&str> {
+  url
+    .split(|c| !is_token_char(*c))
+    .filter(|v| v.len() >= 2)
+    .filter_map(|v| std::str::from_utf8(v).ok())
+    .collect()
+}
+
+/// urlのトークンと完全に一致するはずの部分
+fn pattern_tokens(filter: &NetworkFilter) -> Vec<String> {
+  let pattern = &filter.pattern;
+  let mut tokens = Vec::new();
+  let mut i = 0;
+  while i < pattern.len() {
+    if !is_token_char(pattern[i]) {
+      i += 1;
+      continue;
+    }
+    let start = i;
+    while i < pattern.len() && is_token_char(pattern[i]) {
+      i += 1;
+    }
+
+    // 前後が`*`やパターンの端の場合は部分一致になるので使えない
+    let bounded_start = if start == 0 {
+      filter.anchor != Anchor::None
+    } else {
+      pattern[start - 1] != b'*'
+    };
+    let bounded_end = if i == pattern.len() { filter.end } else { pattern[i] != b'*' };
+    if bounded_start && bounded_end && i - start >= 2 {
+      tokens.push(String::from_utf8_lossy(&pattern[start..i]).to_ascii_lowercase());
+    }
+  }
+
+  tokens
+}
+
+fn parse_network(line: &str) -> Option<NetworkFilter> {
+  let (exception, line) = match line.strip_prefix("@@") {
+    Some(v) => (true, v),
+    None => (false, line),
+  };
+
+  let (pattern, options) = match line.rfind('$') {
+    Some(i) if is_options(&line[i + 1..]) => (&line[..i], Some(&line[i + 1..])),
+    _ => (line, None),
+  };
+  // 正規表現には対応しない
+  if pattern.len() > 1 && pattern.starts_with('/') && pattern.ends_with('/') {
+    return None;
+  }
+
+  let mut filter = NetworkFilter {
+    pattern: Box::default(),
+    anchor: Anchor::None,
+    end: false,
+    exception,
+    important: false,
+    match_case: false,
+    third_party: None,
+    domains: Box::default(),
+    types: DEFAULT_TYPES,
+  };
+
+  let mut pattern = pattern;
+  if let Some(v) = pattern.strip_prefix("||") {
+    filter.anchor = Anchor::Host;
+    pattern = v;
+  } else if let Some(v) = pattern.strip_prefix('|') {
+    filter.anchor = Anchor::Start;
+    pattern = v;
+  }
+  if let Some(v) = pattern.strip_suffix('|') {
+    filter.end = true;
+    pattern = v;
+  }
+
+  if let Some(options) = options {
+    let (mut include, mut exclude) = (0u16, 0u16);
+    let mut domains = Vec::new();
+    for option in options.split(',') {
+      let option = option.trim();
+      match option {
+        "third-party" | "3p" => filter.third_party = Some(true),
+        "~third-party" | "~3p" | "first-party" | "1p" => filter.third_party = Some(false),
+        "important" => filter.important = true,
+        "match-case" => filter.match_case = true,
+        _ => {
+          if let Some(list) = option.strip_prefix("domain=") {
+            for v in list.split('|').filter(|v| !v.is_empty()) {
+              match v.strip_prefix('~') {
+                Some(v) => domains.push((v.to_ascii_lowercase().into(), false)),
+                None => domains.push((v.to_ascii_lowercase().into(), true)),
+              }
+            }
+          } else if let Some(kind) = option.strip_prefix('~').and_then(ResourceType::from_option) {
+            exclude |= kind.bit();
+          } else if let Some(kind) = ResourceType::from_option(option) {
+            include |= kind.bit();
+          } else {
+            // redirect,csp,removeparam等は扱えないのでルールごと読み飛ばす
+            return None;
+          }
+        }
+      }
+    }
+    filter.types = if include != 0 { include } else { DEFAULT_TYPES } & !exclude;
+    filter.domains = domains.into();
+  }
+
+  let pattern = pattern.trim_start_matches('*');
+  let pattern = if filter.end { pattern } else { pattern.trim_end_matches('*') };
+  // すべてのurlに一致してしまうルールは無視する
+  if pattern.is_empty() && filter.domains.is_empty() {
+    return None;
+  }
+  filter.pattern = if filter.match_case {
+    pattern.as_bytes().into()
+  } else {
+    pattern.to_ascii_lowercase().into_bytes().into()
+  };
+
+  Some(filter)
+}
+
+fn is_options(value: &str) -> bool {
+  !value.is_empty() && value.bytes().all(|c| c.is_ascii_alphanumeric() || b"~,=|._-*".contains(&c))
+}
+
+/// (ドメイン, 例外か, セレクター)
+fn split_cosmetic(line: &str) -> Option<(&str, bool, &str)> {
+  let i = line.find('#')?;
+  let (domains, rest) = line.split_at(i);
+  if let Some(selector) = rest.strip_prefix("##") {
+    Some((domains, false, selector))
+  } else if let Some(selector) = rest.strip_prefix("#@#") {
+    Some((domains, true, selector))
+  } else if rest.starts_with("#?#")
+    || rest.starts_with("#$#")
+    || rest.starts_with("#@$#")
+    || rest.starts_with("#@?#")
+    || rest.starts_with("#%#")
+  {
+    // 拡張された書き方は読み飛ばす
+    Some((domains, false, ""))
+  } else {
+    None
+  }
+}
+
+fn parse_cosmetic(domains: &str, selector: &str) -> Option<CosmeticFilter> {
+  const PROCEDURAL: &[&str] = &[
+    ":has-text(",
+    ":-abp-",
+    ":matches-css",
+    ":matches-path(",
+    ":xpath(",
+    ":upward(",
+    ":remove(",
+    ":style(",
+    ":min-text-length(",
+    ":watch-attr(",
+  ];
+
+  let selector = selector.trim();
+  if selector.is_empty() || selector.starts_with('+') || selector.starts_with('^') || PROCEDURAL.iter().any(|v| selector.contains(v)) {
+    return None;
+  }
+
+  let (mut include, mut exclude) = (Vec::new(), Vec::new());
+  for v in domains.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+    match v.strip_prefix('~') {
+      Some(v) => exclude.push(v.to_ascii_lowercase().into()),
+      None => include.push(v.to_ascii_lowercase().into()),
+    }
+  }
+
+  Some(CosmeticFilter {
+    selector: selector.into(),
+    include: include.into(),
+    exclude: exclude.into(),
+  })
+}
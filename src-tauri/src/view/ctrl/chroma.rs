@@ -0,0 +1,80 @@
+use tauri::WebviewWindow;
+use webview2_com::Microsoft::Web::WebView2::Win32::COREWEBVIEW2_COLOR;
+
+use super::page_transparent::set_background;
+
+/// OBSのクロマキーでよく使われる緑
+pub const DEFAULT_CHROMA_COLOR: u32 = 0x00ff00;
+
+/// ページの透明な部分の後ろに単色(0xRRGGBB)を敷く
+pub fn set_chroma(window: &WebviewWindow, color: u32) -> anyhow::Result<()> {
+  let color = COREWEBVIEW2_COLOR {
+    A: 255,
+    R: (color >> 16) as u8,
+    G: (color >> 8) as u8,
+    B: color as u8,
+  };
+
+  set_background(window, color, true)
+}
+
+pub mod command {
+  use std::sync::{atomic::Ordering, Arc};
+
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{
+    util::{AppState, ErrToString},
+    view::{
+      ctrl::{apply_background, transparent},
+      util::ctrl_to_window_and_data,
+    },
+  };
+
+  #[command]
+  #[specta]
+  pub fn toggle_chroma(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let atomic = Arc::clone(&window_data.chroma);
+    let condition = atomic.0.load(Ordering::Acquire);
+    let color = atomic.1.load(Ordering::Acquire);
+    let color_key = atomic.2.load(Ordering::Acquire);
+
+    set_chroma(ctrl, state, !condition, color, color_key)?;
+
+    Ok(!condition)
+  }
+
+  /// color -> 0xRRGGBB
+  /// color_key -> ウィンドウにカラーキーを設定してデスクトップでもその色を透過する
+  #[command]
+  #[specta]
+  pub fn set_chroma(ctrl: WebviewWindow, state: State<'_, AppState>, value: bool, color: u32, color_key: bool) -> Result<(), String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let atomic = Arc::clone(&window_data.chroma);
+
+    atomic.0.store(value, Ordering::Release);
+    atomic.1.store(color & 0xffffff, Ordering::Release);
+    atomic.2.store(color_key, Ordering::Release);
+
+    apply_background(&window, &window_data).err_to_string()?;
+    transparent::set_transparent(window.hwnd().unwrap(), window_data.alpha(), window_data.color_key()).err_to_string()?;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_chroma(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<(bool, u32, bool), String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let atomic = Arc::clone(&window_data.chroma);
+    let data = (
+      atomic.0.load(Ordering::Acquire),
+      atomic.1.load(Ordering::Acquire),
+      atomic.2.load(Ordering::Acquire),
+    );
+
+    Ok(data)
+  }
+}
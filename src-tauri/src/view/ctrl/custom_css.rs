@@ -0,0 +1,44 @@
+use tauri::WebviewWindow;
+
+use crate::view::util::{inject_style, remove_style};
+
+const STYLE_ID: &str = "custom";
+
+/// ページに追加するユーザーのcss、空なら取り除く
+pub fn set_custom_css(window: &WebviewWindow, css: &str) -> anyhow::Result<()> {
+  if css.trim().is_empty() {
+    remove_style(window, STYLE_ID)
+  } else {
+    inject_style(window, STYLE_ID, css)
+  }
+}
+
+pub mod command {
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{
+    util::{AppState, ErrToString},
+    view::util::ctrl_to_window_and_data,
+  };
+
+  /// 読み込み直しても残る
+  #[command]
+  #[specta]
+  pub fn set_custom_css(ctrl: WebviewWindow, state: State<'_, AppState>, css: String) -> Result<(), String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    super::set_custom_css(&window, &css).err_to_string()?;
+    *window_data.custom_css.lock().unwrap() = css;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_custom_css(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<String, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    Ok(window_data.custom_css.lock().unwrap().clone())
+  }
+}
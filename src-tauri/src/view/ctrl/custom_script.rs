@@ -0,0 +1,64 @@
+use tauri::{Url, WebviewWindow};
+
+use crate::util::WindowData;
+
+/// スクリプトを許可したオリジンとして残す値
+pub fn script_origin(url: &Url) -> String {
+  url.origin().ascii_serialization()
+}
+
+/// 許可したオリジンのページでだけ実行する
+pub fn run_custom_script(window: &WebviewWindow, window_data: &WindowData) -> anyhow::Result<()> {
+  let script = window_data.custom_script.lock().unwrap().clone();
+  if script.trim().is_empty() {
+    return Ok(());
+  }
+
+  let url = window.url()?;
+  let allowed = window_data.custom_script_origin.lock().unwrap().clone();
+  // data:等のオリジンはすべて"null"になるので区別できない
+  if !url.origin().is_tuple() || allowed.as_deref() != Some(script_origin(&url).as_str()) {
+    println!(
+      "custom script is not run on {}, it is allowed for {}",
+      script_origin(&url),
+      allowed.as_deref().unwrap_or("no origin")
+    );
+    return Ok(());
+  }
+  window.eval(script)?;
+
+  Ok(())
+}
+
+pub mod command {
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{
+    util::{AppState, ErrToString},
+    view::util::ctrl_to_window_and_data,
+  };
+
+  use super::script_origin;
+
+  /// ページを読み込むたびに実行するjs、変更は次に読み込んだときから
+  /// 今開いているページのオリジンでだけ実行する
+  #[command]
+  #[specta]
+  pub fn set_custom_script(ctrl: WebviewWindow, state: State<'_, AppState>, script: String) -> Result<(), String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let origin = script_origin(&window.url().err_to_string()?);
+    *window_data.custom_script.lock().unwrap() = script;
+    *window_data.custom_script_origin.lock().unwrap() = Some(origin);
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_custom_script(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<String, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    Ok(window_data.custom_script.lock().unwrap().clone())
+  }
+}
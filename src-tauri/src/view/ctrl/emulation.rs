@@ -0,0 +1,129 @@
+use std::sync::atomic::Ordering;
+
+use anyhow::{ensure, Context};
+use serde_json::json;
+use tauri::{LogicalSize, Manager, PhysicalSize, WebviewWindow};
+
+use crate::{
+  util::{AppState, WindowData},
+  view::util::call_devtools,
+};
+
+use super::{apply_zoom, user_agent::current_preset};
+
+/// 選択しているプリセットの画面を再現する
+/// CSSの幅はzoomで固定し、ウィンドウはプリセットの縦横比に合わせる
+pub fn set_emulation(window: &WebviewWindow, state: &AppState, window_data: &WindowData, value: bool) -> anyhow::Result<()> {
+  let preset_id = window_data.agent_preset.lock().unwrap().clone();
+  let preset = current_preset(state, &preset_id).with_context(|| format!("preset {} is not found", preset_id))?;
+
+  if value {
+    ensure!(preset.can_emulate(), "preset {} has no viewport size", preset_id);
+    // 幅と高さは0にしてウィンドウの大きさに任せる
+    call_devtools(
+      window,
+      "Emulation.setDeviceMetricsOverride",
+      &json!({
+        "width": 0,
+        "height": 0,
+        "deviceScaleFactor": preset.device_scale_factor,
+        "mobile": preset.mobile,
+      }),
+    )?;
+    call_devtools(
+      window,
+      "Emulation.setTouchEmulationEnabled",
+      &json!({ "enabled": true, "maxTouchPoints": 5 }),
+    )?;
+
+    let (width, height) = preset.window_size(window_data.zoom.load(Ordering::Acquire)).unwrap_or_default();
+    window.set_size(LogicalSize::new(width, height))?;
+  } else {
+    call_devtools(window, "Emulation.clearDeviceMetricsOverride", &json!({}))?;
+    call_devtools(window, "Emulation.setTouchEmulationEnabled", &json!({ "enabled": false }))?;
+  }
+  window_data.emulation.store(value, Ordering::Release);
+
+  Ok(())
+}
+
+/// zoomを変えたときにviewport_widthが変わらないようにウィンドウの大きさを合わせる
+pub fn resize_to_zoom(window: &WebviewWindow, state: &AppState, window_data: &WindowData) -> anyhow::Result<()> {
+  if !window_data.emulation.load(Ordering::Acquire) {
+    return Ok(());
+  }
+
+  let preset_id = window_data.agent_preset.lock().unwrap().clone();
+  if let Some((width, height)) = current_preset(state, &preset_id).and_then(|v| v.window_size(window_data.zoom.load(Ordering::Acquire))) {
+    window.set_size(LogicalSize::new(width, height))?;
+  }
+
+  Ok(())
+}
+
+/// WindowEvent::Resizedから呼ぶ、幅に合わせてzoomを変えて縦横比を保つ
+pub fn on_resized(window: &WebviewWindow, size: PhysicalSize<u32>) -> anyhow::Result<()> {
+  let state = window.state::<AppState>();
+  let Some(window_data) = state.get_window_data(window.label()) else {
+    return Ok(());
+  };
+  // 最小化したときは0になる
+  if !window_data.emulation.load(Ordering::Acquire) || size.width == 0 {
+    return Ok(());
+  }
+
+  let preset_id = window_data.agent_preset.lock().unwrap().clone();
+  let size = size.to_logical::<f64>(window.scale_factor()?);
+  let Some((zoom, height)) = current_preset(&state, &preset_id).and_then(|v| v.fit_width(size.width)) else {
+    return Ok(());
+  };
+
+  if window_data.zoom.load(Ordering::Acquire) != zoom {
+    apply_zoom(window, &window_data, zoom)?;
+  }
+  // set_sizeで再びResizedが来るので差が小さければ何もしない
+  if (size.height - height).abs() > 1.0 {
+    window.set_size(LogicalSize::new(size.width, height))?;
+  }
+
+  Ok(())
+}
+
+pub mod command {
+  use std::sync::atomic::Ordering;
+
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{
+    util::{AppState, ErrToString},
+    view::util::ctrl_to_window_and_data,
+  };
+
+  #[command]
+  #[specta]
+  pub fn set_emulation(ctrl: WebviewWindow, state: State<'_, AppState>, value: bool) -> Result<(), String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    super::set_emulation(&window, &state, &window_data, value).err_to_string()?;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn toggle_emulation(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let value = !window_data.emulation.load(Ordering::Acquire);
+    super::set_emulation(&window, &state, &window_data, value).err_to_string()?;
+
+    Ok(value)
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_emulation(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    Ok(window_data.emulation.load(Ordering::Acquire))
+  }
+}
@@ -0,0 +1,37 @@
+pub mod command {
+  use std::sync::atomic::Ordering;
+
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{util::AppState, view::util::ctrl_to_window_and_data};
+
+  #[command]
+  #[specta]
+  pub fn toggle_incognito(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let condition = window_data.incognito.load(Ordering::Acquire);
+
+    set_incognito(ctrl, state, !condition)?;
+
+    Ok(!condition)
+  }
+
+  /// true -> 履歴に残さない
+  #[command]
+  #[specta]
+  pub fn set_incognito(ctrl: WebviewWindow, state: State<'_, AppState>, value: bool) -> Result<(), String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    window_data.incognito.store(value, Ordering::Release);
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_incognito(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    Ok(window_data.incognito.load(Ordering::Acquire))
+  }
+}
@@ -0,0 +1,140 @@
+pub mod policy;
+
+use std::{sync::atomic::Ordering, thread};
+
+use tauri::{AppHandle, Manager, Url, WebviewUrl, WebviewWindow};
+use webview2_com::{take_pwstr, NewWindowRequestedEventHandler};
+use windows::{
+  core::PWSTR,
+  Win32::{Foundation::BOOL, System::WinRT::EventRegistrationToken},
+};
+
+use crate::{util::AppState, view::util::open_browser};
+
+use policy::{LinkAction, NavigationPolicy};
+
+/// WebviewWindowBuilder::on_navigationから呼ぶ、falseを返すと移動を止める
+pub fn on_navigation(app: &AppHandle, label: &str, url: &Url) -> bool {
+  let Some((origin, policy)) = policy_of(app, label) else {
+    return true;
+  };
+
+  match policy.navigation(&origin, url) {
+    LinkAction::Current => true,
+    action => {
+      follow(app, label, action, url.clone());
+      false
+    }
+  }
+}
+
+/// target=_blankやwindow.openで開かれるウィンドウを横取りする
+pub fn attach_new_window_handler(app: &AppHandle, window: &WebviewWindow) -> anyhow::Result<()> {
+  let label = window.label().to_string();
+  window.with_webview({
+    let app = app.clone();
+    move |webview| {
+      #[cfg(windows)]
+      unsafe {
+        let controller = webview.controller();
+        let webview = controller.CoreWebView2().unwrap();
+        let mut token = EventRegistrationToken::default();
+
+        webview
+          .add_NewWindowRequested(
+            &NewWindowRequestedEventHandler::create(Box::new(move |_, args| {
+              let Some(args) = args else { return Ok(()) };
+              let mut uri = PWSTR::null();
+              args.Uri(&mut uri)?;
+              let uri = take_pwstr(uri);
+              // webviewに新しいウィンドウを作らせない
+              args.SetHandled(BOOL::from(true))?;
+
+              let Ok(url) = Url::parse(&uri) else { return Ok(()) };
+              let action = match policy_of(&app, &label) {
+                Some((origin, policy)) => policy.new_window(&origin, &url),
+                None => NavigationPolicy::default().popup,
+              };
+              follow(&app, &label, action, url);
+
+              Ok(())
+            })),
+            &mut token,
+          )
+          .unwrap();
+      }
+    }
+  })?;
+
+  Ok(())
+}
+
+/// オーバーレイで最初に開いたurlとポリシー
+fn policy_of(app: &AppHandle, label: &str) -> Option<(Url, NavigationPolicy)> {
+  let state = app.state::<AppState>();
+  let window_data = state.get_window_data(label)?;
+  let WebviewUrl::External(origin) = &window_data.url else {
+    return None;
+  };
+  let policy = window_data.navigation.lock().unwrap().clone();
+
+  Some((origin.clone(), policy))
+}
+
+pub fn follow(app: &AppHandle, label: &str, action: LinkAction, url: Url) {
+  let res = match action {
+    LinkAction::Current => app
+      .get_webview_window(label)
+      .map_or(Ok(()), |v| v.navigate(url).map_err(Into::into)),
+    LinkAction::Browser => open_browser(&url),
+    LinkAction::Overlay => {
+      // ナビゲーションのコールバック内でウィンドウを作るとデッドロックするので別スレッドで作る
+      let app = app.clone();
+      // リンク元と同じプロファイルとプロキシ、シークレット、UAで開く
+      let source = app.state::<AppState>().get_window_data(label);
+      let profile = source.as_ref().and_then(|v| v.profile.clone());
+      let incognito = source.as_ref().is_some_and(|v| v.incognito.load(Ordering::Acquire));
+      let agent_preset = source.as_ref().map(|v| v.agent_preset.lock().unwrap().clone());
+      let proxy = source.and_then(|v| v.proxy.lock().unwrap().clone());
+      thread::spawn(move || {
+        let state = app.state::<AppState>();
+        if let Err(e) = super::view_create(&app, &state, WebviewUrl::External(url), profile, proxy, incognito, agent_preset) {
+          println!("failed to open overlay: {}", e);
+        }
+      });
+      Ok(())
+    }
+    LinkAction::Drop => Ok(()),
+  };
+
+  if let Err(e) = res {
+    println!("failed to follow link: {}", e);
+  }
+}
+
+pub mod command {
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{util::AppState, view::util::ctrl_to_window_and_data};
+
+  use super::policy::NavigationPolicy;
+
+  #[command]
+  #[specta]
+  pub fn set_navigation_policy(ctrl: WebviewWindow, state: State<'_, AppState>, policy: NavigationPolicy) -> Result<(), String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    *window_data.navigation.lock().unwrap() = policy;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_navigation_policy(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<NavigationPolicy, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let policy = window_data.navigation.lock().unwrap().clone();
+
+    Ok(policy)
+  }
+}
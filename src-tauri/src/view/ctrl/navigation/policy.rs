@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use url::{Host, Url};
+
+/// どのurlへの移動を許可するか
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+#[serde(tag = "mode", content = "patterns")]
+pub enum NavigationMode {
+  #[default]
+  AllowAll,
+  /// 最初に開いたurlと同じドメイン(サブドメインを含む)のみ
+  SameDomain,
+  Allowlist(Vec<String>),
+  Blocklist(Vec<String>),
+}
+
+/// リンクをどこで開くか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum LinkAction {
+  /// 同じオーバーレイで開く
+  Current,
+  /// 既定のブラウザで開く
+  #[default]
+  Browser,
+  /// 新しいオーバーレイで開く
+  Overlay,
+  /// 何もしない
+  Drop,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct NavigationPolicy {
+  pub mode: NavigationMode,
+  /// 許可されない移動の扱い、Currentの場合はDropとして扱う
+  pub blocked: LinkAction,
+  /// target=_blank等で新しいウィンドウを開こうとしたときの扱い
+  pub popup: LinkAction,
+}
+
+impl NavigationPolicy {
+  /// originはオーバーレイで最初に開いたurl
+  pub fn allows(&self, origin: &Url, target: &Url) -> bool {
+    // about:blankやdata:等はページの一部として扱う
+    if !matches!(target.scheme(), "http" | "https") {
+      return true;
+    }
+
+    match &self.mode {
+      NavigationMode::AllowAll => true,
+      NavigationMode::SameDomain => same_site(origin, target),
+      NavigationMode::Allowlist(patterns) => same_site(origin, target) || patterns.iter().any(|v| matches_pattern(v, target)),
+      NavigationMode::Blocklist(patterns) => !patterns.iter().any(|v| matches_pattern(v, target)),
+    }
+  }
+
+  /// ページ内の移動
+  pub fn navigation(&self, origin: &Url, target: &Url) -> LinkAction {
+    if self.allows(origin, target) {
+      LinkAction::Current
+    } else {
+      self.blocked_action()
+    }
+  }
+
+  /// 新しいウィンドウを開こうとしたとき
+  pub fn new_window(&self, origin: &Url, target: &Url) -> LinkAction {
+    if self.allows(origin, target) {
+      self.popup
+    } else {
+      self.blocked_action()
+    }
+  }
+
+  fn blocked_action(&self) -> LinkAction {
+    match self.blocked {
+      LinkAction::Current => LinkAction::Drop,
+      v => v,
+    }
+  }
+}
+
+/// パターンの書き方
+/// - `example.com` -> example.comとそのサブドメイン
+/// - `*.example.com` -> example.comのサブドメインのみ
+/// - `https://example.com/path/*` -> url全体に対するワイルドカード
+pub fn matches_pattern(pattern: &str, url: &Url) -> bool {
+  let pattern = pattern.trim();
+  if pattern.is_empty() {
+    return false;
+  }
+  if pattern.contains("://") {
+    return wildcard(pattern, url.as_str());
+  }
+
+  let Some(host) = url.host_str() else { return false };
+  let host = host.to_ascii_lowercase();
+  let pattern = pattern.to_ascii_lowercase();
+  match pattern.strip_prefix("*.") {
+    Some(domain) => host.ends_with(&format!(".{}", domain)),
+    None if pattern.contains('*') => wildcard(&pattern, &host),
+    None => host == pattern || host.ends_with(&format!(".{}", pattern)),
+  }
+}
+
+/// 登録可能ドメインが同じか
+/// Public Suffix Listは使わないので`co.jp`のような2階層のものは近似で判定する
+pub fn same_site(a: &Url, b: &Url) -> bool {
+  match (a.host(), b.host()) {
+    (Some(Host::Domain(a)), Some(Host::Domain(b))) => site_of(&a.to_ascii_lowercase()) == site_of(&b.to_ascii_lowercase()),
+    (Some(a), Some(b)) => a == b,
+    _ => false,
+  }
+}
+
+pub fn site_of(host: &str) -> &str {
+  let host = host.trim_end_matches('.');
+  let labels: Vec<&str> = host.rsplitn(4, '.').collect();
+  let take = match labels.as_slice() {
+    // example.co.jp, example.com.au
+    [tld, second, _, ..]
+      if tld.len() == 2 && matches!(*second, "co" | "com" | "net" | "org" | "ac" | "go" | "ne" | "or" | "gov" | "edu") =>
+    {
+      3
+    }
+    [_, _, ..] => 2,
+    _ => return host,
+  };
+
+  let len: usize = labels[..take].iter().map(|v| v.len()).sum::<usize>() + take - 1;
+  &host[host.len() - len..]
+}
+
+/// `*`だけを特殊文字として扱う単純なワイルドカード
+pub fn wildcard(pattern: &str, text: &str) -> bool {
+  let mut parts = pattern.split('*');
+  let first = parts.next().unwrap_or_default();
+  let Some(mut rest) = text.strip_prefix(first) else { return false };
+
+  let parts: Vec<&str> = parts.collect();
+  let Some((last, middle)) = parts.split_last() else {
+    // `*`を含まない
+    return rest.is_empty();
+  };
+
+  for part in middle {
+    match rest.find(part) {
+      Some(i) => rest = &rest[i + part.len()..],
+      None => return false,
+    }
+  }
+
+  rest.ends_with(last)
+}
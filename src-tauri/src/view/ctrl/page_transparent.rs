@@ -0,0 +1,93 @@
+use tauri::WebviewWindow;
+use webview2_com::Microsoft::Web::WebView2::Win32::{ICoreWebView2Controller2, COREWEBVIEW2_COLOR};
+use windows::core::Interface;
+
+use crate::view::util::{inject_style, remove_style};
+
+const STYLE_ID: &str = "page-transparent";
+const STYLE: &str = "html, body { background: transparent !important; background-color: transparent !important; }";
+
+/// webviewの背景色を透明にする(ウィンドウ全体のalphaとは独立)
+/// force_css -> html,bodyの背景もcssで透明にする
+pub fn set_page_transparent(window: &WebviewWindow, value: bool, force_css: bool) -> anyhow::Result<()> {
+  let color = if value {
+    COREWEBVIEW2_COLOR { A: 0, R: 0, G: 0, B: 0 }
+  } else {
+    COREWEBVIEW2_COLOR {
+      A: 255,
+      R: 255,
+      G: 255,
+      B: 255,
+    }
+  };
+
+  set_background(window, color, value && force_css)
+}
+
+/// webviewの背景色を設定する
+/// transparent_css -> html,bodyの背景をcssで透明にする
+pub fn set_background(window: &WebviewWindow, color: COREWEBVIEW2_COLOR, transparent_css: bool) -> anyhow::Result<()> {
+  window.with_webview(move |webview| {
+    #[cfg(windows)]
+    unsafe {
+      let controller_2: ICoreWebView2Controller2 = webview.controller().cast().unwrap();
+      controller_2.SetDefaultBackgroundColor(color).unwrap();
+    }
+  })?;
+
+  if transparent_css {
+    inject_style(window, STYLE_ID, STYLE)?;
+  } else {
+    remove_style(window, STYLE_ID)?;
+  }
+
+  Ok(())
+}
+
+pub mod command {
+  use std::sync::{atomic::Ordering, Arc};
+
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{
+    util::{AppState, ErrToString},
+    view::{ctrl::apply_background, util::ctrl_to_window_and_data},
+  };
+
+  #[command]
+  #[specta]
+  pub fn toggle_page_transparent(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let atomic = Arc::clone(&window_data.page_transparent);
+    let condition = atomic.0.load(Ordering::Acquire);
+    let force_css = atomic.1.load(Ordering::Acquire);
+
+    set_page_transparent(ctrl, state, !condition, force_css)?;
+
+    Ok(!condition)
+  }
+
+  #[command]
+  #[specta]
+  pub fn set_page_transparent(ctrl: WebviewWindow, state: State<'_, AppState>, value: bool, force_css: bool) -> Result<(), String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let atomic = Arc::clone(&window_data.page_transparent);
+
+    atomic.0.store(value, Ordering::Release);
+    atomic.1.store(force_css, Ordering::Release);
+    apply_background(&window, &window_data).err_to_string()?;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_page_transparent(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<(bool, bool), String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let atomic = Arc::clone(&window_data.page_transparent);
+    let data = (atomic.0.load(Ordering::Acquire), atomic.1.load(Ordering::Acquire));
+
+    Ok(data)
+  }
+}
@@ -0,0 +1,292 @@
+use std::{
+  sync::{atomic::Ordering, Arc},
+  time::Duration,
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use webview2_com::{
+  ExecuteScriptCompletedHandler,
+  Microsoft::Web::WebView2::Win32::{
+    COREWEBVIEW2_PROCESS_FAILED_KIND, COREWEBVIEW2_WEB_ERROR_STATUS, COREWEBVIEW2_WEB_ERROR_STATUS_OPERATION_CANCELED,
+  },
+  NavigationCompletedEventHandler, ProcessFailedEventHandler,
+};
+use windows::{
+  core::HSTRING,
+  Win32::{Foundation::BOOL, System::WinRT::EventRegistrationToken},
+};
+
+use crate::util::{backoff, AppState, TaskKind, TaskStatus, WindowData};
+
+pub const WATCHDOG_BACKOFF_BASE: Duration = Duration::from_secs(2);
+pub const WATCHDOG_BACKOFF_MAX: Duration = Duration::from_secs(300);
+/// 読み込み完了からこの時間が経っても中身が空なら失敗とみなす
+pub const BLANK_CHECK_DELAY: Duration = Duration::from_secs(5);
+
+const BLANK_CHECK_SCRIPT: &str = "(document.body?.innerText.trim().length ?? 0) + (document.body?.children.length ?? 0)";
+
+#[derive(Debug, Clone, Deserialize, Serialize, Type)]
+pub struct RefreshStatus {
+  pub interval: u32,
+  pub watchdog: bool,
+  pub failures: u32,
+  pub tasks: Vec<TaskStatus>,
+}
+
+impl RefreshStatus {
+  pub fn new(state: &AppState, window_data: &WindowData) -> Self {
+    Self {
+      interval: window_data.refresh.load(Ordering::Acquire),
+      watchdog: window_data.watchdog.0.load(Ordering::Acquire),
+      failures: window_data.watchdog.1.load(Ordering::Acquire),
+      tasks: state.scheduler.status(&window_data.label),
+    }
+  }
+}
+
+pub fn reload(window: &WebviewWindow) -> anyhow::Result<()> {
+  window.with_webview(|webview| {
+    #[cfg(windows)]
+    unsafe {
+      let controller = webview.controller();
+      let webview = controller.CoreWebView2().unwrap();
+      webview.Reload().unwrap();
+    }
+  })?;
+
+  Ok(())
+}
+
+/// secs -> 0なら自動更新しない
+pub fn set_refresh_interval(app: &AppHandle, label: &str, secs: u32) {
+  let state = app.state::<AppState>();
+  if secs == 0 {
+    state.scheduler.cancel(label, TaskKind::Refresh);
+    return;
+  }
+
+  state.scheduler.repeat(label, TaskKind::Refresh, Duration::from_secs(secs.into()), {
+    let app = app.clone();
+    let label = label.to_string();
+    move || {
+      if let Some(window) = app.get_webview_window(&label) {
+        if let Err(e) = reload(&window) {
+          println!("failed to refresh {}: {}", label, e);
+        }
+      }
+    }
+  });
+}
+
+/// レンダラーのクラッシュとナビゲーションの失敗を監視する
+pub fn attach_watchdog(app: &AppHandle, window: &WebviewWindow) -> anyhow::Result<()> {
+  let label = window.label().to_string();
+  window.with_webview({
+    let app = app.clone();
+    move |webview| {
+      #[cfg(windows)]
+      unsafe {
+        let controller = webview.controller();
+        let webview = controller.CoreWebView2().unwrap();
+        let mut token = EventRegistrationToken::default();
+
+        webview
+          .add_ProcessFailed(
+            &ProcessFailedEventHandler::create(Box::new({
+              let app = app.clone();
+              let label = label.clone();
+              move |_, args| {
+                let Some(args) = args else { return Ok(()) };
+                let mut kind = COREWEBVIEW2_PROCESS_FAILED_KIND::default();
+                args.ProcessFailedKind(&mut kind)?;
+                println!("process failed: {:?}", kind);
+                report_failure(&app, &label);
+
+                Ok(())
+              }
+            })),
+            &mut token,
+          )
+          .unwrap();
+
+        webview
+          .add_NavigationCompleted(
+            &NavigationCompletedEventHandler::create(Box::new(move |_, args| {
+              let Some(args) = args else { return Ok(()) };
+              let mut success = BOOL::default();
+              args.IsSuccess(&mut success)?;
+              let mut status = COREWEBVIEW2_WEB_ERROR_STATUS::default();
+              args.WebErrorStatus(&mut status)?;
+
+              if success.as_bool() {
+                schedule_blank_check(&app, &label);
+              } else if status != COREWEBVIEW2_WEB_ERROR_STATUS_OPERATION_CANCELED {
+                // 別のページへの移動でキャンセルされた場合は失敗にしない
+                println!("navigation failed: {:?}", status);
+                report_failure(&app, &label);
+              }
+
+              Ok(())
+            })),
+            &mut token,
+          )
+          .unwrap();
+      }
+    }
+  })?;
+
+  Ok(())
+}
+
+/// 失敗回数に応じて待ってからリロードする
+pub fn report_failure(app: &AppHandle, label: &str) {
+  let state = app.state::<AppState>();
+  let Some(window_data) = state.get_window_data(label) else { return };
+  let watchdog = Arc::clone(&window_data.watchdog);
+  if !watchdog.0.load(Ordering::Acquire) {
+    return;
+  }
+
+  let attempt = watchdog.1.fetch_add(1, Ordering::AcqRel) + 1;
+  let delay = backoff(attempt, WATCHDOG_BACKOFF_BASE, WATCHDOG_BACKOFF_MAX);
+  println!("watchdog: reload {} in {:?} (attempt {})", label, delay, attempt);
+
+  state.scheduler.once(label, TaskKind::Watchdog, delay, {
+    let app = app.clone();
+    let label = label.to_string();
+    move || {
+      if let Some(window) = app.get_webview_window(&label) {
+        if let Err(e) = reload(&window) {
+          println!("failed to reload {}: {}", label, e);
+        }
+      }
+    }
+  });
+}
+
+fn report_success(app: &AppHandle, label: &str) {
+  let state = app.state::<AppState>();
+  if let Some(window_data) = state.get_window_data(label) {
+    window_data.watchdog.1.store(0, Ordering::Release);
+  }
+}
+
+fn schedule_blank_check(app: &AppHandle, label: &str) {
+  let state = app.state::<AppState>();
+  let Some(window_data) = state.get_window_data(label) else { return };
+  if !window_data.watchdog.0.load(Ordering::Acquire) {
+    return;
+  }
+
+  state.scheduler.once(label, TaskKind::BlankCheck, BLANK_CHECK_DELAY, {
+    let app = app.clone();
+    let label = label.to_string();
+    move || {
+      if let Err(e) = check_blank(&app, &label) {
+        println!("failed to check page: {}", e);
+      }
+    }
+  });
+}
+
+fn check_blank(app: &AppHandle, label: &str) -> anyhow::Result<()> {
+  let window = app.get_webview_window(label).context("window is not found")?;
+  window.with_webview({
+    let app = app.clone();
+    let label = label.to_string();
+    move |webview| {
+      #[cfg(windows)]
+      unsafe {
+        let controller = webview.controller();
+        let webview = controller.CoreWebView2().unwrap();
+        webview
+          .ExecuteScript(
+            &HSTRING::from(BLANK_CHECK_SCRIPT),
+            &ExecuteScriptCompletedHandler::create(Box::new(move |res, result| {
+              // 実行できなかった場合もクラッシュとして扱う
+              if res.is_err() || result.trim() == "0" {
+                report_failure(&app, &label);
+              } else {
+                report_success(&app, &label);
+              }
+
+              Ok(())
+            })),
+          )
+          .unwrap();
+      }
+    }
+  })?;
+
+  Ok(())
+}
+
+pub mod command {
+  use std::sync::{atomic::Ordering, Arc};
+
+  use specta::specta;
+  use tauri::{command, AppHandle, State, WebviewWindow};
+
+  use crate::{
+    util::{AppState, TaskKind},
+    view::util::ctrl_to_window_and_data,
+  };
+
+  /// secs -> 0で自動更新を止める
+  #[command]
+  #[specta]
+  pub fn set_refresh_interval(app: AppHandle, ctrl: WebviewWindow, state: State<'_, AppState>, secs: u32) -> Result<(), String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    super::set_refresh_interval(&app, window.label(), secs);
+    window_data.refresh.store(secs, Ordering::Release);
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_refresh_interval(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<u32, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    Ok(window_data.refresh.load(Ordering::Acquire))
+  }
+
+  #[command]
+  #[specta]
+  pub fn toggle_watchdog(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let condition = window_data.watchdog.0.load(Ordering::Acquire);
+
+    set_watchdog(ctrl, state, !condition)?;
+
+    Ok(!condition)
+  }
+
+  #[command]
+  #[specta]
+  pub fn set_watchdog(ctrl: WebviewWindow, state: State<'_, AppState>, value: bool) -> Result<(), String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let atomic = Arc::clone(&window_data.watchdog);
+
+    atomic.0.store(value, Ordering::Release);
+    atomic.1.store(0, Ordering::Release);
+    if !value {
+      state.scheduler.cancel(window.label(), TaskKind::Watchdog);
+      state.scheduler.cancel(window.label(), TaskKind::BlankCheck);
+    }
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_watchdog(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    Ok(window_data.watchdog.0.load(Ordering::Acquire))
+  }
+}
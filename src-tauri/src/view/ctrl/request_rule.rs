@@ -0,0 +1,95 @@
+pub mod rules;
+
+use tauri::Url;
+use webview2_com::Microsoft::Web::WebView2::Win32::{ICoreWebView2HttpRequestHeaders, ICoreWebView2WebResourceRequest};
+use windows::{core::HSTRING, Win32::Foundation::BOOL};
+
+use crate::util::{AppState, WindowData};
+
+use rules::{resolve, HeaderAction};
+
+/// Conf.request_rulesとオーバーレイのルールでリクエストヘッダーを書き換える
+/// User-Agentもヘッダーのみ変わるのでnavigator.userAgentには反映されない
+pub(super) unsafe fn rewrite_headers(
+  state: &AppState,
+  window_data: &WindowData,
+  request: &ICoreWebView2WebResourceRequest,
+  url: &str,
+) -> windows::core::Result<()> {
+  let Ok(url) = Url::parse(url) else { return Ok(()) };
+  let actions = {
+    let overlay = window_data.request_rules.lock().unwrap();
+    resolve(&state.config.read().unwrap().request_rules, &overlay, &url)
+  };
+  if !actions.is_empty() {
+    apply(&request.Headers()?, &actions)?;
+  }
+
+  Ok(())
+}
+
+unsafe fn apply(headers: &ICoreWebView2HttpRequestHeaders, actions: &[HeaderAction]) -> windows::core::Result<()> {
+  for action in actions {
+    match action {
+      HeaderAction::Add { name, value } => {
+        let mut contains = BOOL::default();
+        headers.Contains(&HSTRING::from(name), &mut contains)?;
+        if !contains.as_bool() {
+          headers.SetHeader(&HSTRING::from(name), &HSTRING::from(value))?;
+        }
+      }
+      HeaderAction::Set { name, value } => headers.SetHeader(&HSTRING::from(name), &HSTRING::from(value))?,
+      HeaderAction::Remove { name } => headers.RemoveHeader(&HSTRING::from(name))?,
+    }
+  }
+
+  Ok(())
+}
+
+pub mod command {
+  use configu::Configurable;
+  use specta::specta;
+  use tauri::{command, State, WebviewWindow};
+
+  use crate::{
+    util::{AppState, ErrToString},
+    view::util::ctrl_to_window_and_data,
+  };
+
+  use super::rules::RequestRule;
+
+  /// オーバーレイだけに適用するルール
+  #[command]
+  #[specta]
+  pub fn set_request_rules(ctrl: WebviewWindow, state: State<'_, AppState>, rules: Vec<RequestRule>) -> Result<(), String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    *window_data.request_rules.lock().unwrap() = rules;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_request_rules(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<Vec<RequestRule>, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let rules = window_data.request_rules.lock().unwrap().clone();
+
+    Ok(rules)
+  }
+
+  /// すべてのオーバーレイに適用するルール
+  #[command]
+  #[specta]
+  pub fn set_global_request_rules(state: State<'_, AppState>, rules: Vec<RequestRule>) -> Result<(), String> {
+    state.config.write().unwrap().request_rules = rules;
+    state.config.save().err_to_string()?;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_global_request_rules(state: State<'_, AppState>) -> Vec<RequestRule> {
+    state.config.read().unwrap().request_rules.clone()
+  }
+}
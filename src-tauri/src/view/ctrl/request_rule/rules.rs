@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use url::Url;
+
+use crate::view::ctrl::navigation::policy::matches_pattern;
+
+pub const USER_AGENT_HEADER: &str = "User-Agent";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+#[serde(tag = "action")]
+pub enum HeaderAction {
+  /// リクエストにヘッダーがない場合のみ追加する
+  Add {
+    name: String,
+    value: String,
+  },
+  /// 追加か上書き
+  Set {
+    name: String,
+    value: String,
+  },
+  Remove {
+    name: String,
+  },
+}
+
+impl HeaderAction {
+  pub fn name(&self) -> &str {
+    match self {
+      Self::Add { name, .. } | Self::Set { name, .. } | Self::Remove { name } => name,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct RequestRule {
+  /// navigation::policy::matches_patternと同じ書き方、`*`はすべてのurl
+  pub domain: String,
+  #[serde(default)]
+  pub user_agent: Option<String>,
+  #[serde(default)]
+  pub headers: Vec<HeaderAction>,
+}
+
+impl RequestRule {
+  pub fn matches(&self, url: &Url) -> bool {
+    self.domain.trim() == "*" || matches_pattern(&self.domain, url)
+  }
+
+  /// 大きいほど優先される
+  /// `*` < ワイルドカードを含むホスト < サブドメインを含むドメイン < 完全一致したホスト < url全体のパターン
+  /// 同じ段階なら`*`以外の文字数が多い方
+  pub fn specificity(&self, url: &Url) -> (u8, usize) {
+    let pattern = self.domain.trim().to_ascii_lowercase();
+    let len = pattern.chars().filter(|c| *c != '*').count();
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+
+    let tier = if pattern == "*" {
+      0
+    } else if pattern.contains("://") {
+      4
+    } else if pattern.contains('*') {
+      1
+    } else if pattern == host {
+      3
+    } else {
+      2
+    };
+
+    (tier, len)
+  }
+
+  fn actions(&self) -> impl Iterator<Item = HeaderAction> + '_ {
+    let user_agent = self.user_agent.iter().map(|v| HeaderAction::Set {
+      name: USER_AGENT_HEADER.into(),
+      value: v.clone(),
+    });
+
+    user_agent.chain(self.headers.iter().cloned())
+  }
+}
+
+/// urlに一致するルールを優先度の低い順に適用し、ヘッダーごとの最終的な操作を返す
+/// オーバーレイのルールはグローバルのルールより優先される
+/// 同じ優先度なら後に定義したルールが優先される
+pub fn resolve(global: &[RequestRule], overlay: &[RequestRule], url: &Url) -> Vec<HeaderAction> {
+  let mut actions: Vec<HeaderAction> = Vec::new();
+  for rule in matching(global, url).into_iter().chain(matching(overlay, url)) {
+    for action in rule.actions() {
+      match actions.iter_mut().find(|v| v.name().eq_ignore_ascii_case(action.name())) {
+        Some(v) => *v = action,
+        None => actions.push(action),
+      }
+    }
+  }
+
+  actions
+}
+
+/// 一致するルールを優先度の低い順に並べる
+fn matching<'a>(rules: &'a [RequestRule], url: &Url) -> Vec<&'a RequestRule> {
+  let mut rules = rules.iter().filter(|v| v.matches(url)).collect::<Vec<_>>();
+  rules.sort_by_key(|v| v.specificity(url));
+  rules
+}
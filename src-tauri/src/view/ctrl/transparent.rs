@@ -1,16 +1,28 @@
 use windows::Win32::{
   Foundation::{COLORREF, HWND},
-  UI::WindowsAndMessaging::{SetLayeredWindowAttributes, LWA_ALPHA},
+  UI::WindowsAndMessaging::{SetLayeredWindowAttributes, LWA_ALPHA, LWA_COLORKEY},
 };
 
-pub fn set_transparent(hwnd: HWND, alpha: u8) -> anyhow::Result<()> {
+/// color_key -> 0xRRGGBB、この色の部分はデスクトップが透けて見える
+pub fn set_transparent(hwnd: HWND, alpha: u8, color_key: Option<u32>) -> anyhow::Result<()> {
+  let (key, flags) = match color_key {
+    Some(color) => (rgb_to_colorref(color), LWA_ALPHA | LWA_COLORKEY),
+    None => (COLORREF(0), LWA_ALPHA),
+  };
+
   unsafe {
-    SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA)?;
+    SetLayeredWindowAttributes(hwnd, key, alpha, flags)?;
   };
 
   Ok(())
 }
 
+/// 0xRRGGBB -> 0x00BBGGRR
+pub fn rgb_to_colorref(color: u32) -> COLORREF {
+  let (r, g, b) = ((color >> 16) & 0xff, (color >> 8) & 0xff, color & 0xff);
+  COLORREF(r | (g << 8) | (b << 16))
+}
+
 pub mod command {
   use std::sync::{atomic::Ordering, Arc};
 
@@ -41,9 +53,12 @@ pub mod command {
     let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
     let atomic = Arc::clone(&window_data.transparent);
 
-    super::set_transparent(window.hwnd().unwrap(), alpha).err_to_string()?;
+    super::set_transparent(window.hwnd().unwrap(), alpha, window_data.color_key()).err_to_string()?;
 
     atomic.0.store(alpha != 255, Ordering::Release);
+    if alpha != 255 {
+      atomic.1.store(alpha, Ordering::Release);
+    }
 
     Ok(())
   }
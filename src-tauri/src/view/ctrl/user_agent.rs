@@ -1,19 +1,55 @@
+pub mod mobile_host;
+pub mod preset;
+
 use crate::util::AppState;
 
+use anyhow::Context;
 use tauri::{State, WebviewWindow};
-use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2Settings2;
+use webview2_com::{CallDevToolsProtocolMethodCompletedHandler, Microsoft::Web::WebView2::Win32::ICoreWebView2Settings2};
 use windows::core::{Interface, HSTRING};
 
-/// true -> mobile
-/// false -> desktop
-pub fn set_user_agent(window: WebviewWindow, state: State<'_, AppState>, value: bool) -> anyhow::Result<()> {
-  let (desktop, mobile) = {
+use mobile_host::rewrite_url;
+use preset::{find_preset, UserAgentPreset};
+
+/// プリセットのUAとクライアントヒントを設定してページを再読み込みする
+/// モバイル用のホストがあるサイトはそちらへ移動する
+pub fn set_user_agent(window: WebviewWindow, state: State<'_, AppState>, preset_id: &str) -> anyhow::Result<UserAgentPreset> {
+  let preset = override_user_agent(&window, &state, preset_id)?;
+  let rewritten = {
     let config = state.config.read().unwrap();
-    (config.agent_desktop.clone(), config.agent_mobile.clone())
+    rewrite_url(&config.host_rewrites, &window.url()?, preset.mobile).map(|v| HSTRING::from(v.as_str()))
   };
 
-  let desktop = HSTRING::from(desktop);
-  let mobile = HSTRING::from(mobile);
+  window.with_webview(move |webview| {
+    #[cfg(windows)]
+    unsafe {
+      let controller = webview.controller();
+      let webview = controller.CoreWebView2().unwrap();
+      match rewritten {
+        Some(url) => webview.Navigate(&url).unwrap(),
+        None => webview.Reload().unwrap(),
+      }
+    }
+  })?;
+
+  Ok(preset)
+}
+
+/// プリセットとそのUA、UAは作る前のオーバーレイのbuilderにも渡す
+pub fn preset_user_agent(state: &AppState, preset_id: &str) -> anyhow::Result<(UserAgentPreset, String)> {
+  let config = state.config.read().unwrap();
+  let preset = find_preset(&config.agent_presets, preset_id).with_context(|| format!("preset {} is not found", preset_id))?;
+  let user_agent = preset.user_agent(&config.agent_desktop, &config.agent_mobile).to_string();
+
+  Ok((preset, user_agent))
+}
+
+/// プリセットのUAとクライアントヒントだけを設定する、ページは読み込み直さず移動もしない
+pub fn override_user_agent(window: &WebviewWindow, state: &AppState, preset_id: &str) -> anyhow::Result<UserAgentPreset> {
+  let (preset, user_agent) = preset_user_agent(state, preset_id)?;
+
+  let params = HSTRING::from(preset.override_params(&user_agent).to_string());
+  let user_agent = HSTRING::from(user_agent);
 
   window.with_webview(move |webview| {
     #[cfg(windows)]
@@ -21,12 +57,29 @@ pub fn set_user_agent(window: WebviewWindow, state: State<'_, AppState>, value:
       let controller = webview.controller();
       let webview = controller.CoreWebView2().unwrap();
       let settings_2: ICoreWebView2Settings2 = webview.Settings().unwrap().cast().unwrap();
-      settings_2.SetUserAgent(&if value { mobile } else { desktop }).unwrap();
-      webview.Reload().unwrap();
+      settings_2.SetUserAgent(&user_agent).unwrap();
+      // クライアントヒントとnavigator.platformはDevTools経由でしか変えられない
+      webview
+        .CallDevToolsProtocolMethod(
+          &HSTRING::from("Emulation.setUserAgentOverride"),
+          &params,
+          &CallDevToolsProtocolMethodCompletedHandler::create(Box::new(|res, _| {
+            if let Err(e) = res {
+              println!("failed to override user agent: {}", e);
+            }
+            Ok(())
+          })),
+        )
+        .unwrap();
     }
   })?;
 
-  Ok(())
+  Ok(preset)
+}
+
+/// オーバーレイで選択しているプリセット
+pub fn current_preset(state: &AppState, preset_id: &str) -> Option<UserAgentPreset> {
+  find_preset(&state.config.read().unwrap().agent_presets, preset_id)
 }
 
 pub mod command {
@@ -34,41 +87,111 @@ pub mod command {
     util::{AppState, ErrToString},
     view::util::ctrl_to_window_and_data,
   };
+  use configu::Configurable;
   use specta::specta;
-  use std::sync::{atomic::Ordering, Arc};
+  use std::sync::atomic::Ordering;
   use tauri::{command, State, WebviewWindow};
 
+  use crate::view::ctrl::emulation;
+
+  use super::{
+    current_preset,
+    mobile_host::{builtin_host_rewrites, HostRewrite},
+    preset::{all_presets, UserAgentPreset, DESKTOP_PRESET, MOBILE_PRESET},
+  };
+
+  /// モバイルのプリセットならデスクトップに、それ以外ならモバイルに切り替える
   #[command]
   #[specta]
   pub fn toggle_user_agent(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
     let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
-    let atomic = Arc::clone(&window_data.mobile_mode);
-    let condition = atomic.load(Ordering::Acquire);
+    let preset_id = window_data.agent_preset.lock().unwrap().clone();
+    let condition = current_preset(&state, &preset_id).is_some_and(|v| v.mobile);
 
     set_user_agent(ctrl, state, !condition)?;
 
     Ok(!condition)
   }
 
+  /// true -> MOBILE_PRESET
+  /// false -> DESKTOP_PRESET
   #[command]
   #[specta]
-  // todo:モバイル用サイトのドメインを切り替える
   pub fn set_user_agent(ctrl: WebviewWindow, state: State<'_, AppState>, value: bool) -> Result<(), String> {
-    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
-    let atomic = Arc::clone(&window_data.mobile_mode);
-
-    super::set_user_agent(window, state, value).err_to_string()?;
-    atomic.store(value, Ordering::Release);
+    set_agent_preset(ctrl, state, if value { MOBILE_PRESET } else { DESKTOP_PRESET }.into())?;
 
     Ok(())
   }
 
+  /// モバイルのプリセットを選択しているか
   #[command]
   #[specta]
   pub fn get_user_agent(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
     let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
-    let atomic = Arc::clone(&window_data.mobile_mode);
+    let preset_id = window_data.agent_preset.lock().unwrap().clone();
+
+    Ok(current_preset(&state, &preset_id).is_some_and(|v| v.mobile))
+  }
+
+  #[command]
+  #[specta]
+  pub fn set_agent_preset(ctrl: WebviewWindow, state: State<'_, AppState>, id: String) -> Result<UserAgentPreset, String> {
+    let (window, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+
+    let preset = super::set_user_agent(window.clone(), state.clone(), &id).err_to_string()?;
+    *window_data.agent_preset.lock().unwrap() = id;
+    // エミュレーション中なら新しいプリセットの画面に合わせる、できなければ解除する
+    if window_data.emulation.load(Ordering::Acquire) {
+      emulation::set_emulation(&window, &state, &window_data, preset.can_emulate()).err_to_string()?;
+    }
+
+    Ok(preset)
+  }
+
+  #[command]
+  #[specta]
+  pub fn get_agent_preset(ctrl: WebviewWindow, state: State<'_, AppState>) -> Result<String, String> {
+    let (_, window_data) = ctrl_to_window_and_data(&ctrl, &state)?;
+    let preset_id = window_data.agent_preset.lock().unwrap().clone();
 
-    Ok(atomic.load(Ordering::Acquire))
+    Ok(preset_id)
+  }
+
+  /// 組み込みのプリセットとConf.agent_presets
+  #[command]
+  #[specta]
+  pub fn list_agent_presets(state: State<'_, AppState>) -> Vec<UserAgentPreset> {
+    all_presets(&state.config.read().unwrap().agent_presets)
+  }
+
+  /// Conf.agent_presetsを置き換える、組み込みと同じidなら上書きになる
+  #[command]
+  #[specta]
+  pub fn set_custom_agent_presets(state: State<'_, AppState>, presets: Vec<UserAgentPreset>) -> Result<(), String> {
+    for preset in &presets {
+      preset.validate().err_to_string()?;
+    }
+    state.config.write().unwrap().agent_presets = presets;
+    state.config.save().err_to_string()?;
+
+    Ok(())
+  }
+
+  /// Conf.host_rewritesと組み込みの対応、前にあるものが優先される
+  #[command]
+  #[specta]
+  pub fn list_host_rewrites(state: State<'_, AppState>) -> Vec<HostRewrite> {
+    let mut rewrites = state.config.read().unwrap().host_rewrites.clone();
+    rewrites.extend(builtin_host_rewrites());
+    rewrites
+  }
+
+  #[command]
+  #[specta]
+  pub fn set_custom_host_rewrites(state: State<'_, AppState>, rewrites: Vec<HostRewrite>) -> Result<(), String> {
+    state.config.write().unwrap().host_rewrites = rewrites;
+    state.config.save().err_to_string()?;
+
+    Ok(())
   }
 }
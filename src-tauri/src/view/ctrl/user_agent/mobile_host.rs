@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use url::Url;
+
+/// デスクトップ用とモバイル用のホストの対応
+/// `*.wikipedia.org` <-> `*.m.wikipedia.org`のように先頭の`*`でサブドメイン1つを引き継げる
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct HostRewrite {
+  pub desktop: String,
+  pub mobile: String,
+}
+
+impl HostRewrite {
+  fn new(desktop: &str, mobile: &str) -> Self {
+    Self {
+      desktop: desktop.into(),
+      mobile: mobile.into(),
+    }
+  }
+}
+
+pub fn builtin_host_rewrites() -> Vec<HostRewrite> {
+  vec![
+    HostRewrite::new("www.youtube.com", "m.youtube.com"),
+    HostRewrite::new("twitter.com", "mobile.twitter.com"),
+    HostRewrite::new("x.com", "mobile.x.com"),
+    HostRewrite::new("www.facebook.com", "m.facebook.com"),
+    HostRewrite::new("www.reddit.com", "m.reddit.com"),
+    HostRewrite::new("www.twitch.tv", "m.twitch.tv"),
+    HostRewrite::new("*.wikipedia.org", "*.m.wikipedia.org"),
+  ]
+}
+
+/// patternに一致すれば`*`に当たるラベルを返す(`*`がなければ空文字)
+fn match_host<'a>(pattern: &str, host: &'a str) -> Option<&'a str> {
+  match pattern.strip_prefix("*.") {
+    Some(suffix) => {
+      let label = host.strip_suffix(suffix)?.strip_suffix('.')?;
+      (!label.is_empty() && !label.contains('.')).then_some(label)
+    }
+    None => pattern.eq_ignore_ascii_case(host).then_some(""),
+  }
+}
+
+fn replace_host(pattern: &str, label: &str) -> String {
+  match pattern.strip_prefix('*') {
+    Some(rest) => format!("{}{}", label, rest),
+    None => pattern.to_string(),
+  }
+}
+
+/// mobile -> trueならモバイル用のホストに、falseならデスクトップ用のホストに書き換える
+/// Conf.host_rewritesを組み込みの対応より優先する、書き換えが不要ならNone
+pub fn rewrite_url(custom: &[HostRewrite], url: &Url, mobile: bool) -> Option<Url> {
+  let host = url.host_str()?.to_ascii_lowercase();
+
+  let builtin = builtin_host_rewrites();
+  for rewrite in custom.iter().chain(builtin.iter()) {
+    let (from, to) = if mobile {
+      (&rewrite.desktop, &rewrite.mobile)
+    } else {
+      (&rewrite.mobile, &rewrite.desktop)
+    };
+    let from = from.trim().to_ascii_lowercase();
+    let to = to.trim().to_ascii_lowercase();
+
+    // 既に切り替え先のホストなら何もしない
+    if match_host(&to, &host).is_some() {
+      return None;
+    }
+    if let Some(label) = match_host(&from, &host) {
+      let mut url = url.clone();
+      url.set_host(Some(&replace_host(&to, label))).ok()?;
+      return Some(url);
+    }
+  }
+
+  None
+}
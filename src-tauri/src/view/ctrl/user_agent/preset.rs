@@ -0,0 +1,250 @@
+use anyhow::ensure;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use specta::Type;
+
+/// webview既定のUA(Conf.agent_desktop)
+pub const DESKTOP_PRESET: &str = "desktop";
+/// toggle_user_agentで切り替えるモバイルのプリセット、UAはConf.agent_mobile
+pub const MOBILE_PRESET: &str = "android_chrome";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct Brand {
+  pub brand: String,
+  pub version: String,
+}
+
+/// Sec-CH-UA-*とnavigator.userAgentDataに使われる値
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct ClientHints {
+  pub brands: Vec<Brand>,
+  pub platform: String,
+  pub platform_version: String,
+  #[serde(default)]
+  pub architecture: String,
+  #[serde(default)]
+  pub model: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Type)]
+pub struct UserAgentPreset {
+  pub id: String,
+  pub name: String,
+  /// 空ならmobileに応じてConf.agent_desktopかConf.agent_mobileを使う
+  #[serde(default)]
+  pub user_agent: String,
+  pub mobile: bool,
+  /// CSSピクセルでの幅、0ならウィンドウの幅に合わせる
+  pub viewport_width: u32,
+  /// エミュレーション時の縦横比に使う、0ならエミュレーションできない
+  #[serde(default)]
+  pub viewport_height: u32,
+  pub device_scale_factor: f64,
+  /// navigator.platform
+  pub platform: String,
+  /// Noneならクライアントヒントを送らない(Safari,Firefox)
+  #[serde(default)]
+  pub client_hints: Option<ClientHints>,
+}
+
+impl UserAgentPreset {
+  pub fn validate(&self) -> anyhow::Result<()> {
+    ensure!(!self.id.trim().is_empty(), "preset id is empty");
+    ensure!(
+      self.device_scale_factor > 0.0 && self.device_scale_factor <= 10.0,
+      "device scale factor must be in 0-10"
+    );
+    ensure!(
+      self.viewport_width <= 10000 && self.viewport_height <= 10000,
+      "viewport size is too large"
+    );
+
+    Ok(())
+  }
+
+  pub fn user_agent<'a>(&'a self, desktop: &'a str, mobile: &'a str) -> &'a str {
+    match (self.user_agent.is_empty(), self.mobile) {
+      (false, _) => &self.user_agent,
+      (true, false) => desktop,
+      (true, true) => mobile,
+    }
+  }
+
+  pub fn can_emulate(&self) -> bool {
+    self.viewport_width > 0 && self.viewport_height > 0
+  }
+
+  /// zoom(%)のときにviewport_widthがちょうど収まるウィンドウの内側の論理サイズ
+  pub fn window_size(&self, zoom: u32) -> Option<(f64, f64)> {
+    self.can_emulate().then(|| {
+      let scale = zoom as f64 / 100.0;
+      (self.viewport_width as f64 * scale, self.viewport_height as f64 * scale)
+    })
+  }
+
+  /// ウィンドウの幅からviewport_widthが収まるzoom(%)と縦横比を保った高さを求める
+  pub fn fit_width(&self, width: f64) -> Option<(u32, f64)> {
+    self.can_emulate().then(|| {
+      let zoom = (width / self.viewport_width as f64 * 100.0).round().clamp(20.0, 500.0) as u32;
+      (zoom, width * self.viewport_height as f64 / self.viewport_width as f64)
+    })
+  }
+
+  /// DevToolsのEmulation.setUserAgentOverrideに渡す値
+  /// webview既定のUAを使うプリセットは空のUAを渡して上書きを解除する
+  pub fn override_params(&self, user_agent: &str) -> Value {
+    if self.id == DESKTOP_PRESET && self.user_agent.is_empty() {
+      return json!({ "userAgent": "" });
+    }
+
+    let mut params = json!({
+      "userAgent": user_agent,
+      "platform": self.platform,
+    });
+    if let Some(hints) = &self.client_hints {
+      params["userAgentMetadata"] = json!({
+        "brands": hints.brands,
+        "platform": hints.platform,
+        "platformVersion": hints.platform_version,
+        "architecture": hints.architecture,
+        "model": hints.model,
+        "mobile": self.mobile,
+      });
+    }
+
+    params
+  }
+}
+
+fn brands(version: &str) -> Vec<Brand> {
+  [("Chromium", version), ("Google Chrome", version), ("Not;A=Brand", "24")]
+    .into_iter()
+    .map(|(brand, version)| Brand {
+      brand: brand.into(),
+      version: version.into(),
+    })
+    .collect()
+}
+
+pub fn builtin_presets() -> Vec<UserAgentPreset> {
+  let safari = |id: &str, name: &str, device: &str, (width, height): (u32, u32), scale: f64| UserAgentPreset {
+    id: id.into(),
+    name: name.into(),
+    user_agent: format!(
+      "Mozilla/5.0 ({}; CPU {}OS 17_6 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.6 Mobile/15E148 Safari/604.1",
+      device,
+      if device == "iPhone" { "iPhone " } else { "" }
+    ),
+    mobile: true,
+    viewport_width: width,
+    viewport_height: height,
+    device_scale_factor: scale,
+    platform: device.into(),
+    client_hints: None,
+  };
+
+  vec![
+    UserAgentPreset {
+      id: DESKTOP_PRESET.into(),
+      name: "Desktop (default)".into(),
+      user_agent: String::new(),
+      mobile: false,
+      viewport_width: 0,
+      viewport_height: 0,
+      device_scale_factor: 1.0,
+      platform: "Win32".into(),
+      client_hints: None,
+    },
+    UserAgentPreset {
+      id: "desktop_chrome".into(),
+      name: "Desktop Chrome".into(),
+      user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36".into(),
+      mobile: false,
+      viewport_width: 0,
+      viewport_height: 0,
+      device_scale_factor: 1.0,
+      platform: "Win32".into(),
+      client_hints: Some(ClientHints {
+        brands: brands("128"),
+        platform: "Windows".into(),
+        platform_version: "15.0.0".into(),
+        architecture: "x86".into(),
+        model: String::new(),
+      }),
+    },
+    UserAgentPreset {
+      id: "desktop_firefox".into(),
+      name: "Desktop Firefox".into(),
+      user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:130.0) Gecko/20100101 Firefox/130.0".into(),
+      mobile: false,
+      viewport_width: 0,
+      viewport_height: 0,
+      device_scale_factor: 1.0,
+      platform: "Win32".into(),
+      client_hints: None,
+    },
+    safari("iphone_safari", "iPhone Safari", "iPhone", (393, 852), 3.0),
+    safari("ipad_safari", "iPad Safari", "iPad", (820, 1180), 2.0),
+    UserAgentPreset {
+      id: MOBILE_PRESET.into(),
+      name: "Android Chrome".into(),
+      user_agent: String::new(),
+      mobile: true,
+      viewport_width: 412,
+      viewport_height: 915,
+      device_scale_factor: 2.625,
+      platform: "Linux armv81".into(),
+      client_hints: Some(ClientHints {
+        brands: brands("116"),
+        platform: "Android".into(),
+        platform_version: "13.0.0".into(),
+        architecture: String::new(),
+        model: "Pixel 7".into(),
+      }),
+    },
+  ]
+}
+
+/// 組み込みのプリセットとConf.agent_presets、同じidならConfの方を使う
+pub fn all_presets(custom: &[UserAgentPreset]) -> Vec<UserAgentPreset> {
+  let mut presets = builtin_presets();
+  for preset in custom {
+    match presets.iter_mut().find(|v| v.id == preset.id) {
+      Some(v) => *v = preset.clone(),
+      None => presets.push(preset.clone()),
+    }
+  }
+
+  presets
+}
+
+pub fn find_preset(custom: &[UserAgentPreset], id: &str) -> Option<UserAgentPreset> {
+  custom
+    .iter()
+    .find(|v| v.id == id)
+    .cloned()
+    .or_else(|| builtin_presets().into_iter().find(|v| v.id == id))
+}
+
+pub fn default_preset() -> Box<str> {
+  DESKTOP_PRESET.into()
+}
+
+/// 以前のmobile_mode(bool)をプリセットのidに変換する
+pub fn migrate_preset<'de, D>(deserializer: D) -> Result<Box<str>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Value {
+    MobileMode(bool),
+    Preset(Box<str>),
+  }
+
+  Ok(match Value::deserialize(deserializer)? {
+    Value::MobileMode(true) => MOBILE_PRESET.into(),
+    Value::MobileMode(false) => DESKTOP_PRESET.into(),
+    Value::Preset(v) => v,
+  })
+}
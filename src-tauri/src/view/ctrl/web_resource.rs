@@ -0,0 +1,60 @@
+use tauri::{AppHandle, Manager, WebviewWindow};
+use webview2_com::{
+  take_pwstr,
+  Microsoft::Web::WebView2::Win32::{ICoreWebView2_2, COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL},
+  WebResourceRequestedEventHandler,
+};
+use windows::{
+  core::{Interface, HSTRING, PWSTR},
+  Win32::System::WinRT::EventRegistrationToken,
+};
+
+use crate::util::AppState;
+
+use super::{blocker, request_rule};
+
+/// WebResourceRequestedのフィルターとハンドラーはwebviewごとに1つにまとめて、ブロックとヘッダーの書き換えを順に行う
+/// ブロックしたリクエストはヘッダーを書き換えない
+pub fn attach_web_resource_handler(app: &AppHandle, window: &WebviewWindow) -> anyhow::Result<()> {
+  let label = window.label().to_string();
+  window.with_webview({
+    let app = app.clone();
+    move |webview| {
+      #[cfg(windows)]
+      unsafe {
+        let controller = webview.controller();
+        let webview = controller.CoreWebView2().unwrap();
+        let environment = webview.cast::<ICoreWebView2_2>().unwrap().Environment().unwrap();
+        let mut token = EventRegistrationToken::default();
+
+        webview
+          .AddWebResourceRequestedFilter(&HSTRING::from("*"), COREWEBVIEW2_WEB_RESOURCE_CONTEXT_ALL)
+          .unwrap();
+        webview
+          .add_WebResourceRequested(
+            &WebResourceRequestedEventHandler::create(Box::new(move |_, args| {
+              let Some(args) = args else { return Ok(()) };
+              let state = app.state::<AppState>();
+              let Some(window_data) = state.get_window_data(&label) else {
+                return Ok(());
+              };
+
+              let request = args.Request()?;
+              let mut uri = PWSTR::null();
+              request.Uri(&mut uri)?;
+              let url = take_pwstr(uri);
+
+              if blocker::block_request(&state, &window_data, &args, &environment, &url)? {
+                return Ok(());
+              }
+              request_rule::rewrite_headers(&state, &window_data, &request, &url)
+            })),
+            &mut token,
+          )
+          .unwrap();
+      }
+    }
+  })?;
+
+  Ok(())
+}
@@ -1,47 +1,268 @@
-pub mod util {}
+pub mod manifest;
+pub mod registry;
 
-pub mod command {
-  use crate::{
-    util::{AppState, ErrToString},
-    view::util::to_window,
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Manager, WebviewWindow};
+use webview2_com::{
+  take_pwstr, BrowserExtensionEnableCompletedHandler, BrowserExtensionRemoveCompletedHandler,
+  Microsoft::Web::WebView2::Win32::{ICoreWebView2Profile7, ICoreWebView2_13},
+  ProfileAddBrowserExtensionCompletedHandler, ProfileGetBrowserExtensionsCompletedHandler,
+};
+use windows::{
+  core::{Interface, HSTRING, PWSTR},
+  Win32::Foundation::BOOL,
+};
+
+use crate::util::AppState;
+
+use registry::{extension_dir, ExtensionRegistry};
+
+/// webviewのプロファイルに入っている拡張機能をレジストリに合わせる
+/// webview側では名前でしか区別できないので名前で照合する
+#[derive(Debug, Clone, Default)]
+struct ExtensionSync {
+  /// (名前, ディレクトリ)
+  enable: Vec<(String, PathBuf)>,
+  disable: Vec<String>,
+  remove: Vec<String>,
+  /// enable,disableにないものも取り除く
+  /// アンインストールしたときに開いていなかったプロファイルには残っているので、次に開いたときに消す
+  prune: bool,
+}
+
+impl ExtensionSync {
+  fn new(state: &AppState, profile: Option<&str>) -> anyhow::Result<Self> {
+    let registry = ExtensionRegistry::load(&state.dir)?;
+    let mut sync = Self {
+      prune: true,
+      ..Default::default()
+    };
+    for entry in registry.extensions {
+      if entry.is_enabled(profile) {
+        sync.enable.push((entry.name, extension_dir(&state.dir, &entry.id)));
+      } else {
+        sync.disable.push(entry.name);
+      }
+    }
+
+    Ok(sync)
+  }
+}
+
+/// 有効な拡張機能を読み込み、無効にしたものは止める
+pub fn load_extensions(app: &AppHandle, window: &WebviewWindow) -> anyhow::Result<()> {
+  let state = app.state::<AppState>();
+  let profile = state.get_window_data(window.label()).and_then(|v| v.profile);
+  let sync = ExtensionSync::new(&state, profile.as_deref())?;
+
+  apply(window, sync)
+}
+
+/// プロファイルが一致する開いているオーバーレイすべてに反映する
+/// profile -> Noneなら共有のデータディレクトリ
+fn apply_to_profile(app: &AppHandle, profile: Option<&str>) -> anyhow::Result<()> {
+  let state = app.state::<AppState>();
+  let sync = ExtensionSync::new(&state, profile)?;
+  for window in open_windows(app, |v| v == profile) {
+    apply(&window, sync.clone())?;
+  }
+
+  Ok(())
+}
+
+/// 開いているすべてのオーバーレイから取り除く
+fn remove_from_all(app: &AppHandle, name: &str) -> anyhow::Result<()> {
+  let sync = ExtensionSync {
+    remove: vec![name.to_string()],
+    ..Default::default()
   };
-  use anyhow::Context;
+  for window in open_windows(app, |_| true) {
+    apply(&window, sync.clone())?;
+  }
+
+  Ok(())
+}
+
+fn open_windows(app: &AppHandle, f: impl Fn(Option<&str>) -> bool) -> Vec<WebviewWindow> {
+  let state = app.state::<AppState>();
+  let labels = state
+    .windows
+    .lock()
+    .unwrap()
+    .iter()
+    .filter(|v| f(v.profile.as_deref()))
+    .map(|v| v.label.clone())
+    .collect::<Vec<_>>();
+
+  labels.into_iter().filter_map(|v| app.get_webview_window(&v)).collect()
+}
+
+fn apply(window: &WebviewWindow, sync: ExtensionSync) -> anyhow::Result<()> {
+  window.with_webview(move |webview| {
+    #[cfg(windows)]
+    unsafe {
+      let res = (|| -> windows::core::Result<()> {
+        let controller = webview.controller();
+        let webview = controller.CoreWebView2()?;
+        // 古いランタイムは拡張機能に対応していない
+        let Ok(profile) = webview
+          .cast::<ICoreWebView2_13>()
+          .and_then(|v| v.Profile())
+          .and_then(|v| v.cast::<ICoreWebView2Profile7>())
+        else {
+          println!("browser extensions are not supported by this WebView2 runtime");
+          return Ok(());
+        };
+
+        profile
+          .clone()
+          .GetBrowserExtensions(&ProfileGetBrowserExtensionsCompletedHandler::create(Box::new(move |res, list| {
+            res?;
+            let mut installed = Vec::new();
+            if let Some(list) = list {
+              let mut count = 0;
+              list.Count(&mut count)?;
+              for i in 0..count {
+                let extension = list.GetValueAtIndex(i)?;
+                let mut name = PWSTR::null();
+                extension.Name(&mut name)?;
+                let name = take_pwstr(name);
+
+                let enable = sync.enable.iter().any(|v| v.0 == name);
+                let known = enable || sync.disable.contains(&name);
+                if sync.remove.contains(&name) || (sync.prune && !known) {
+                  extension.Remove(&BrowserExtensionRemoveCompletedHandler::create(Box::new(|res| {
+                    if let Err(e) = res {
+                      println!("failed to remove extension: {}", e);
+                    }
+                    Ok(())
+                  })))?;
+                  continue;
+                }
+
+                if known {
+                  extension.Enable(
+                    BOOL::from(enable),
+                    &BrowserExtensionEnableCompletedHandler::create(Box::new(|res| {
+                      if let Err(e) = res {
+                        println!("failed to enable or disable extension: {}", e);
+                      }
+                      Ok(())
+                    })),
+                  )?;
+                }
+                installed.push(name);
+              }
+            }
+
+            for (name, dir) in sync.enable.into_iter().filter(|v| !installed.contains(&v.0)) {
+              profile.AddBrowserExtension(
+                &HSTRING::from(dir.as_os_str()),
+                &ProfileAddBrowserExtensionCompletedHandler::create(Box::new(move |res, _| {
+                  if let Err(e) = res {
+                    println!("failed to load extension {}: {}", name, e);
+                  }
+                  Ok(())
+                })),
+              )?;
+            }
+
+            Ok(())
+          })))
+      })();
+      if let Err(e) = res {
+        println!("failed to sync extensions: {}", e);
+      }
+    }
+  })?;
+
+  Ok(())
+}
+
+pub mod command {
+  use anyhow::bail;
   use specta::specta;
   use tauri::{command, AppHandle, State, WebviewWindow};
   use tauri_plugin_dialog::DialogExt;
-  use webview2_com::Microsoft::Web::WebView2::Win32::{ICoreWebView2Profile7, ICoreWebView2_13};
-  use windows::core::Interface;
 
+  use crate::util::{AppState, ErrToString};
+
+  use super::{
+    apply_to_profile,
+    registry::{ExtensionEntry, ExtensionRegistry},
+    remove_from_all,
+  };
+
+  #[command]
+  #[specta]
+  pub fn list_extensions(state: State<'_, AppState>) -> Result<Vec<ExtensionEntry>, String> {
+    let registry = ExtensionRegistry::load(&state.dir).err_to_string()?;
+
+    Ok(registry.extensions)
+  }
+
+  /// 展開済みの拡張機能のフォルダーを選んでインストールする、キャンセルしたらNone
+  /// 既に読み込んでいるオーバーレイには次に開いたときに反映される
   #[command]
   #[specta]
-  pub fn test(app: AppHandle, ctrl: WebviewWindow, _state: State<'_, AppState>) -> Result<(), String> {
-    let window = to_window(&ctrl)?;
-    window
-      .with_webview(move |webview| {
-        #[cfg(target_os = "windows")]
-        (|| -> anyhow::Result<()> {
-          unsafe {
-            let controller = webview.controller();
-            let webview = controller.CoreWebView2().unwrap();
-            let _webview_profile_7 = webview.cast::<ICoreWebView2_13>()?.Profile()?.cast::<ICoreWebView2Profile7>()?;
-
-            let _path = app
-              .dialog()
-              .file()
-              .blocking_pick_folder()
-              .context("failed to get dir path")
-              .unwrap();
-            // let res = webview_profile_7.AddBrowserExtension(path.to_str().unwrap().into(), handler);
-
-            // Ok(())
-            todo!()
-          }
-        })()
-        .unwrap();
-      })
-      .err_to_string()?;
-
-    println!("test command");
+  pub async fn install_extension(
+    app: AppHandle,
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+  ) -> Result<Option<ExtensionEntry>, String> {
+    let Some(path) = app.dialog().file().set_parent(&window).blocking_pick_folder() else {
+      return Ok(None);
+    };
+    let path = path.into_path().err_to_string()?;
+
+    let mut registry = ExtensionRegistry::load(&state.dir).err_to_string()?;
+    let entry = registry.install(&state.dir, &path).err_to_string()?;
+    registry.save(&state.dir).err_to_string()?;
+
+    Ok(Some(entry))
+  }
+
+  /// 開いているオーバーレイから取り除いてからファイルを消す
+  #[command]
+  #[specta]
+  pub fn uninstall_extension(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let mut registry = ExtensionRegistry::load(&state.dir).err_to_string()?;
+    let name = registry
+      .get(&id)
+      .ok_or_else(|| format!("extension {} is not found", id))?
+      .name
+      .clone();
+    remove_from_all(&app, &name).err_to_string()?;
+
+    registry.uninstall(&state.dir, &id).err_to_string()?;
+    registry.save(&state.dir).err_to_string()?;
+
     Ok(())
   }
+
+  /// profile -> Noneなら共有のデータディレクトリ
+  #[command]
+  #[specta]
+  pub fn set_extension_enabled(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    profile: Option<String>,
+    value: bool,
+  ) -> Result<(), String> {
+    (|| {
+      if let Some(profile) = &profile {
+        if !state.config.read().unwrap().profiles.contains(profile) {
+          bail!("profile {} is not found", profile);
+        }
+      }
+
+      let mut registry = ExtensionRegistry::load(&state.dir)?;
+      registry.set_enabled(&id, profile.as_deref(), value)?;
+      registry.save(&state.dir)?;
+      apply_to_profile(&app, profile.as_deref())
+    })()
+    .err_to_string()
+  }
 }
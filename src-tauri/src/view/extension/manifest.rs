@@ -0,0 +1,116 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, ensure, Context};
+use serde::Deserialize;
+use serde_json::Value;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const LOCALES_DIR: &str = "_locales";
+const NAME_MAX_LEN: usize = 75;
+
+/// manifest.jsonのうち登録と検証に使う部分
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Manifest {
+  pub manifest_version: u32,
+  pub name: String,
+  pub version: String,
+  #[serde(default)]
+  pub description: String,
+  #[serde(default)]
+  pub default_locale: Option<String>,
+  #[serde(default)]
+  pub permissions: Vec<String>,
+  #[serde(default)]
+  pub host_permissions: Vec<String>,
+}
+
+impl Manifest {
+  pub fn parse(text: &str) -> anyhow::Result<Self> {
+    let manifest = serde_json::from_str::<Self>(text.trim_start_matches('\u{feff}')).context("failed to parse manifest.json")?;
+    manifest.validate()?;
+
+    Ok(manifest)
+  }
+
+  /// 展開済みの拡張機能のディレクトリから読む、`__MSG_*__`はdefault_localeの文言に置き換える
+  pub fn read(dir: &Path) -> anyhow::Result<Self> {
+    let text =
+      fs::read_to_string(dir.join(MANIFEST_FILE)).with_context(|| format!("{} is not found in {}", MANIFEST_FILE, dir.display()))?;
+    let mut manifest = Self::parse(&text)?;
+
+    let locales = dir.join(LOCALES_DIR);
+    match &manifest.default_locale {
+      Some(locale) => {
+        let path = locales.join(locale).join("messages.json");
+        let text = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let messages = serde_json::from_str::<Value>(text.trim_start_matches('\u{feff}'))
+          .with_context(|| format!("failed to parse {}", path.display()))?;
+        manifest.name = localize(&manifest.name, &messages);
+        manifest.description = localize(&manifest.description, &messages);
+        ensure!(!manifest.name.trim().is_empty(), "name is empty in locale {}", locale);
+      }
+      None => ensure!(!locales.exists(), "default_locale is required when {} exists", LOCALES_DIR),
+    }
+
+    Ok(manifest)
+  }
+
+  pub fn validate(&self) -> anyhow::Result<()> {
+    ensure!(
+      (2..=3).contains(&self.manifest_version),
+      "manifest_version {} is not supported",
+      self.manifest_version
+    );
+    ensure!(!self.name.trim().is_empty(), "name is empty");
+    ensure!(
+      self.name.chars().count() <= NAME_MAX_LEN,
+      "name must be at most {} characters",
+      NAME_MAX_LEN
+    );
+    validate_version(&self.version)?;
+
+    let localized = message_key(&self.name).is_some() || message_key(&self.description).is_some();
+    ensure!(
+      !localized || self.default_locale.is_some(),
+      "default_locale is required to use __MSG_*__"
+    );
+
+    Ok(())
+  }
+}
+
+/// 1-4個の`.`で区切った0-65535の整数
+pub fn validate_version(version: &str) -> anyhow::Result<()> {
+  let parts = version.split('.').collect::<Vec<_>>();
+  ensure!((1..=4).contains(&parts.len()), "version {} must have 1-4 parts", version);
+  for part in parts {
+    let valid = !part.is_empty()
+      && part.chars().all(|c| c.is_ascii_digit())
+      && (part == "0" || !part.starts_with('0'))
+      && part.parse::<u32>().is_ok_and(|v| v <= 65535);
+    if !valid {
+      bail!("version {} is invalid", version);
+    }
+  }
+
+  Ok(())
+}
+
+/// `__MSG_name__` -> Some("name")
+fn message_key(value: &str) -> Option<&str> {
+  value.strip_prefix("__MSG_")?.strip_suffix("__")
+}
+
+/// messages.jsonのキーは大文字小文字を区別しない
+fn localize(value: &str, messages: &Value) -> String {
+  let Some(key) = message_key(value) else {
+    return value.to_string();
+  };
+
+  messages
+    .as_object()
+    .and_then(|v| v.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)))
+    .and_then(|(_, v)| v["message"].as_str())
+    .unwrap_or(value)
+    .to_string()
+}
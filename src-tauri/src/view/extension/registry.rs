@@ -0,0 +1,176 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::util::DEFAULT_PROFILE;
+
+use super::manifest::Manifest;
+
+/// 設定ファイルと同じディレクトリに作る
+pub const EXTENSIONS_DIR: &str = "extensions";
+pub const REGISTRY_FILE: &str = "extensions.toml";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct ExtensionEntry {
+  /// 名前から作る、EXTENSIONS_DIR内のディレクトリ名にもなる
+  pub id: String,
+  pub name: String,
+  pub version: String,
+  #[serde(default)]
+  pub description: String,
+  pub manifest_version: u32,
+  /// 有効にしているプロファイル、共有のデータディレクトリはDEFAULT_PROFILE
+  #[serde(default)]
+  pub enabled: Vec<String>,
+}
+
+impl ExtensionEntry {
+  pub fn is_enabled(&self, profile: Option<&str>) -> bool {
+    let profile = profile.unwrap_or(DEFAULT_PROFILE);
+    self.enabled.iter().any(|v| v == profile)
+  }
+}
+
+/// インストールした展開済みの拡張機能の一覧
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExtensionRegistry {
+  #[serde(default)]
+  pub extensions: Vec<ExtensionEntry>,
+}
+
+impl ExtensionRegistry {
+  /// ファイルがなければ空の一覧にする
+  pub fn load(root: &Path) -> anyhow::Result<Self> {
+    let path = root.join(REGISTRY_FILE);
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+    let text = fs::read_to_string(&path)?;
+
+    toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+  }
+
+  pub fn save(&self, root: &Path) -> anyhow::Result<()> {
+    fs::write(root.join(REGISTRY_FILE), toml::to_string(self)?)?;
+
+    Ok(())
+  }
+
+  pub fn get(&self, id: &str) -> Option<&ExtensionEntry> {
+    self.extensions.iter().find(|v| v.id == id)
+  }
+
+  /// sourceをEXTENSIONS_DIRにコピーして登録する
+  /// 同じidが既にあればファイルを置き換え、有効にしているプロファイルは引き継ぐ
+  pub fn install(&mut self, root: &Path, source: &Path) -> anyhow::Result<ExtensionEntry> {
+    let manifest = Manifest::read(source)?;
+    let id = extension_id(&manifest.name)?;
+
+    let dir = extension_dir(root, &id);
+    ensure!(
+      !dir.starts_with(source) && !source.starts_with(&dir),
+      "{} overlaps the extensions directory",
+      source.display()
+    );
+    if dir.exists() {
+      fs::remove_dir_all(&dir)?;
+    }
+    copy_dir(source, &dir)?;
+
+    let enabled = self.get(&id).map(|v| v.enabled.clone()).unwrap_or_default();
+    let entry = ExtensionEntry {
+      id: id.clone(),
+      name: manifest.name,
+      version: manifest.version,
+      description: manifest.description,
+      manifest_version: manifest.manifest_version,
+      enabled,
+    };
+    match self.extensions.iter_mut().find(|v| v.id == id) {
+      Some(v) => *v = entry.clone(),
+      None => self.extensions.push(entry.clone()),
+    }
+
+    Ok(entry)
+  }
+
+  pub fn uninstall(&mut self, root: &Path, id: &str) -> anyhow::Result<ExtensionEntry> {
+    let index = self
+      .extensions
+      .iter()
+      .position(|v| v.id == id)
+      .with_context(|| format!("extension {} is not found", id))?;
+    let dir = extension_dir(root, id);
+    if dir.exists() {
+      fs::remove_dir_all(dir)?;
+    }
+
+    Ok(self.extensions.remove(index))
+  }
+
+  /// profile -> Noneなら共有のデータディレクトリ
+  pub fn set_enabled(&mut self, id: &str, profile: Option<&str>, value: bool) -> anyhow::Result<()> {
+    let entry = self
+      .extensions
+      .iter_mut()
+      .find(|v| v.id == id)
+      .with_context(|| format!("extension {} is not found", id))?;
+    let profile = profile.unwrap_or(DEFAULT_PROFILE);
+    entry.enabled.retain(|v| v != profile);
+    if value {
+      entry.enabled.push(profile.to_string());
+    }
+
+    Ok(())
+  }
+
+  /// プロファイルを消したときに呼ぶ
+  pub fn remove_profile(&mut self, profile: &str) {
+    for entry in &mut self.extensions {
+      entry.enabled.retain(|v| v != profile);
+    }
+  }
+}
+
+pub fn extension_dir(root: &Path, id: &str) -> PathBuf {
+  root.join(EXTENSIONS_DIR).join(id)
+}
+
+/// 英数字以外を`_`にして小文字にする
+pub fn extension_id(name: &str) -> anyhow::Result<String> {
+  let mut id = String::new();
+  for c in name.trim().chars() {
+    if c.is_ascii_alphanumeric() || c == '-' {
+      id.push(c.to_ascii_lowercase());
+    } else if !id.ends_with('_') {
+      id.push('_');
+    }
+  }
+  let id = id.trim_matches('_').to_string();
+  if id.is_empty() {
+    bail!("cannot make an id from {}", name);
+  }
+
+  Ok(id)
+}
+
+fn copy_dir(source: &Path, target: &Path) -> anyhow::Result<()> {
+  fs::create_dir_all(target)?;
+  for entry in fs::read_dir(source)? {
+    let entry = entry?;
+    let file_type = entry.file_type()?;
+    let target = target.join(entry.file_name());
+    if file_type.is_dir() {
+      copy_dir(&entry.path(), &target)?;
+    } else if file_type.is_file() {
+      fs::copy(entry.path(), target)?;
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,199 @@
+pub mod store;
+
+use std::{
+  sync::atomic::Ordering,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindow};
+use webview2_com::take_pwstr;
+use windows::core::PWSTR;
+
+use crate::util::{AppState, SerDeWindowData};
+
+use super::ctrl;
+
+use store::{Bookmark, BookmarkList};
+
+pub type WindowBookmark = Bookmark<SerDeWindowData>;
+pub type WindowBookmarkList = BookmarkList<SerDeWindowData>;
+
+/// ページの読み込みが終わったら呼ぶ、シークレットのオーバーレイとhttp(s)以外は残さない
+pub fn record_visit(window: &WebviewWindow) -> anyhow::Result<()> {
+  let state = window.state::<AppState>();
+  let window_data = state.get_window_data(window.label()).context("failure to get window data")?;
+  if window_data.incognito.load(Ordering::Acquire) {
+    return Ok(());
+  }
+
+  let app = window.app_handle().clone();
+  window.with_webview(move |webview| {
+    #[cfg(windows)]
+    unsafe {
+      let page = (|| -> windows::core::Result<(String, String)> {
+        let webview = webview.controller().CoreWebView2()?;
+        let (mut url, mut title) = (PWSTR::null(), PWSTR::null());
+        webview.Source(&mut url)?;
+        webview.DocumentTitle(&mut title)?;
+        Ok((take_pwstr(url), take_pwstr(title)))
+      })();
+      let (url, title) = match page {
+        Ok(v) => v,
+        Err(e) => {
+          println!("failed to read page for history: {}", e);
+          return;
+        }
+      };
+      if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return;
+      }
+
+      let state = app.state::<AppState>();
+      if let Err(e) = state.history.lock().unwrap().visit(&url, &title, now()) {
+        println!("failed to record history: {}", e);
+      }
+    }
+  })?;
+
+  Ok(())
+}
+
+/// UNIXエポックからの秒
+fn now() -> u32 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |v| v.as_secs() as u32)
+}
+
+/// 保存していた設定があれば反映して開く
+/// ウィンドウを作るのでメインスレッドから呼ばないこと
+/// 作ったオーバーレイのlabelを返す
+pub fn open_bookmark(app: &AppHandle, id: &str) -> anyhow::Result<String> {
+  let state = app.state::<AppState>();
+  let bookmark = WindowBookmarkList::load(&state.dir)?
+    .get(id)
+    .cloned()
+    .with_context(|| format!("bookmark {} is not found", id))?;
+
+  let profile = bookmark.settings.as_ref().and_then(|v| v.profile.as_deref().map(Into::into));
+  let proxy = bookmark.settings.as_ref().and_then(|v| v.proxy.clone());
+  let incognito = bookmark.settings.as_ref().is_some_and(|v| v.incognito);
+  let agent_preset = bookmark.settings.as_ref().map(|v| v.agent_preset.to_string());
+  let url = WebviewUrl::External(bookmark.url.parse()?);
+  let label = ctrl::view_create(app, &state, url, profile, proxy, incognito, agent_preset)?;
+  if let Some(settings) = &bookmark.settings {
+    // オーバーレイはもう開いているので設定の失敗では止めない
+    if let Err(e) = ctrl::apply_window_data(app, &label, settings) {
+      println!("failed to apply bookmark settings: {}", e);
+    }
+    state.emit_windows(app);
+  }
+
+  Ok(label)
+}
+
+pub mod command {
+  use specta::specta;
+  use tauri::{command, AppHandle, State};
+  use uuid::Uuid;
+
+  use crate::util::{normalize_url, AppState, ErrToString};
+
+  use super::{
+    store::{Bookmark, HistoryEntry},
+    WindowBookmark, WindowBookmarkList,
+  };
+
+  /// urlの入力欄の補完に使う
+  #[command]
+  #[specta]
+  pub fn search_history(state: State<'_, AppState>, query: String, limit: u32) -> Vec<HistoryEntry> {
+    state.history.lock().unwrap().search(&query, limit as usize)
+  }
+
+  #[command]
+  #[specta]
+  pub fn clear_history(state: State<'_, AppState>) -> Result<(), String> {
+    state.history.lock().unwrap().clear().err_to_string()
+  }
+
+  /// tag -> 指定すればそのタグのものだけ
+  #[command]
+  #[specta]
+  pub fn search_bookmarks(state: State<'_, AppState>, query: String, tag: Option<String>) -> Result<Vec<WindowBookmark>, String> {
+    let list = WindowBookmarkList::load(&state.dir).err_to_string()?;
+
+    Ok(list.search(&query, tag.as_deref()).into_iter().cloned().collect())
+  }
+
+  #[command]
+  #[specta]
+  pub fn list_bookmark_tags(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let list = WindowBookmarkList::load(&state.dir).err_to_string()?;
+
+    Ok(list.tags())
+  }
+
+  /// label -> 指定すればそのオーバーレイの今の設定も残す
+  #[command]
+  #[specta]
+  pub fn create_bookmark(
+    state: State<'_, AppState>,
+    url: String,
+    title: String,
+    tags: Vec<String>,
+    label: Option<String>,
+  ) -> Result<WindowBookmark, String> {
+    let url = normalize_url(&url).err_to_string()?;
+    let settings = match label {
+      Some(label) => {
+        let window_data = state.get_window_data(&label).ok_or(format!("overlay {} is not found", label))?;
+        Some((&window_data).into())
+      }
+      None => None,
+    };
+
+    let mut list = WindowBookmarkList::load(&state.dir).err_to_string()?;
+    let id = Uuid::new_v4().to_string();
+    list
+      .add(Bookmark {
+        id: id.clone(),
+        url: url.to_string(),
+        title,
+        tags,
+        settings,
+      })
+      .err_to_string()?;
+    list.save(&state.dir).err_to_string()?;
+
+    Ok(list.get(&id).cloned().unwrap())
+  }
+
+  /// 同じidのブックマークを置き換える
+  #[command]
+  #[specta]
+  pub fn set_bookmark(state: State<'_, AppState>, mut bookmark: WindowBookmark) -> Result<(), String> {
+    bookmark.url = normalize_url(&bookmark.url).err_to_string()?.to_string();
+    let mut list = WindowBookmarkList::load(&state.dir).err_to_string()?;
+    list.set(bookmark).err_to_string()?;
+    list.save(&state.dir).err_to_string()?;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn delete_bookmark(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let mut list = WindowBookmarkList::load(&state.dir).err_to_string()?;
+    list.remove(&id).err_to_string()?;
+    list.save(&state.dir).err_to_string()?;
+
+    Ok(())
+  }
+
+  /// 作ったオーバーレイのlabelを返す
+  #[command]
+  #[specta]
+  pub async fn open_bookmark(app: AppHandle, id: String) -> Result<String, String> {
+    super::open_bookmark(&app, &id).err_to_string()
+  }
+}
@@ -0,0 +1,302 @@
+use std::{
+  cmp::Reverse,
+  collections::HashMap,
+  fs::{self, OpenOptions},
+  io::{ErrorKind, Write},
+  path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use specta::Type;
+
+/// 設定ファイルと同じディレクトリに作る
+pub const HISTORY_FILE: &str = "history.jsonl";
+pub const BOOKMARKS_FILE: &str = "bookmarks.toml";
+/// これを超えたら古いものから消す
+pub const MAX_HISTORY: usize = 5000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct HistoryEntry {
+  pub url: String,
+  #[serde(default)]
+  pub title: String,
+  /// 最後に開いた時刻、UNIXエポックからの秒
+  pub visited_at: u32,
+  pub visits: u32,
+}
+
+/// 開くたびに1行追記し、行数が増えすぎたらurlごとに1行へ詰める
+#[derive(Debug)]
+pub struct HistoryStore {
+  path: PathBuf,
+  entries: HashMap<String, HistoryEntry>,
+  /// ファイルの行数
+  lines: usize,
+}
+
+impl HistoryStore {
+  /// ファイルがなければ空にする
+  /// 書き込み途中で終了した行などは読み飛ばし、次に詰めるときに消える
+  pub fn open(root: &Path) -> anyhow::Result<Self> {
+    let path = root.join(HISTORY_FILE);
+    let text = match fs::read_to_string(&path) {
+      Ok(v) => v,
+      Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+      Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+
+    let mut store = Self {
+      path,
+      entries: HashMap::new(),
+      lines: 0,
+    };
+    for line in text.lines().filter(|v| !v.trim().is_empty()) {
+      store.lines += 1;
+      if let Ok(record) = serde_json::from_str::<HistoryEntry>(line) {
+        store.merge(record);
+      }
+    }
+    if store.needs_compaction() {
+      store.compact()?;
+    }
+
+    Ok(store)
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  pub fn get(&self, url: &str) -> Option<&HistoryEntry> {
+    self.entries.get(url)
+  }
+
+  /// 新しいものが先頭
+  pub fn entries(&self) -> Vec<HistoryEntry> {
+    let mut entries = self.entries.values().cloned().collect::<Vec<_>>();
+    entries.sort_by_key(|v| (Reverse(v.visited_at), v.url.clone()));
+    entries
+  }
+
+  /// now -> UNIXエポックからの秒
+  /// titleが空なら前のタイトルを残す
+  pub fn visit(&mut self, url: &str, title: &str, now: u32) -> anyhow::Result<()> {
+    let record = HistoryEntry {
+      url: url.to_string(),
+      title: title.to_string(),
+      visited_at: now,
+      visits: 1,
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+    writeln!(file, "{}", serde_json::to_string(&record)?)?;
+    self.lines += 1;
+    self.merge(record);
+
+    if self.needs_compaction() {
+      self.compact()?;
+    }
+
+    Ok(())
+  }
+
+  /// urlごとに1行にして書き直す、MAX_HISTORYを超えた古いものは消す
+  pub fn compact(&mut self) -> anyhow::Result<()> {
+    let mut entries = self.entries();
+    entries.truncate(MAX_HISTORY);
+
+    let mut text = String::new();
+    // 古いものから書く
+    for entry in entries.iter().rev() {
+      text += &serde_json::to_string(entry)?;
+      text.push('\n');
+    }
+    // 途中で失敗しても元のファイルを壊さない
+    let temp = self.path.with_extension("jsonl.tmp");
+    fs::write(&temp, text)?;
+    fs::rename(&temp, &self.path)?;
+
+    self.lines = entries.len();
+    self.entries = entries.into_iter().map(|v| (v.url.clone(), v)).collect();
+
+    Ok(())
+  }
+
+  pub fn clear(&mut self) -> anyhow::Result<()> {
+    self.entries.clear();
+    self.compact()
+  }
+
+  /// urlとタイトルがすべての語を含むものを探す
+  /// 語の先頭でurlが始まるもの、開いた回数が多いもの、新しいものの順
+  pub fn search(&self, query: &str, limit: usize) -> Vec<HistoryEntry> {
+    let terms = search_terms(query);
+    let mut found = self
+      .entries
+      .values()
+      .filter(|v| matches_terms(&terms, &[&v.url, &v.title]))
+      .map(|v| (terms.first().is_some_and(|term| strip_url(&v.url).starts_with(term.as_str())), v))
+      .collect::<Vec<_>>();
+    found.sort_by_key(|(prefix, v)| (Reverse(*prefix), Reverse(v.visits), Reverse(v.visited_at), v.url.clone()));
+
+    found.into_iter().take(limit).map(|v| v.1.clone()).collect()
+  }
+
+  fn merge(&mut self, record: HistoryEntry) {
+    let entry = self.entries.entry(record.url.clone()).or_insert_with(|| HistoryEntry {
+      url: record.url.clone(),
+      title: String::new(),
+      visited_at: 0,
+      visits: 0,
+    });
+    entry.visits = entry.visits.saturating_add(record.visits);
+    entry.visited_at = entry.visited_at.max(record.visited_at);
+    if !record.title.is_empty() {
+      entry.title = record.title;
+    }
+  }
+
+  /// MAX_HISTORYを超えても毎回書き直さないよう行数だけで決める
+  fn needs_compaction(&self) -> bool {
+    self.lines > self.entries.len().min(MAX_HISTORY) * 2 + 100
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Type)]
+pub struct Bookmark<T> {
+  pub id: String,
+  pub url: String,
+  #[serde(default)]
+  pub title: String,
+  #[serde(default)]
+  pub tags: Vec<String>,
+  /// 開くときに反映するオーバーレイの設定、なければ既定の設定で開く
+  pub settings: Option<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BookmarkList<T> {
+  #[serde(default = "Vec::new")]
+  pub bookmarks: Vec<Bookmark<T>>,
+}
+
+impl<T> Default for BookmarkList<T> {
+  fn default() -> Self {
+    Self { bookmarks: Vec::new() }
+  }
+}
+
+impl<T> BookmarkList<T>
+where
+  T: Serialize + DeserializeOwned,
+{
+  /// ファイルがなければ空にする
+  pub fn load(root: &Path) -> anyhow::Result<Self> {
+    let path = root.join(BOOKMARKS_FILE);
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+    let text = fs::read_to_string(&path)?;
+
+    toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+  }
+
+  pub fn save(&self, root: &Path) -> anyhow::Result<()> {
+    fs::write(root.join(BOOKMARKS_FILE), toml::to_string(self)?)?;
+
+    Ok(())
+  }
+}
+
+impl<T> BookmarkList<T> {
+  pub fn get(&self, id: &str) -> Option<&Bookmark<T>> {
+    self.bookmarks.iter().find(|v| v.id == id)
+  }
+
+  pub fn add(&mut self, mut bookmark: Bookmark<T>) -> anyhow::Result<()> {
+    ensure!(self.get(&bookmark.id).is_none(), "bookmark {} already exists", bookmark.id);
+    bookmark.tags = normalize_tags(bookmark.tags);
+    self.bookmarks.push(bookmark);
+
+    Ok(())
+  }
+
+  /// 同じidのものを置き換える
+  pub fn set(&mut self, mut bookmark: Bookmark<T>) -> anyhow::Result<()> {
+    let Some(v) = self.bookmarks.iter_mut().find(|v| v.id == bookmark.id) else {
+      bail!("bookmark {} is not found", bookmark.id);
+    };
+    bookmark.tags = normalize_tags(bookmark.tags);
+    *v = bookmark;
+
+    Ok(())
+  }
+
+  pub fn remove(&mut self, id: &str) -> anyhow::Result<Bookmark<T>> {
+    let index = self
+      .bookmarks
+      .iter()
+      .position(|v| v.id == id)
+      .with_context(|| format!("bookmark {} is not found", id))?;
+
+    Ok(self.bookmarks.remove(index))
+  }
+
+  /// tag -> 大文字小文字を区別せずに一致するタグを持つものだけにする
+  pub fn search(&self, query: &str, tag: Option<&str>) -> Vec<&Bookmark<T>> {
+    let terms = search_terms(query);
+    self
+      .bookmarks
+      .iter()
+      .filter(|v| match tag {
+        Some(tag) => v.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim())),
+        None => true,
+      })
+      .filter(|v| {
+        let tags = v.tags.join(" ");
+        matches_terms(&terms, &[&v.url, &v.title, &tags])
+      })
+      .collect()
+  }
+
+  /// 使われているタグの一覧
+  pub fn tags(&self) -> Vec<String> {
+    let mut tags = self.bookmarks.iter().flat_map(|v| v.tags.iter().cloned()).collect::<Vec<_>>();
+    tags.sort_by_key(|v| v.to_lowercase());
+    tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    tags
+  }
+}
+
+/// 前後の空白を除き、空のものと大文字小文字だけ違う重複を取り除く
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+  let mut normalized: Vec<String> = Vec::new();
+  for tag in tags {
+    let tag = tag.trim();
+    if !tag.is_empty() && !normalized.iter().any(|v| v.eq_ignore_ascii_case(tag)) {
+      normalized.push(tag.to_string());
+    }
+  }
+
+  normalized
+}
+
+fn search_terms(query: &str) -> Vec<String> {
+  query.split_whitespace().map(str::to_lowercase).collect()
+}
+
+fn matches_terms(terms: &[String], fields: &[&str]) -> bool {
+  let fields = fields.iter().map(|v| v.to_lowercase()).collect::<Vec<_>>();
+  terms.iter().all(|term| fields.iter().any(|v| v.contains(term.as_str())))
+}
+
+/// スキームとwww.を除く、入力途中のurlと比べるため
+fn strip_url(url: &str) -> String {
+  let url = url.to_lowercase();
+  let url = url.split_once("://").map_or(url.as_str(), |v| v.1);
+  url.strip_prefix("www.").unwrap_or(url).to_string()
+}
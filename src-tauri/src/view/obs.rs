@@ -0,0 +1,101 @@
+pub mod scene;
+
+use std::{env, path::PathBuf};
+
+use anyhow::{bail, Context};
+use tauri::{AppHandle, LogicalSize, Manager, WebviewUrl};
+
+use crate::util::{normalize_url, AppState};
+
+use super::{
+  ctrl::{self, custom_css, page_transparent},
+  util::to_ctrl_label,
+};
+
+use scene::BrowserSource;
+
+/// OBSがシーンコレクションを保存するディレクトリ、なければNone
+pub fn scenes_dir() -> Option<PathBuf> {
+  let dir = PathBuf::from(env::var_os("APPDATA")?)
+    .join("obs-studio")
+    .join("basic")
+    .join("scenes");
+  dir.is_dir().then_some(dir)
+}
+
+/// ブラウザソースの大きさとcssでオーバーレイを作る
+/// 作ったオーバーレイのlabelを返す
+pub fn import_source(app: &AppHandle, source: &BrowserSource) -> anyhow::Result<String> {
+  if let Some(reason) = &source.unsupported {
+    bail!("{}: {}", source.name, reason);
+  }
+  let state = app.state::<AppState>();
+  let url = normalize_url(&source.url)?;
+  let label = ctrl::view_create(app, &state, WebviewUrl::External(url), None, None, false, None)?;
+  let window = app.get_webview_window(&label).context("window is not found")?;
+  let ctrl = app
+    .get_webview_window(&to_ctrl_label(label.as_str()))
+    .context("ctrl is not found")?;
+
+  // OBSの幅と高さはページのcssピクセル
+  window.set_size(LogicalSize::new(source.width, source.height))?;
+  // OBSのブラウザソースは背景が透明
+  page_transparent::command::set_page_transparent(ctrl.clone(), state.clone(), true, false).map_err(anyhow::Error::msg)?;
+  custom_css::command::set_custom_css(ctrl, state, source.css.clone()).map_err(anyhow::Error::msg)?;
+
+  Ok(label)
+}
+
+pub mod command {
+  use std::fs;
+
+  use specta::specta;
+  use tauri::{command, AppHandle, State, WebviewWindow};
+  use tauri_plugin_dialog::DialogExt;
+
+  use crate::util::{AppState, ErrToString};
+
+  use super::{
+    import_source,
+    scene::{parse_scene_collection, BrowserSource, SceneCollection},
+    scenes_dir,
+  };
+
+  /// OBSのシーンコレクションを選んでブラウザソースを一覧にする、キャンセルしたらNone
+  #[command]
+  #[specta]
+  pub async fn pick_obs_scene_collection(app: AppHandle, window: WebviewWindow) -> Result<Option<SceneCollection>, String> {
+    let mut dialog = app
+      .dialog()
+      .file()
+      .set_parent(&window)
+      .add_filter("OBS Scene Collection", &["json"]);
+    if let Some(dir) = scenes_dir() {
+      dialog = dialog.set_directory(dir);
+    }
+    let Some(path) = dialog.blocking_pick_file() else {
+      return Ok(None);
+    };
+    let path = path.into_path().err_to_string()?;
+
+    let text = fs::read_to_string(&path).err_to_string()?;
+    parse_scene_collection(&text).map(Some).err_to_string()
+  }
+
+  /// 選んだブラウザソースをオーバーレイにする、対応していないものが含まれていれば何も作らない
+  /// 途中で失敗したら、それまでに作ったオーバーレイは開いたままErrを返す
+  /// 作ったオーバーレイのlabelを返す
+  #[command]
+  #[specta]
+  pub async fn import_obs_sources(app: AppHandle, state: State<'_, AppState>, sources: Vec<BrowserSource>) -> Result<Vec<String>, String> {
+    if let Some(source) = sources.iter().find(|v| v.unsupported.is_some()) {
+      return Err(format!("{}: {}", source.name, source.unsupported.as_deref().unwrap_or_default()));
+    }
+
+    let labels = sources.iter().map(|v| import_source(&app, v)).collect::<anyhow::Result<Vec<_>>>();
+    // 途中で失敗しても開いたものは反映する
+    state.emit_windows(&app);
+
+    labels.err_to_string()
+  }
+}
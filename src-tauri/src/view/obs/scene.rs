@@ -0,0 +1,103 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+
+use crate::util::normalize_url;
+
+pub const BROWSER_SOURCE_ID: &str = "browser_source";
+const SCENE_IDS: &[&str] = &["scene", "group"];
+
+// OBSは既定値と同じ設定を保存しないので補う
+pub const DEFAULT_URL: &str = "https://obsproject.com/browser-source";
+pub const DEFAULT_WIDTH: u32 = 800;
+pub const DEFAULT_HEIGHT: u32 = 600;
+pub const DEFAULT_CSS: &str = "body { background-color: rgba(0, 0, 0, 0); margin: 0px auto; overflow: hidden; }";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct SceneCollection {
+  pub name: String,
+  pub sources: Vec<BrowserSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct BrowserSource {
+  pub name: String,
+  /// このソースを含むシーン
+  pub scenes: Vec<String>,
+  pub url: String,
+  pub width: u32,
+  pub height: u32,
+  pub css: String,
+  /// オーバーレイにできない理由、ローカルファイルなど
+  pub unsupported: Option<String>,
+}
+
+/// シーンコレクションのjson(`obs-studio/basic/scenes/*.json`)からブラウザソースを探す
+pub fn parse_scene_collection(text: &str) -> anyhow::Result<SceneCollection> {
+  let root = serde_json::from_str::<Value>(text).context("invalid scene collection")?;
+  let sources = root
+    .get("sources")
+    .and_then(Value::as_array)
+    .context("sources is not found in the scene collection")?;
+
+  // (シーン, ソース)
+  let items = sources
+    .iter()
+    .filter(|v| SCENE_IDS.contains(&str_of(v, "id")))
+    .flat_map(|scene| {
+      let items = scene.pointer("/settings/items").and_then(Value::as_array);
+      items
+        .into_iter()
+        .flatten()
+        .map(move |item| (str_of(scene, "name"), str_of(item, "name")))
+    })
+    .collect::<Vec<_>>();
+
+  let sources = sources
+    .iter()
+    .filter(|v| str_of(v, "id") == BROWSER_SOURCE_ID)
+    .map(|source| {
+      let name = str_of(source, "name").to_string();
+      let scenes = items.iter().filter(|v| v.1 == name).map(|v| v.0.to_string()).collect();
+      let settings = source.get("settings").unwrap_or(&Value::Null);
+      let size = |key: &str, default: u32| {
+        settings
+          .get(key)
+          .and_then(Value::as_u64)
+          .map_or(default, |v| v.clamp(1, u32::MAX as u64) as u32)
+      };
+
+      let is_local_file = settings.get("is_local_file").and_then(Value::as_bool).unwrap_or(false);
+      let (url, unsupported) = if is_local_file {
+        let path = settings.get("local_file").and_then(Value::as_str).unwrap_or_default();
+        (path.to_string(), Some("local files are not supported".to_string()))
+      } else {
+        let url = settings.get("url").and_then(Value::as_str).unwrap_or(DEFAULT_URL);
+        match normalize_url(url) {
+          Ok(v) => (v.to_string(), None),
+          Err(e) => (url.to_string(), Some(e.to_string())),
+        }
+      };
+
+      BrowserSource {
+        name,
+        scenes,
+        url,
+        width: size("width", DEFAULT_WIDTH),
+        height: size("height", DEFAULT_HEIGHT),
+        css: settings.get("css").and_then(Value::as_str).unwrap_or(DEFAULT_CSS).to_string(),
+        unsupported,
+      }
+    })
+    .collect();
+
+  Ok(SceneCollection {
+    name: str_of(&root, "name").to_string(),
+    sources,
+  })
+}
+
+fn str_of<'a>(value: &'a Value, key: &str) -> &'a str {
+  value.get(key).and_then(Value::as_str).unwrap_or_default()
+}
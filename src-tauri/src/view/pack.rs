@@ -0,0 +1,224 @@
+pub mod format;
+
+use anyhow::Context;
+use tauri::{AppHandle, LogicalPosition, LogicalSize, Manager, WebviewUrl};
+
+use crate::util::{AppState, SerDeWindowData};
+
+use super::ctrl::{self, custom_script::script_origin, user_agent::preset::find_preset};
+
+use format::{ImportStep, PackOverlay};
+
+/// 開いているオーバーレイの(label, url)
+pub fn open_overlays(state: &AppState) -> Vec<(String, String)> {
+  state
+    .get_windows()
+    .iter()
+    .map(|v| (v.label.to_string(), v.url.to_string()))
+    .collect()
+}
+
+/// オーバーレイの今の設定と位置をパックに書ける形にする
+pub fn export_overlay(app: &AppHandle, label: &str) -> anyhow::Result<PackOverlay> {
+  let state = app.state::<AppState>();
+  let window = app.get_webview_window(label).context("window is not found")?;
+  let window_data = state.get_window_data(label).context("failure to get window data")?;
+  let data = SerDeWindowData::from(&window_data);
+  let scale = window.scale_factor()?;
+  let position = window.outer_position()?.to_logical::<i32>(scale);
+  let size = window.inner_size()?.to_logical::<u32>(scale);
+  let (transparent, alpha) = data.transparent;
+
+  Ok(PackOverlay {
+    url: data.url.to_string(),
+    title: data.title.to_string(),
+    position: (position.x, position.y),
+    size: (size.width, size.height),
+    opacity: transparent.then_some(alpha),
+    pin: data.pin,
+    click_through: data.pointer_ignore,
+    agent_preset: Some(data.agent_preset.to_string()),
+    zoom: data.zoom,
+    css: data.custom_css,
+    script: data.custom_script,
+  })
+}
+
+/// 作ったオーバーレイのlabelを返す
+pub fn import_step(app: &AppHandle, step: ImportStep) -> anyhow::Result<String> {
+  let state = app.state::<AppState>();
+  if let Some(label) = &step.replace {
+    // 同じオーバーレイを置き換える手順が先にあれば閉じている
+    if let Some(window) = app.get_webview_window(label) {
+      window.close()?;
+    }
+  }
+
+  let overlay = step.overlay;
+  // 渡した人が独自に作ったプリセットはこちらにないことがある
+  let agent_preset = overlay.agent_preset.filter(|preset| {
+    let found = find_preset(&state.config.read().unwrap().agent_presets, preset).is_some();
+    if !found {
+      println!("preset {} is not found, using the default", preset);
+    }
+    found
+  });
+  let url = WebviewUrl::External(overlay.url.parse()?);
+  let label = ctrl::view_create(app, &state, url, None, None, false, agent_preset)?;
+  let window = app.get_webview_window(&label).context("window is not found")?;
+  window.set_size(LogicalSize::new(overlay.size.0, overlay.size.1))?;
+  window.set_position(LogicalPosition::new(overlay.position.0, overlay.position.1))?;
+
+  let window_data = state.get_window_data(&label).context("failure to get window data")?;
+  let mut data = SerDeWindowData::from(&window_data);
+  data.transparent = (overlay.opacity.is_some(), overlay.opacity.unwrap_or(data.transparent.1));
+  data.pin = overlay.pin;
+  data.pointer_ignore = overlay.click_through;
+  data.zoom = overlay.zoom;
+  data.custom_css = overlay.css;
+  // パックのurlのオリジンで確認しているので、他のオリジンへ移動したら実行しない
+  data.custom_script_origin = Some(script_origin(&overlay.url.parse()?));
+  data.custom_script = overlay.script;
+  // オーバーレイはもう開いているので設定の失敗では止めない
+  if let Err(e) = ctrl::apply_window_data(app, &label, &data) {
+    println!("failed to apply pack settings: {}", e);
+  }
+
+  Ok(label)
+}
+
+pub mod command {
+  use std::fs;
+
+  use serde::{Deserialize, Serialize};
+  use specta::{specta, Type};
+  use tauri::{command, AppHandle, Manager, WebviewWindow};
+  use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+  use crate::util::{AppState, ErrToString};
+
+  use super::{
+    export_overlay,
+    format::{parse_pack, Pack, PackOverlayPreview, PackSelection, PACK_EXTENSION},
+    import_step, open_overlays,
+  };
+
+  const FILTER_NAME: &str = "Relais Pack";
+
+  #[derive(Debug, Clone, Deserialize, Serialize, Type)]
+  pub struct PackPreview {
+    /// import_packに渡す
+    pub path: String,
+    pub name: String,
+    pub overlays: Vec<PackOverlayPreview>,
+    pub has_scripts: bool,
+  }
+
+  /// labelsが空なら開いているすべてのオーバーレイを書き出す
+  /// キャンセルされたらNone、それ以外は書き出したファイルのパスを返す
+  #[command]
+  #[specta]
+  pub async fn export_pack(app: AppHandle, window: WebviewWindow, name: String, labels: Vec<String>) -> Result<Option<String>, String> {
+    let state = app.state::<AppState>();
+    let labels = if labels.is_empty() {
+      open_overlays(&state).into_iter().map(|v| v.0).collect()
+    } else {
+      labels
+    };
+    let overlays = labels
+      .iter()
+      .map(|v| export_overlay(&app, v))
+      .collect::<anyhow::Result<Vec<_>>>()
+      .err_to_string()?;
+    let text = Pack::new(name.clone(), overlays).to_toml().err_to_string()?;
+
+    let file_name = if name.trim().is_empty() { "overlays" } else { name.trim() };
+    let Some(path) = app
+      .dialog()
+      .file()
+      .set_parent(&window)
+      .add_filter(FILTER_NAME, &[PACK_EXTENSION])
+      .set_file_name(format!("{}.{}", file_name, PACK_EXTENSION))
+      .blocking_save_file()
+    else {
+      return Ok(None);
+    };
+    let path = path.into_path().err_to_string()?;
+    fs::write(&path, text).err_to_string()?;
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+  }
+
+  /// パックを選んで中身と開いているオーバーレイとの重複を返す、キャンセルしたらNone
+  #[command]
+  #[specta]
+  pub async fn preview_pack(app: AppHandle, window: WebviewWindow) -> Result<Option<PackPreview>, String> {
+    let Some(path) = app
+      .dialog()
+      .file()
+      .set_parent(&window)
+      .add_filter(FILTER_NAME, &[PACK_EXTENSION])
+      .blocking_pick_file()
+    else {
+      return Ok(None);
+    };
+    let path = path.into_path().err_to_string()?;
+
+    let pack = parse_pack(&fs::read_to_string(&path).err_to_string()?).err_to_string()?;
+    let state = app.state::<AppState>();
+
+    Ok(Some(PackPreview {
+      path: path.to_string_lossy().into_owned(),
+      name: pack.name.clone(),
+      overlays: pack.preview(&open_overlays(&state)),
+      has_scripts: pack.has_scripts(),
+    }))
+  }
+
+  /// 選んだオーバーレイだけを開く
+  /// パックは他の人が作ったものなので、スクリプトがあれば確認して、許可されなければスクリプトを除いて開く
+  /// 作ったオーバーレイのlabelを返す
+  #[command]
+  #[specta]
+  pub async fn import_pack(
+    app: AppHandle,
+    window: WebviewWindow,
+    path: String,
+    selection: Vec<PackSelection>,
+  ) -> Result<Vec<String>, String> {
+    // プレビューの後に書き換えられていても確認するのは開く中身
+    let pack = parse_pack(&fs::read_to_string(&path).err_to_string()?).err_to_string()?;
+    let state = app.state::<AppState>();
+    let mut steps = pack.plan(&selection, &open_overlays(&state)).err_to_string()?;
+
+    let scripted = steps
+      .iter()
+      .filter(|v| v.overlay.has_script())
+      .map(|v| v.overlay.url.as_str())
+      .collect::<Vec<_>>();
+    if !scripted.is_empty() {
+      let allowed = app
+        .dialog()
+        .message(format!(
+          "This pack contains scripts that will run on these sites:\n\n{}\n\nEach script only runs while its overlay stays on the same site.\nOnly allow scripts from people you trust.",
+          scripted.join("\n")
+        ))
+        .title("Run scripts from the pack?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Allow".into(), "Skip Scripts".into()))
+        .parent(&window)
+        .blocking_show();
+      if !allowed {
+        for step in &mut steps {
+          step.overlay.script.clear();
+        }
+      }
+    }
+
+    let labels = steps.into_iter().map(|v| import_step(&app, v)).collect::<anyhow::Result<Vec<_>>>();
+    // 途中で失敗しても開いたものは反映する
+    state.emit_windows(&app);
+
+    labels.err_to_string()
+  }
+}
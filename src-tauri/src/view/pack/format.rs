@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, ensure, Context};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::util::normalize_url;
+
+pub const PACK_EXTENSION: &str = "relaispack";
+/// 形式を変えたら上げる、これより新しいパックは読まない
+pub const PACK_VERSION: u32 = 1;
+pub const DEFAULT_ZOOM: u32 = 100;
+
+/// オーバーレイの構成を他の人に渡すためのファイル
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct Pack {
+  pub version: u32,
+  #[serde(default)]
+  pub name: String,
+  #[serde(default)]
+  pub overlays: Vec<PackOverlay>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct PackOverlay {
+  pub url: String,
+  #[serde(default)]
+  pub title: String,
+  /// DPIの違う環境でも同じ大きさになるよう論理ピクセル
+  pub position: (i32, i32),
+  pub size: (u32, u32),
+  /// 透過するときのalpha、Noneなら不透明
+  #[serde(default)]
+  pub opacity: Option<u8>,
+  #[serde(default)]
+  pub pin: bool,
+  #[serde(default)]
+  pub click_through: bool,
+  /// Noneなら既定のプリセット
+  #[serde(default)]
+  pub agent_preset: Option<String>,
+  #[serde(default = "default_zoom")]
+  pub zoom: u32,
+  #[serde(default)]
+  pub css: String,
+  #[serde(default)]
+  pub script: String,
+}
+
+fn default_zoom() -> u32 {
+  DEFAULT_ZOOM
+}
+
+/// 同じurlのオーバーレイが開いているときの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum ConflictResolution {
+  Skip,
+  /// 開いているものを閉じてから開く
+  Replace,
+  /// 両方残す
+  Duplicate,
+}
+
+/// index -> Pack::overlaysの位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct PackSelection {
+  pub index: u32,
+  pub resolution: ConflictResolution,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct PackOverlayPreview {
+  pub overlay: PackOverlay,
+  /// 同じurlで開いているオーバーレイのlabel
+  pub conflict: Option<String>,
+}
+
+/// 読み込むオーバーレイと、先に閉じるオーバーレイのlabel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportStep {
+  pub overlay: PackOverlay,
+  pub replace: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+  version: u32,
+}
+
+/// 新しい版のパックは中身を読む前に断る
+pub fn parse_pack(text: &str) -> anyhow::Result<Pack> {
+  let header = toml::from_str::<Header>(text).context("invalid pack")?;
+  ensure!(
+    (1..=PACK_VERSION).contains(&header.version),
+    "pack version {} is not supported, update relais to open it",
+    header.version
+  );
+
+  let mut pack = toml::from_str::<Pack>(text).context("invalid pack")?;
+  for (i, overlay) in pack.overlays.iter_mut().enumerate() {
+    overlay.url = normalize_url(&overlay.url)
+      .with_context(|| format!("overlay {} has an invalid url", i + 1))?
+      .to_string();
+    ensure!(overlay.size.0 > 0 && overlay.size.1 > 0, "overlay {} has an empty size", i + 1);
+    overlay.zoom = overlay.zoom.clamp(20, 500);
+  }
+
+  Ok(pack)
+}
+
+impl Pack {
+  pub fn new(name: String, overlays: Vec<PackOverlay>) -> Self {
+    Self {
+      version: PACK_VERSION,
+      name,
+      overlays,
+    }
+  }
+
+  pub fn to_toml(&self) -> anyhow::Result<String> {
+    Ok(toml::to_string_pretty(self)?)
+  }
+
+  pub fn has_scripts(&self) -> bool {
+    self.overlays.iter().any(PackOverlay::has_script)
+  }
+
+  /// open -> 開いているオーバーレイの(label, url)
+  pub fn preview(&self, open: &[(String, String)]) -> Vec<PackOverlayPreview> {
+    self
+      .overlays
+      .iter()
+      .map(|overlay| PackOverlayPreview {
+        overlay: overlay.clone(),
+        conflict: find_conflict(overlay, open),
+      })
+      .collect()
+  }
+
+  /// 選ばれたものだけを選んだ順に並べる、同じurlのオーバーレイが開いていればresolutionに従う
+  pub fn plan(&self, selection: &[PackSelection], open: &[(String, String)]) -> anyhow::Result<Vec<ImportStep>> {
+    let mut seen = HashSet::new();
+    let mut steps = Vec::new();
+    for selected in selection {
+      let overlay = self
+        .overlays
+        .get(selected.index as usize)
+        .with_context(|| format!("overlay {} is not in the pack", selected.index))?;
+      if !seen.insert(selected.index) {
+        bail!("overlay {} is selected twice", selected.index);
+      }
+
+      let replace = match (find_conflict(overlay, open), selected.resolution) {
+        (Some(_), ConflictResolution::Skip) => continue,
+        (Some(label), ConflictResolution::Replace) => Some(label),
+        _ => None,
+      };
+      steps.push(ImportStep {
+        overlay: overlay.clone(),
+        replace,
+      });
+    }
+
+    Ok(steps)
+  }
+}
+
+impl PackOverlay {
+  pub fn has_script(&self) -> bool {
+    !self.script.trim().is_empty()
+  }
+}
+
+fn find_conflict(overlay: &PackOverlay, open: &[(String, String)]) -> Option<String> {
+  let url = normalize_url(&overlay.url).ok()?;
+  open
+    .iter()
+    .find(|(_, v)| normalize_url(v).is_ok_and(|v| v == url))
+    .map(|(label, _)| label.clone())
+}
@@ -0,0 +1,189 @@
+pub mod table;
+
+use std::{
+  cell::RefCell,
+  collections::HashMap,
+  sync::atomic::{AtomicU32, Ordering},
+};
+
+use anyhow::Context;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use webview2_com::{
+  take_pwstr,
+  Microsoft::Web::WebView2::Win32::{
+    ICoreWebView2Deferral, ICoreWebView2PermissionRequestedEventArgs, ICoreWebView2PermissionRequestedEventArgs3,
+    COREWEBVIEW2_PERMISSION_KIND, COREWEBVIEW2_PERMISSION_KIND_CAMERA, COREWEBVIEW2_PERMISSION_KIND_CLIPBOARD_READ,
+    COREWEBVIEW2_PERMISSION_KIND_GEOLOCATION, COREWEBVIEW2_PERMISSION_KIND_MICROPHONE, COREWEBVIEW2_PERMISSION_KIND_NOTIFICATIONS,
+    COREWEBVIEW2_PERMISSION_STATE_ALLOW, COREWEBVIEW2_PERMISSION_STATE_DENY,
+  },
+  PermissionRequestedEventHandler,
+};
+use windows::{
+  core::{Interface, PWSTR},
+  Win32::{Foundation::BOOL, System::WinRT::EventRegistrationToken},
+};
+
+use crate::{util::AppState, view::util::window_focus, MAIN_LABEL};
+
+use table::{normalize_origin, PermissionKind, PermissionState};
+
+thread_local! {
+  // COMのオブジェクトはSendではないのでメインスレッドで保持する
+  static PENDING: RefCell<HashMap<u32, (ICoreWebView2PermissionRequestedEventArgs, ICoreWebView2Deferral)>> = RefCell::new(HashMap::new());
+}
+
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+fn to_kind(kind: COREWEBVIEW2_PERMISSION_KIND) -> Option<PermissionKind> {
+  match kind {
+    COREWEBVIEW2_PERMISSION_KIND_CAMERA => Some(PermissionKind::Camera),
+    COREWEBVIEW2_PERMISSION_KIND_MICROPHONE => Some(PermissionKind::Microphone),
+    COREWEBVIEW2_PERMISSION_KIND_NOTIFICATIONS => Some(PermissionKind::Notifications),
+    COREWEBVIEW2_PERMISSION_KIND_GEOLOCATION => Some(PermissionKind::Geolocation),
+    COREWEBVIEW2_PERMISSION_KIND_CLIPBOARD_READ => Some(PermissionKind::Clipboard),
+    _ => None,
+  }
+}
+
+/// webviewからの許可のリクエストをConfの設定で処理する
+pub fn attach_permission_handler(app: &AppHandle, window: &WebviewWindow) -> anyhow::Result<()> {
+  window.with_webview({
+    let app = app.clone();
+    move |webview| {
+      #[cfg(windows)]
+      unsafe {
+        let controller = webview.controller();
+        let webview = controller.CoreWebView2().unwrap();
+        let mut token = EventRegistrationToken::default();
+
+        webview
+          .add_PermissionRequested(
+            &PermissionRequestedEventHandler::create(Box::new(move |_, args| {
+              let Some(args) = args else { return Ok(()) };
+              let mut kind = COREWEBVIEW2_PERMISSION_KIND::default();
+              args.PermissionKind(&mut kind)?;
+              // 対象外の種類はwebviewに任せる
+              let Some(kind) = to_kind(kind) else { return Ok(()) };
+
+              let mut uri = PWSTR::null();
+              args.Uri(&mut uri)?;
+              let origin = normalize_origin(&take_pwstr(uri));
+
+              // webview側のプロファイルには保存させない
+              if let Ok(args_3) = args.cast::<ICoreWebView2PermissionRequestedEventArgs3>() {
+                args_3.SetSavesInProfile(BOOL::from(false))?;
+              }
+
+              let state = app.state::<AppState>();
+              let value = state.config.read().unwrap().permissions.get(&origin, kind);
+              match value {
+                PermissionState::Allow => args.SetState(COREWEBVIEW2_PERMISSION_STATE_ALLOW)?,
+                PermissionState::Deny => args.SetState(COREWEBVIEW2_PERMISSION_STATE_DENY)?,
+                PermissionState::Ask => {
+                  if let Err(e) = ask(&app, args, origin, kind) {
+                    println!("failed to ask permission: {}", e);
+                  }
+                }
+              }
+
+              Ok(())
+            })),
+            &mut token,
+          )
+          .unwrap();
+      }
+    }
+  })?;
+
+  Ok(())
+}
+
+/// メインウィンドウにダイアログを出して答えが出るまでリクエストを保留する
+unsafe fn ask(
+  app: &AppHandle,
+  args: ICoreWebView2PermissionRequestedEventArgs,
+  origin: String,
+  kind: PermissionKind,
+) -> anyhow::Result<()> {
+  let main_window = app.get_webview_window(MAIN_LABEL).context("main window is not found")?;
+  // 保留してから失敗すると完了されずに残るので、先にフォーカスする
+  window_focus(&main_window)?;
+  let deferral = args.GetDeferral()?;
+  let id = NEXT_ID.fetch_add(1, Ordering::AcqRel);
+  PENDING.with_borrow_mut(|v| v.insert(id, (args, deferral)));
+
+  app
+    .dialog()
+    .message(format!("{} wants to use {:?}.", origin, kind))
+    .title("Permission request")
+    .kind(MessageDialogKind::Warning)
+    .buttons(MessageDialogButtons::OkCancelCustom("Allow".into(), "Deny".into()))
+    .parent(&main_window)
+    .show({
+      let app = app.clone();
+      move |allowed| {
+        let res = app.run_on_main_thread(move || {
+          if let Err(e) = unsafe { resolve(id, allowed) } {
+            println!("failed to resolve permission: {}", e);
+          }
+        });
+        if let Err(e) = res {
+          println!("failed to resolve permission: {}", e);
+        }
+      }
+    });
+
+  Ok(())
+}
+
+unsafe fn resolve(id: u32, allowed: bool) -> anyhow::Result<()> {
+  let (args, deferral) = PENDING
+    .with_borrow_mut(|v| v.remove(&id))
+    .context("permission request is not found")?;
+  let value = if allowed {
+    COREWEBVIEW2_PERMISSION_STATE_ALLOW
+  } else {
+    COREWEBVIEW2_PERMISSION_STATE_DENY
+  };
+  args.SetState(value)?;
+  deferral.Complete()?;
+
+  Ok(())
+}
+
+pub mod command {
+  use configu::Configurable;
+  use specta::specta;
+  use tauri::{command, State};
+
+  use crate::util::{AppState, ErrToString};
+
+  use super::table::{PermissionKind, PermissionState, SitePermission};
+
+  #[command]
+  #[specta]
+  pub fn get_permissions(state: State<'_, AppState>) -> Vec<SitePermission> {
+    state.config.read().unwrap().permissions.list().to_vec()
+  }
+
+  /// Askを指定すると設定を消す
+  #[command]
+  #[specta]
+  pub fn set_permission(state: State<'_, AppState>, origin: String, kind: PermissionKind, value: PermissionState) -> Result<(), String> {
+    state.config.write().unwrap().permissions.set(&origin, kind, value);
+    state.config.save().err_to_string()?;
+
+    Ok(())
+  }
+
+  /// origin -> Noneならすべてのオリジンの設定を消す
+  #[command]
+  #[specta]
+  pub fn reset_permissions(state: State<'_, AppState>, origin: Option<String>) -> Result<(), String> {
+    state.config.write().unwrap().permissions.reset(origin.as_deref());
+    state.config.save().err_to_string()?;
+
+    Ok(())
+  }
+}
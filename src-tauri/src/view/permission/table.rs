@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, Type)]
+pub enum PermissionKind {
+  Camera,
+  Microphone,
+  Notifications,
+  Geolocation,
+  Clipboard,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum PermissionState {
+  Allow,
+  Deny,
+  /// 毎回メインウィンドウで確認する
+  #[default]
+  Ask,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub struct SitePermission {
+  pub origin: String,
+  pub kind: PermissionKind,
+  pub state: PermissionState,
+}
+
+/// オリジンごとの許可設定、Askは保存しない
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, Type)]
+#[serde(transparent)]
+pub struct PermissionTable(pub Vec<SitePermission>);
+
+impl PermissionTable {
+  pub fn get(&self, origin: &str, kind: PermissionKind) -> PermissionState {
+    let origin = normalize_origin(origin);
+    self
+      .0
+      .iter()
+      .find(|v| v.origin == origin && v.kind == kind)
+      .map(|v| v.state)
+      .unwrap_or_default()
+  }
+
+  pub fn set(&mut self, origin: &str, kind: PermissionKind, state: PermissionState) {
+    let origin = normalize_origin(origin);
+    self.0.retain(|v| !(v.origin == origin && v.kind == kind));
+    if state != PermissionState::Ask {
+      self.0.push(SitePermission { origin, kind, state });
+    }
+  }
+
+  /// origin -> Noneならすべて消す
+  pub fn reset(&mut self, origin: Option<&str>) {
+    match origin {
+      Some(origin) => {
+        let origin = normalize_origin(origin);
+        self.0.retain(|v| v.origin != origin);
+      }
+      None => self.0.clear(),
+    }
+  }
+
+  pub fn list(&self) -> &[SitePermission] {
+    &self.0
+  }
+}
+
+/// `https://example.com/path` -> `https://example.com`
+/// urlとして読めない場合は前後の空白と末尾の`/`を取り除くだけ
+pub fn normalize_origin(origin: &str) -> String {
+  match Url::parse(origin.trim()) {
+    Ok(url) if url.has_host() => url.origin().ascii_serialization(),
+    _ => origin.trim().trim_end_matches('/').to_ascii_lowercase(),
+  }
+}
@@ -0,0 +1,75 @@
+use anyhow::bail;
+
+use crate::util::AppState;
+
+/// プロファイルを使っているオーバーレイがあればErrにする
+/// webviewがデータディレクトリを開いている間は消せないため
+pub fn ensure_unused(state: &AppState, name: &str) -> anyhow::Result<()> {
+  let in_use = state
+    .windows
+    .lock()
+    .unwrap()
+    .iter()
+    .any(|v| v.profile.as_deref().is_some_and(|v| v.eq_ignore_ascii_case(name)));
+  if in_use {
+    bail!("profile {} is used by an open overlay", name);
+  }
+
+  Ok(())
+}
+
+pub mod command {
+  use configu::Configurable;
+  use specta::specta;
+  use tauri::{command, State};
+
+  use crate::{
+    util::{self, AppState, ErrToString},
+    view::extension::registry::ExtensionRegistry,
+  };
+
+  use super::ensure_unused;
+
+  #[command]
+  #[specta]
+  pub fn list_profiles(state: State<'_, AppState>) -> Vec<String> {
+    state.config.read().unwrap().profiles.clone()
+  }
+
+  #[command]
+  #[specta]
+  pub fn create_profile(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    util::create_profile(&state.dir, &mut state.config.write().unwrap().profiles, &name).err_to_string()?;
+    state.config.save().err_to_string()?;
+
+    Ok(())
+  }
+
+  #[command]
+  #[specta]
+  pub fn delete_profile(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    ensure_unused(&state, &name).err_to_string()?;
+    let name = {
+      let mut config = state.config.write().unwrap();
+      let name = util::delete_profile(&state.dir, &mut config.profiles, &name).err_to_string()?;
+      config.proxy.profiles.remove(&name);
+      name
+    };
+    let mut extensions = ExtensionRegistry::load(&state.dir).err_to_string()?;
+    extensions.remove_profile(&name);
+    extensions.save(&state.dir).err_to_string()?;
+    state.config.save().err_to_string()?;
+
+    Ok(())
+  }
+
+  /// cookieやキャッシュ等をすべて消す
+  #[command]
+  #[specta]
+  pub fn clear_profile(state: State<'_, AppState>, name: String) -> Result<(), String> {
+    ensure_unused(&state, &name).err_to_string()?;
+    util::clear_profile(&state.dir, &state.config.read().unwrap().profiles, &name).err_to_string()?;
+
+    Ok(())
+  }
+}
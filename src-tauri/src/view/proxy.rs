@@ -0,0 +1,73 @@
+pub mod command {
+  use anyhow::Context;
+  use configu::Configurable;
+  use specta::specta;
+  use tauri::{command, AppHandle, State};
+
+  use crate::{
+    util::{AppState, ErrToString, ProxyConfig, ProxySettings},
+    view::ctrl::sync_windows,
+  };
+
+  #[command]
+  #[specta]
+  pub fn get_proxy(state: State<'_, AppState>) -> ProxySettings {
+    state.config.read().unwrap().proxy.clone()
+  }
+
+  /// profile -> Noneならグローバルの設定、Someでproxy -> Noneならプロファイルの設定を消す
+  /// 開いているオーバーレイには作り直すまで反映されない
+  #[command]
+  #[specta]
+  pub fn set_proxy(state: State<'_, AppState>, profile: Option<String>, proxy: Option<ProxyConfig>) -> Result<(), String> {
+    if let Some(proxy) = &proxy {
+      proxy.validate().err_to_string()?;
+    }
+
+    {
+      let mut config = state.config.write().unwrap();
+      match (profile, proxy) {
+        (None, proxy) => config.proxy.global = proxy.unwrap_or_default(),
+        (Some(profile), Some(proxy)) => {
+          if !config.profiles.contains(&profile) {
+            return Err(format!("profile {} is not found", profile));
+          }
+          config.proxy.profiles.insert(profile, proxy);
+        }
+        (Some(profile), None) => {
+          config.proxy.profiles.remove(&profile);
+        }
+      }
+    }
+    state.config.save().err_to_string()?;
+
+    Ok(())
+  }
+
+  /// labelのオーバーレイだけに使うプロキシ、proxy -> Noneならプロファイルかグローバルの設定に戻す
+  /// webviewの引数は作るときにしか渡せないので、閉じて開き直すまで反映されない
+  #[command]
+  #[specta]
+  pub fn set_overlay_proxy(app: AppHandle, state: State<'_, AppState>, label: String, proxy: Option<ProxyConfig>) -> Result<(), String> {
+    if let Some(proxy) = &proxy {
+      proxy.validate().err_to_string()?;
+    }
+
+    let window_data = state
+      .get_window_data(&label)
+      .context("failure to get window data")
+      .err_to_string()?;
+    *window_data.proxy.lock().unwrap() = proxy;
+    state.emit_windows(&app);
+    sync_windows(&state).err_to_string()?;
+
+    Ok(())
+  }
+
+  /// 保存せずにプロキシのurlとバイパスリストの書式を確認する
+  #[command]
+  #[specta]
+  pub fn validate_proxy(proxy: ProxyConfig) -> Result<(), String> {
+    proxy.validate().err_to_string()
+  }
+}
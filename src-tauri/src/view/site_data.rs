@@ -0,0 +1,245 @@
+use std::{sync::mpsc, time::Duration};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use webview2_com::{
+  CallDevToolsProtocolMethodCompletedHandler, ClearBrowsingDataCompletedHandler,
+  Microsoft::Web::WebView2::Win32::{
+    ICoreWebView2Profile2, ICoreWebView2_13, COREWEBVIEW2_BROWSING_DATA_KINDS, COREWEBVIEW2_BROWSING_DATA_KINDS_CACHE_STORAGE,
+    COREWEBVIEW2_BROWSING_DATA_KINDS_COOKIES, COREWEBVIEW2_BROWSING_DATA_KINDS_DISK_CACHE, COREWEBVIEW2_BROWSING_DATA_KINDS_INDEXED_DB,
+    COREWEBVIEW2_BROWSING_DATA_KINDS_LOCAL_STORAGE, COREWEBVIEW2_BROWSING_DATA_KINDS_SERVICE_WORKERS,
+  },
+};
+use windows::core::{Interface, HSTRING};
+
+use crate::util::{AppState, DEFAULT_PROFILE};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum SiteDataKind {
+  Cookies,
+  /// Originの場合はCache Storageのみ、Profileの場合はHTTPキャッシュも含む
+  Cache,
+  /// localStorageとIndexedDB
+  LocalStorage,
+  ServiceWorkers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Type)]
+pub enum ClearScope {
+  /// オーバーレイで開いているページのオリジン
+  Origin,
+  /// オーバーレイのプロファイル全体
+  Profile,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Type)]
+pub struct ClearReport {
+  pub scope: ClearScope,
+  /// オリジンかプロファイル名
+  pub target: String,
+  pub cleared: Vec<SiteDataKind>,
+}
+
+/// DevToolsのStorage.clearDataForOriginに渡すstorageTypes
+pub fn storage_types(kinds: &[SiteDataKind]) -> String {
+  kinds
+    .iter()
+    .map(|v| match v {
+      SiteDataKind::Cookies => "cookies",
+      SiteDataKind::Cache => "cache_storage",
+      SiteDataKind::LocalStorage => "local_storage,indexeddb",
+      SiteDataKind::ServiceWorkers => "service_workers",
+    })
+    .collect::<Vec<_>>()
+    .join(",")
+}
+
+fn browsing_data_kinds(kinds: &[SiteDataKind]) -> COREWEBVIEW2_BROWSING_DATA_KINDS {
+  kinds.iter().fold(COREWEBVIEW2_BROWSING_DATA_KINDS(0), |acc, v| {
+    acc
+      | match v {
+        SiteDataKind::Cookies => COREWEBVIEW2_BROWSING_DATA_KINDS_COOKIES,
+        SiteDataKind::Cache => COREWEBVIEW2_BROWSING_DATA_KINDS_DISK_CACHE | COREWEBVIEW2_BROWSING_DATA_KINDS_CACHE_STORAGE,
+        SiteDataKind::LocalStorage => COREWEBVIEW2_BROWSING_DATA_KINDS_LOCAL_STORAGE | COREWEBVIEW2_BROWSING_DATA_KINDS_INDEXED_DB,
+        SiteDataKind::ServiceWorkers => COREWEBVIEW2_BROWSING_DATA_KINDS_SERVICE_WORKERS,
+      }
+  })
+}
+
+/// 完了するまで待つのでメインスレッドから呼ばないこと
+pub fn clear_site_data(
+  window: &WebviewWindow,
+  profile: Option<&str>,
+  scope: ClearScope,
+  kinds: &[SiteDataKind],
+) -> anyhow::Result<ClearReport> {
+  let mut cleared = Vec::with_capacity(kinds.len());
+  for kind in kinds {
+    if !cleared.contains(kind) {
+      cleared.push(*kind);
+    }
+  }
+  if cleared.is_empty() {
+    return Ok(ClearReport {
+      scope,
+      target: String::new(),
+      cleared,
+    });
+  }
+
+  let (tx, rx) = mpsc::channel::<windows::core::Result<()>>();
+  let target = match scope {
+    ClearScope::Origin => {
+      let origin = window.url()?.origin().ascii_serialization();
+      let params = serde_json::json!({
+        "origin": origin,
+        "storageTypes": storage_types(&cleared),
+      })
+      .to_string();
+
+      window.with_webview(move |webview| {
+        #[cfg(windows)]
+        unsafe {
+          let res = (|| {
+            let controller = webview.controller();
+            let webview = controller.CoreWebView2()?;
+            webview.CallDevToolsProtocolMethod(
+              &HSTRING::from("Storage.clearDataForOrigin"),
+              &HSTRING::from(params),
+              &CallDevToolsProtocolMethodCompletedHandler::create(Box::new({
+                let tx = tx.clone();
+                move |res, _| {
+                  let _ = tx.send(res);
+                  Ok(())
+                }
+              })),
+            )
+          })();
+          if let Err(e) = res {
+            let _ = tx.send(Err(e));
+          }
+        }
+      })?;
+
+      origin
+    }
+    ClearScope::Profile => {
+      let data_kinds = browsing_data_kinds(&cleared);
+      window.with_webview(move |webview| {
+        #[cfg(windows)]
+        unsafe {
+          let res = (|| {
+            let controller = webview.controller();
+            let webview = controller.CoreWebView2()?;
+            let profile = webview.cast::<ICoreWebView2_13>()?.Profile()?.cast::<ICoreWebView2Profile2>()?;
+            profile.ClearBrowsingData(
+              data_kinds,
+              &ClearBrowsingDataCompletedHandler::create(Box::new({
+                let tx = tx.clone();
+                move |res| {
+                  let _ = tx.send(res);
+                  Ok(())
+                }
+              })),
+            )
+          })();
+          if let Err(e) = res {
+            let _ = tx.send(Err(e));
+          }
+        }
+      })?;
+
+      profile.unwrap_or(DEFAULT_PROFILE).to_string()
+    }
+  };
+
+  rx.recv_timeout(TIMEOUT).context("timed out while clearing site data")??;
+
+  Ok(ClearReport { scope, target, cleared })
+}
+
+/// トレイから呼ぶ、開いているすべてのオーバーレイのキャッシュを消して結果を表示する
+pub fn clear_cache_all(app: &AppHandle) {
+  let state = app.state::<AppState>();
+  let labels = state
+    .windows
+    .lock()
+    .unwrap()
+    .iter()
+    .map(|v| v.label.to_string())
+    .collect::<Vec<_>>();
+
+  let mut cleared = Vec::new();
+  let mut failed = Vec::new();
+  for label in labels {
+    let Some(window) = app.get_webview_window(&label) else {
+      continue;
+    };
+    match clear_site_data(
+      &window,
+      None,
+      ClearScope::Origin,
+      &[SiteDataKind::Cache, SiteDataKind::ServiceWorkers],
+    ) {
+      Ok(report) => cleared.push(report.target),
+      Err(e) => failed.push(format!("{}: {}", window.title().unwrap_or(label), e)),
+    }
+  }
+
+  let message = if cleared.is_empty() && failed.is_empty() {
+    "No overlay is open.".to_string()
+  } else {
+    let mut message = format!("Cleared cache of {} overlay(s).", cleared.len());
+    for v in cleared {
+      message += &format!("\n{}", v);
+    }
+    if !failed.is_empty() {
+      message += "\n\nFailed:";
+      for v in failed {
+        message += &format!("\n{}", v);
+      }
+    }
+    message
+  };
+
+  app
+    .dialog()
+    .message(message)
+    .title("Clear cache")
+    .kind(MessageDialogKind::Info)
+    .show(|_| ());
+}
+
+pub mod command {
+  use anyhow::Context;
+  use specta::specta;
+  use tauri::{command, AppHandle, Manager, State};
+
+  use crate::util::{AppState, ErrToString};
+
+  use super::{ClearReport, ClearScope, SiteDataKind};
+
+  /// labelのオーバーレイのオリジンかプロファイルのデータを消す
+  #[command]
+  #[specta]
+  pub async fn clear_site_data(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    label: String,
+    scope: ClearScope,
+    kinds: Vec<SiteDataKind>,
+  ) -> Result<ClearReport, String> {
+    let window = app.get_webview_window(&label).context("window is not found").err_to_string()?;
+    let window_data = state
+      .get_window_data(&label)
+      .context("failure to get window data")
+      .err_to_string()?;
+
+    super::clear_site_data(&window, window_data.profile.as_deref(), scope, &kinds).err_to_string()
+  }
+}
@@ -1,11 +1,20 @@
 use crate::util::{AppState, ErrToString, WindowData};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use std::sync::Arc;
 use tauri::{AppHandle, Manager, PhysicalPosition, State, WebviewWindow};
+use webview2_com::CallDevToolsProtocolMethodCompletedHandler;
+use windows::{
+  core::{w, HSTRING, PCWSTR},
+  Win32::{
+    Foundation::HWND,
+    UI::{Shell::ShellExecuteW, WindowsAndMessaging::SW_SHOWNORMAL},
+  },
+};
 
 pub const WINDOW_LABEL_PREFIX: &str = "window_";
 pub const CTRL_LABEL_PREFIX: &str = "ctrl_";
+pub const STYLE_ID_PREFIX: &str = "relais-style-";
 
 pub fn to_ctrl_label<'a, T: Into<&'a str>>(label: T) -> String {
   CTRL_LABEL_PREFIX.to_string() + label.into()
@@ -82,8 +91,121 @@ pub fn window_hide(window: &WebviewWindow) -> anyhow::Result<()> {
   Ok(())
 }
 
+/// すべてのオーバーレイとそのコントロールを表示または非表示にする
+pub fn set_overlays_visible(app: &AppHandle, visible: bool) -> anyhow::Result<()> {
+  let state = app.state::<AppState>();
+  let labels = state.windows.lock().unwrap().iter().map(|v| v.label.clone()).collect::<Vec<_>>();
+  for label in labels {
+    let windows = [
+      app.get_webview_window(&label),
+      app.get_webview_window(&to_ctrl_label(label.as_str())),
+    ];
+    for window in windows.into_iter().flatten() {
+      if visible {
+        window.show()?;
+      } else {
+        window.hide()?;
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// 1つでも表示していればすべて隠し、そうでなければすべて表示する
+/// 表示したらtrue
+pub fn toggle_overlays_visible(app: &AppHandle) -> anyhow::Result<bool> {
+  let state = app.state::<AppState>();
+  let labels = state.windows.lock().unwrap().iter().map(|v| v.label.clone()).collect::<Vec<_>>();
+  let visible = labels
+    .iter()
+    .filter_map(|v| app.get_webview_window(v))
+    .any(|v| v.is_visible().unwrap_or(false));
+  set_overlays_visible(app, !visible)?;
+
+  Ok(!visible)
+}
+
 pub fn window_minimize(window: &WebviewWindow) -> anyhow::Result<()> {
   window.minimize()?;
 
   Ok(())
 }
+
+/// 既定のブラウザでurlを開く
+pub fn open_browser(url: &url::Url) -> anyhow::Result<()> {
+  if !matches!(url.scheme(), "http" | "https") {
+    bail!("unsupported scheme: {}", url.scheme());
+  }
+
+  let res = unsafe {
+    ShellExecuteW(
+      HWND::default(),
+      w!("open"),
+      &HSTRING::from(url.as_str()),
+      PCWSTR::null(),
+      PCWSTR::null(),
+      SW_SHOWNORMAL,
+    )
+  };
+  // 32以下はエラー
+  if res.0 as isize <= 32 {
+    bail!("failed to open {}", url);
+  }
+
+  Ok(())
+}
+
+/// ページに`<style>`を挿入する、同じidのstyleがあれば中身を置き換える
+pub fn inject_style(window: &WebviewWindow, id: &str, css: &str) -> anyhow::Result<()> {
+  let id = serde_json::to_string(&(STYLE_ID_PREFIX.to_string() + id))?;
+  let css = serde_json::to_string(css)?;
+  window.eval(format!(
+    r#"(() => {{
+  let el = document.getElementById({id});
+  if (!el) {{
+    el = document.createElement("style");
+    el.id = {id};
+    (document.head ?? document.documentElement).appendChild(el);
+  }}
+  el.textContent = {css};
+}})();"#
+  ))?;
+
+  Ok(())
+}
+
+/// inject_styleで挿入した`<style>`を取り除く
+pub fn remove_style(window: &WebviewWindow, id: &str) -> anyhow::Result<()> {
+  let id = serde_json::to_string(&(STYLE_ID_PREFIX.to_string() + id))?;
+  window.eval(format!("document.getElementById({id})?.remove();"))?;
+
+  Ok(())
+}
+
+/// DevToolsProtocolのメソッドを呼ぶ、結果は待たずに失敗した場合のみ出力する
+pub fn call_devtools(window: &WebviewWindow, method: &str, params: &serde_json::Value) -> anyhow::Result<()> {
+  let method = method.to_string();
+  let params = HSTRING::from(params.to_string());
+  window.with_webview(move |webview| {
+    #[cfg(windows)]
+    unsafe {
+      let controller = webview.controller();
+      let webview = controller.CoreWebView2().unwrap();
+      webview
+        .CallDevToolsProtocolMethod(
+          &HSTRING::from(&method),
+          &params,
+          &CallDevToolsProtocolMethodCompletedHandler::create(Box::new(move |res, _| {
+            if let Err(e) = res {
+              println!("failed to call {}: {}", method, e);
+            }
+            Ok(())
+          })),
+        )
+        .unwrap();
+    }
+  })?;
+
+  Ok(())
+}
@@ -0,0 +1,179 @@
+use app_lib::view::ctrl::blocker::filter::{BlockerSettings, FilterEngine, Request, ResourceType, Verdict};
+
+const SAMPLE: &str = r#"[Adblock Plus 2.0]
+! Title: sample
+||ads.example.com^
+||tracker.net^$third-party
+/banner/*/ad_$image
+|https://cdn.example.org/ads.js|
+@@||ads.example.com/allowed/
+||cdn.example.org^$script,domain=news.com|~sports.news.com
+||popup.example.com^$popup
+||important.example^$important
+@@||important.example^
+/^https?:\/\/regex\.example\//
+##.ad-banner
+##div[id^="sponsor"]
+news.com##.cookie-banner
+~sports.news.com,news.com##.promo
+news.com#@#.ad-banner
+example.com##+js(nobab)
+example.com#?#div:has-text(Ad)
+"#;
+
+fn check(engine: &FilterEngine, url: &str, source: Option<&str>, kind: ResourceType) -> Verdict {
+  engine.check(&Request {
+    url,
+    source_host: source,
+    kind,
+  })
+}
+
+#[test]
+fn parse_stats() {
+  let stats = FilterEngine::parse(SAMPLE).stats();
+  assert_eq!(stats.network, 8);
+  assert_eq!(stats.cosmetic, 4);
+  // $popup、正規表現、スクリプトレット、拡張セレクター
+  assert_eq!(stats.skipped, 4);
+}
+
+#[test]
+fn host_anchor() {
+  let engine = FilterEngine::parse(SAMPLE);
+  let image = ResourceType::Image;
+
+  assert_eq!(check(&engine, "https://ads.example.com/a.png", None, image), Verdict::Block);
+  assert_eq!(check(&engine, "https://sub.ads.example.com/a.png", None, image), Verdict::Block);
+  assert_eq!(check(&engine, "https://ADS.example.com:8080", None, image), Verdict::Block);
+  // ホストの途中からは一致しない
+  assert_eq!(check(&engine, "https://badads.example.com/", None, image), Verdict::NoMatch);
+  assert_eq!(check(&engine, "https://ads.example.community/", None, image), Verdict::NoMatch);
+  // 例外
+  assert_eq!(check(&engine, "https://ads.example.com/allowed/a.png", None, image), Verdict::Allow);
+  // 種類の指定がなければページ本体はブロックしない
+  assert_eq!(
+    check(&engine, "https://ads.example.com/", None, ResourceType::Document),
+    Verdict::NoMatch
+  );
+}
+
+#[test]
+fn wildcard_and_anchors() {
+  let engine = FilterEngine::parse(SAMPLE);
+
+  assert_eq!(
+    check(&engine, "https://site.com/banner/300x250/ad_1.gif", None, ResourceType::Image),
+    Verdict::Block
+  );
+  assert_eq!(
+    check(&engine, "https://site.com/banner/300x250/ad_1.gif", None, ResourceType::Script),
+    Verdict::NoMatch
+  );
+  assert_eq!(
+    check(&engine, "https://cdn.example.org/ads.js", None, ResourceType::Other),
+    Verdict::Block
+  );
+  // 末尾の`|`
+  assert_eq!(
+    check(&engine, "https://cdn.example.org/ads.js?v=1", None, ResourceType::Other),
+    Verdict::NoMatch
+  );
+  // `$important`は例外より優先される
+  assert_eq!(
+    check(&engine, "https://important.example/x", None, ResourceType::Script),
+    Verdict::Block
+  );
+}
+
+#[test]
+fn wildcards() {
+  let engine = FilterEngine::parse("/a**b^c*d|\n|https://*.example.net/*^x^\n/start*middle*\n");
+  let other = ResourceType::Other;
+
+  assert_eq!(check(&engine, "https://site.com/ab/cd", None, other), Verdict::Block);
+  assert_eq!(check(&engine, "https://site.com/a/x/b?c/d", None, other), Verdict::Block);
+  assert_eq!(check(&engine, "https://site.com/a/x/b?c/d/e", None, other), Verdict::NoMatch);
+  // `^`はurlの終わりにも一致する
+  assert_eq!(check(&engine, "https://cdn.example.net/a/x", None, other), Verdict::Block);
+  assert_eq!(check(&engine, "https://cdn.example.net/a/xy", None, other), Verdict::NoMatch);
+  assert_eq!(check(&engine, "https://site.com/start/then/middle", None, other), Verdict::Block);
+  assert_eq!(check(&engine, "https://site.com/middle/start", None, other), Verdict::NoMatch);
+}
+
+#[test]
+fn many_wildcards() {
+  // 戻りながら探すと一致しないurlで指数的に遅くなる
+  let engine = FilterEngine::parse(&format!("{}b\n", "*a".repeat(20)));
+  let url = format!("https://site.com/{}", "a".repeat(2000));
+
+  assert_eq!(check(&engine, &url, None, ResourceType::Other), Verdict::NoMatch);
+  assert_eq!(check(&engine, &(url + "b"), None, ResourceType::Other), Verdict::Block);
+}
+
+#[test]
+fn options() {
+  let engine = FilterEngine::parse(SAMPLE);
+  let script = ResourceType::Script;
+
+  assert_eq!(check(&engine, "https://tracker.net/t.js", Some("news.com"), script), Verdict::Block);
+  assert_eq!(
+    check(&engine, "https://tracker.net/t.js", Some("www.tracker.net"), script),
+    Verdict::NoMatch
+  );
+
+  let url = "https://cdn.example.org/lib.js";
+  assert_eq!(check(&engine, url, Some("www.news.com"), script), Verdict::Block);
+  assert_eq!(check(&engine, url, Some("sports.news.com"), script), Verdict::NoMatch);
+  assert_eq!(check(&engine, url, Some("other.com"), script), Verdict::NoMatch);
+  assert_eq!(check(&engine, url, None, script), Verdict::NoMatch);
+  assert_eq!(check(&engine, url, Some("news.com"), ResourceType::Image), Verdict::NoMatch);
+}
+
+#[test]
+fn cosmetic() {
+  let engine = FilterEngine::parse(SAMPLE);
+
+  let selectors = engine.cosmetic_selectors("example.com");
+  assert_eq!(selectors, vec![".ad-banner", "div[id^=\"sponsor\"]"]);
+
+  let selectors = engine.cosmetic_selectors("www.news.com");
+  assert!(!selectors.contains(&".ad-banner"));
+  assert!(selectors.contains(&".cookie-banner"));
+  assert!(selectors.contains(&".promo"));
+
+  let selectors = engine.cosmetic_selectors("sports.news.com");
+  assert!(!selectors.contains(&".promo"));
+
+  let css = engine.cosmetic_css("example.com");
+  assert_eq!(css.lines().count(), 2);
+  assert!(css.starts_with(".ad-banner { display: none !important; }"));
+}
+
+#[test]
+fn many_lists() {
+  let mut engine = FilterEngine::parse("||a.example^\n");
+  engine.add_list("||b.example^\n##.b\n");
+
+  assert_eq!(engine.stats().network, 2);
+  assert_eq!(engine.stats().cosmetic, 1);
+  assert_eq!(check(&engine, "https://b.example/", None, ResourceType::Xhr), Verdict::Block);
+}
+
+#[test]
+fn allowlist() {
+  let mut settings = BlockerSettings {
+    lists: Vec::new(),
+    allowlist: vec!["example.com".into(), "*.news.com".into()],
+  };
+  assert!(settings.is_allowed("example.com"));
+  assert!(settings.is_allowed("WWW.example.com"));
+  assert!(settings.is_allowed("sports.news.com"));
+  assert!(!settings.is_allowed("badexample.com"));
+
+  // サブドメインで切り替えても元のエントリーを取り除く
+  assert!(!settings.toggle_allowed("www.example.com"));
+  assert!(!settings.is_allowed("example.com"));
+  assert!(settings.toggle_allowed("other.org"));
+  assert_eq!(settings.allowlist, vec!["*.news.com".to_string(), "other.org".to_string()]);
+}
@@ -0,0 +1,189 @@
+use std::{
+  env,
+  io::Write,
+  net::{Ipv4Addr, TcpListener},
+  path::PathBuf,
+  thread,
+};
+
+use anyhow::bail;
+use app_lib::cli::{
+  args::{parse_args, CliCommand, OpenArgs},
+  deep_link::{parse_deep_link, DeepLinkSettings},
+  instance::{acquire, forward, serve, Instance, Reply},
+};
+
+fn parse(args: &str) -> anyhow::Result<CliCommand> {
+  parse_args(args.split_whitespace())
+}
+
+#[test]
+fn commands() {
+  assert_eq!(parse("").unwrap(), CliCommand::Run);
+  assert_eq!(parse("list").unwrap(), CliCommand::List);
+  assert_eq!(parse("toggle").unwrap(), CliCommand::Toggle);
+  assert_eq!(parse("close 2").unwrap(), CliCommand::Close("2".into()));
+  assert_eq!(parse("--help").unwrap(), CliCommand::Help);
+  assert_eq!(parse("-V").unwrap(), CliCommand::Version);
+  assert!(parse("list").unwrap().needs_instance());
+  assert!(!parse("open example.com").unwrap().needs_instance());
+
+  assert!(parse("unknown").is_err());
+  assert!(parse("close").is_err());
+  assert!(parse("list extra").is_err());
+}
+
+#[test]
+fn open() {
+  let CliCommand::Open(open) =
+    parse("open example.com/live --pin --opacity 60 --zoom=120 --mobile --at -100,50 --size 400x300 --profile work").unwrap()
+  else {
+    panic!("not open");
+  };
+  assert_eq!(
+    open,
+    OpenArgs {
+      url: "https://example.com/live".parse().unwrap(),
+      profile: Some("work".into()),
+      pin: true,
+      opacity: Some(60),
+      zoom: Some(120),
+      mobile: true,
+      position: Some((-100, 50)),
+      size: Some((400, 300)),
+    }
+  );
+  assert_eq!(open.alpha(), Some(153));
+
+  assert_eq!(
+    parse("open http://localhost:8080").unwrap(),
+    CliCommand::Open(OpenArgs::new("http://localhost:8080".parse().unwrap()))
+  );
+}
+
+#[test]
+fn invalid_open() {
+  for args in [
+    "open",
+    "open --pin",
+    "open example.com other.com",
+    "open file:///C:/a.html",
+    "open example.com --opacity 101",
+    "open example.com --opacity",
+    "open example.com --zoom 10",
+    "open example.com --at 100",
+    "open example.com --size 0x300",
+    "open example.com --unknown",
+  ] {
+    assert!(parse(args).is_err(), "{}", args);
+  }
+}
+
+#[test]
+fn deep_link() {
+  let open = parse_deep_link("relais://open?url=https://example.com/chat&opacity=70&pin=1&mobile=1").unwrap();
+  assert_eq!(open.url.as_str(), "https://example.com/chat");
+  assert_eq!(open.opacity, Some(70));
+  assert!(open.pin && open.mobile);
+
+  // クエリを含むurlはエンコードして渡す
+  let open = parse_deep_link("relais:open?url=https%3A%2F%2Fexample.com%2F%3Fa%3D1%26b%3D2&pin=0&size=400x300&at=10,20").unwrap();
+  assert_eq!(open.url.as_str(), "https://example.com/?a=1&b=2");
+  assert!(!open.pin);
+  assert_eq!((open.size, open.position), (Some((400, 300)), Some((10, 20))));
+
+  assert_eq!(
+    parse_args(["RELAIS://open?url=example.com"]).unwrap(),
+    CliCommand::DeepLink(OpenArgs::new("https://example.com".parse().unwrap()))
+  );
+
+  for link in [
+    "relais://open",
+    "relais://close?url=https://example.com",
+    "relais://open?url=javascript:alert(1)",
+    "relais://open?url=file:///C:/a.html",
+    "relais://open?url=https://example.com&opacity=200",
+    "relais://open?url=https://example.com&pin=yes",
+    "relais://open?url=https://example.com&script=1",
+    "https://example.com",
+  ] {
+    assert!(parse_deep_link(link).is_err(), "{}", link);
+  }
+}
+
+#[test]
+fn deep_link_allowlist() {
+  let settings = DeepLinkSettings {
+    allowlist: vec!["example.com".into(), "https://chat.test/room/*".into()],
+  };
+  let allowed = |url: &str| settings.is_allowed(&url.parse().unwrap());
+
+  assert!(allowed("https://example.com/chat"));
+  assert!(allowed("https://live.example.com/"));
+  assert!(allowed("https://chat.test/room/1"));
+  assert!(!allowed("https://chat.test/admin"));
+  assert!(!allowed("https://example.com.evil.test/"));
+  assert!(!DeepLinkSettings::default().is_allowed(&"https://example.com".parse().unwrap()));
+}
+
+fn key_path(name: &str) -> PathBuf {
+  env::temp_dir().join(format!("relais-cli-test-{}-{}.key", std::process::id(), name))
+}
+
+fn free_port() -> u16 {
+  TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port()
+}
+
+#[test]
+fn single_instance() {
+  let port = free_port();
+  let key_path = key_path("single");
+
+  let Instance::Primary { listener, key } = acquire(port, &key_path).unwrap() else {
+    panic!("not primary");
+  };
+  serve(listener, key, |args| match args.first().map(String::as_str) {
+    Some("list") => Ok("1\twindow_a\n".into()),
+    _ => bail!("unknown command"),
+  });
+
+  let Instance::Secondary { stream, key } = acquire(port, &key_path).unwrap() else {
+    panic!("not secondary");
+  };
+  assert_eq!(forward(stream, &key, &["list".into()]).unwrap(), Reply::Ok("1\twindow_a\n".into()));
+
+  let Instance::Secondary { stream, key } = acquire(port, &key_path).unwrap() else {
+    panic!("not secondary");
+  };
+  assert_eq!(
+    forward(stream, &key, &["other".into()]).unwrap(),
+    Reply::Error("unknown command".into())
+  );
+
+  // 鍵を知らない接続では実行しない
+  let Instance::Secondary { stream, .. } = acquire(port, &key_path).unwrap() else {
+    panic!("not secondary");
+  };
+  assert_eq!(forward(stream, "wrong", &["list".into()]).unwrap(), Reply::Rejected);
+}
+
+#[test]
+fn unrelated_listener() {
+  let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+  let port = listener.local_addr().unwrap().port();
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let _ = stream.unwrap().write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n");
+    }
+  });
+
+  // 鍵ファイルがなければ確認できない
+  let key_path = key_path("unrelated");
+  assert!(acquire(port, &key_path).is_err());
+
+  std::fs::write(&key_path, "stale").unwrap();
+  let Instance::Secondary { stream, key } = acquire(port, &key_path).unwrap() else {
+    panic!("not secondary");
+  };
+  assert!(forward(stream, &key, &["list".into()]).is_err());
+}
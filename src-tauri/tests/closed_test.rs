@@ -0,0 +1,73 @@
+mod common;
+
+use std::fs;
+
+use app_lib::view::closed::stack::{ClosedStack, CLOSED_FILE, MAX_CLOSED};
+use common::temp_root;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+struct Entry {
+  url: String,
+  position: (i32, i32),
+  profile: Option<String>,
+}
+
+fn entry(i: i32) -> Entry {
+  Entry {
+    url: format!("https://example.com/{}", i),
+    position: (i, -i),
+    profile: None,
+  }
+}
+
+#[test]
+fn bounded() {
+  let mut stack = ClosedStack::default();
+  for i in 0..MAX_CLOSED as i32 + 5 {
+    stack.push(entry(i));
+  }
+  assert_eq!(stack.entries().len(), MAX_CLOSED);
+  // 新しいものが先頭で、古いものから捨てる
+  assert_eq!(stack.entries()[0], entry(MAX_CLOSED as i32 + 4));
+  assert_eq!(stack.entries()[MAX_CLOSED - 1], entry(5));
+}
+
+#[test]
+fn take_and_restore() {
+  let mut stack = ClosedStack::default();
+  for i in 0..3 {
+    stack.push(entry(i));
+  }
+
+  assert_eq!(stack.take(1), Some(entry(1)));
+  assert_eq!(stack.take(5), None);
+  assert_eq!(stack.pop(), Some(entry(2)));
+  assert_eq!(stack.entries(), [entry(0)]);
+
+  stack.restore(0, entry(2));
+  stack.restore(10, entry(1));
+  assert_eq!(stack.entries(), [entry(2), entry(0), entry(1)]);
+
+  stack.clear();
+  assert_eq!(stack.pop(), None);
+}
+
+#[test]
+fn persist() {
+  let root = temp_root("closed", "persist");
+  assert!(ClosedStack::<Entry>::load(&root).unwrap().entries().is_empty());
+
+  let mut stack = ClosedStack::default();
+  stack.push(entry(1));
+  stack.push(Entry {
+    profile: Some("work".into()),
+    ..entry(2)
+  });
+  stack.save(&root).unwrap();
+  assert!(root.join(CLOSED_FILE).exists());
+  assert_eq!(ClosedStack::<Entry>::load(&root).unwrap(), stack);
+
+  fs::write(root.join(CLOSED_FILE), "entries = 1").unwrap();
+  assert!(ClosedStack::<Entry>::load(&root).is_err());
+}
@@ -0,0 +1,9 @@
+use std::{env, fs, path::PathBuf};
+
+/// テストごとに別のディレクトリを使う
+pub fn temp_root(prefix: &str, name: &str) -> PathBuf {
+  let root = env::temp_dir().join(format!("relais-{}-test-{}-{}", prefix, std::process::id(), name));
+  let _ = fs::remove_dir_all(&root);
+  fs::create_dir_all(&root).unwrap();
+  root
+}
@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use app_lib::util::{filter_cookies, parse_json, parse_netscape, to_json, to_netscape, Cookie, CookieFormat, SameSite};
+
+const NETSCAPE: &str = "# Netscape HTTP Cookie File
+# https://curl.se/docs/http-cookies.html
+
+.example.com\tTRUE\t/\tTRUE\t1893456000\tsid\tabc123
+#HttpOnly_login.example.com\tFALSE\t/auth\tFALSE\t0\ttoken\txyz
+other.test\tTRUE\t/\tFALSE\t1893456000\tempty
+";
+
+fn cookie(name: &str, domain: &str) -> Cookie {
+  Cookie {
+    name: name.into(),
+    value: "v".into(),
+    domain: domain.into(),
+    path: "/".into(),
+    expires: None,
+    secure: false,
+    http_only: false,
+    same_site: None,
+  }
+}
+
+#[test]
+fn netscape_parse() {
+  let cookies = parse_netscape(NETSCAPE).unwrap();
+  assert_eq!(cookies.len(), 3);
+
+  assert_eq!(cookies[0].domain, ".example.com");
+  assert_eq!(cookies[0].name, "sid");
+  assert_eq!(cookies[0].value, "abc123");
+  assert_eq!(cookies[0].expires, Some(1893456000.0));
+  assert!(cookies[0].secure);
+  assert!(!cookies[0].http_only);
+
+  assert_eq!(cookies[1].domain, "login.example.com");
+  assert_eq!(cookies[1].path, "/auth");
+  assert_eq!(cookies[1].expires, None);
+  assert!(cookies[1].http_only);
+
+  // サブドメインが有効なら`.`を付ける、値が空なら最後のフィールドは省略できる
+  assert_eq!(cookies[2].domain, ".other.test");
+  assert_eq!(cookies[2].value, "");
+}
+
+#[test]
+fn netscape_errors() {
+  assert!(parse_netscape("example.com\tTRUE\t/\tFALSE\t0").is_err());
+  assert!(parse_netscape("example.com\tYES\t/\tFALSE\t0\tname\tvalue").is_err());
+  assert!(parse_netscape("example.com\tTRUE\t/\tFALSE\tnever\tname\tvalue").is_err());
+  assert!(parse_netscape("example.com\tTRUE\t/\tFALSE\t0\tname\tvalue\textra").is_err());
+  assert!(parse_netscape("# comment only\n\n").unwrap().is_empty());
+}
+
+#[test]
+fn netscape_roundtrip() {
+  let cookies = parse_netscape(NETSCAPE).unwrap();
+
+  assert_eq!(parse_netscape(&to_netscape(&cookies)).unwrap(), cookies);
+}
+
+#[test]
+fn json_roundtrip() {
+  let mut cookies = parse_netscape(NETSCAPE).unwrap();
+  cookies[0].same_site = Some(SameSite::Lax);
+
+  assert_eq!(parse_json(&to_json(&cookies).unwrap()).unwrap(), cookies);
+}
+
+#[test]
+fn json_from_extension() {
+  // ブラウザ拡張の書き出し形式
+  let text = r#"[
+    {"domain": ".example.com", "expirationDate": 1893456000.5, "httpOnly": true, "name": "sid", "path": "/", "sameSite": "no_restriction", "secure": true, "value": "abc"},
+    {"domain": "example.com", "name": "theme", "value": "dark", "sameSite": "strict"},
+    {"domain": "example.com", "name": "lang", "value": "ja", "sameSite": "unspecified"}
+  ]"#;
+  let cookies = parse_json(text).unwrap();
+
+  assert_eq!(cookies[0].expires, Some(1893456000.5));
+  assert!(cookies[0].http_only);
+  assert_eq!(cookies[0].same_site, Some(SameSite::NoRestriction));
+  assert_eq!(cookies[1].path, "/");
+  assert_eq!(cookies[1].expires, None);
+  assert_eq!(cookies[1].same_site, Some(SameSite::Strict));
+  assert_eq!(cookies[2].same_site, None);
+}
+
+#[test]
+fn format_from_path() {
+  assert_eq!(CookieFormat::from_path(Path::new("cookies.json")), CookieFormat::Json);
+  assert_eq!(CookieFormat::from_path(Path::new("Cookies.JSON")), CookieFormat::Json);
+  assert_eq!(CookieFormat::from_path(Path::new("cookies.txt")), CookieFormat::Netscape);
+  assert_eq!(CookieFormat::from_path(Path::new("cookies")), CookieFormat::Netscape);
+}
+
+#[test]
+fn filter_by_domain() {
+  let cookies = vec![
+    cookie("a", ".example.com"),
+    cookie("b", "login.example.com"),
+    cookie("c", "notexample.com"),
+    cookie("d", "other.test"),
+  ];
+
+  let names = |v: Vec<Cookie>| v.into_iter().map(|v| v.name).collect::<Vec<_>>();
+  assert_eq!(names(filter_cookies(cookies.clone(), &["Example.com".into()])), ["a", "b"]);
+  assert_eq!(names(filter_cookies(cookies.clone(), &["login.example.com".into()])), ["b"]);
+  assert_eq!(names(filter_cookies(cookies.clone(), &[])).len(), 4);
+}
+
+#[test]
+fn expired() {
+  let mut value = cookie("a", "example.com");
+  assert!(!value.is_expired(100.0));
+
+  value.expires = Some(50.0);
+  assert!(value.is_expired(100.0));
+  assert!(!value.is_expired(10.0));
+}
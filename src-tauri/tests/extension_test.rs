@@ -0,0 +1,145 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use app_lib::view::extension::{
+  manifest::{validate_version, Manifest},
+  registry::{extension_dir, extension_id, ExtensionRegistry, REGISTRY_FILE},
+};
+use common::temp_root;
+
+fn write_extension(dir: &Path, manifest: &str) {
+  fs::create_dir_all(dir.join("js")).unwrap();
+  fs::write(dir.join("manifest.json"), manifest).unwrap();
+  fs::write(dir.join("js/content.js"), "console.log(1);").unwrap();
+}
+
+const MANIFEST: &str = r#"{
+  "manifest_version": 3,
+  "name": "Dark Reader",
+  "version": "4.9.92",
+  "description": "Dark mode for every website",
+  "permissions": ["storage"],
+  "host_permissions": ["<all_urls>"],
+  "content_scripts": [{ "matches": ["<all_urls>"], "js": ["js/content.js"] }]
+}"#;
+
+#[test]
+fn parse_manifest() {
+  let manifest = Manifest::parse(&format!("\u{feff}{}", MANIFEST)).unwrap();
+  assert_eq!(manifest.manifest_version, 3);
+  assert_eq!(manifest.name, "Dark Reader");
+  assert_eq!(manifest.permissions, vec!["storage"]);
+  assert_eq!(manifest.host_permissions, vec!["<all_urls>"]);
+
+  assert!(Manifest::parse(r#"{ "manifest_version": 1, "name": "a", "version": "1" }"#).is_err());
+  assert!(Manifest::parse(r#"{ "manifest_version": 3, "name": " ", "version": "1" }"#).is_err());
+  assert!(Manifest::parse(r#"{ "manifest_version": 3, "version": "1" }"#).is_err());
+  // default_localeがない
+  assert!(Manifest::parse(r#"{ "manifest_version": 3, "name": "__MSG_name__", "version": "1" }"#).is_err());
+  assert!(Manifest::parse("not json").is_err());
+}
+
+#[test]
+fn versions() {
+  for version in ["1", "1.0", "1.2.3.4", "0.65535"] {
+    assert!(validate_version(version).is_ok(), "{}", version);
+  }
+  for version in ["", "1.", "1.2.3.4.5", "01", "1.65536", "1.0-beta", "v1"] {
+    assert!(validate_version(version).is_err(), "{}", version);
+  }
+}
+
+#[test]
+fn localized_name() {
+  let root = temp_root("extension", "locale");
+  let dir = root.join("source");
+  write_extension(
+    &dir,
+    r#"{ "manifest_version": 2, "name": "__MSG_extName__", "description": "__MSG_missing__", "version": "1.0", "default_locale": "en" }"#,
+  );
+  fs::create_dir_all(dir.join("_locales/en")).unwrap();
+  fs::write(
+    dir.join("_locales/en/messages.json"),
+    r#"{ "extname": { "message": "Localized" } }"#,
+  )
+  .unwrap();
+
+  let manifest = Manifest::read(&dir).unwrap();
+  assert_eq!(manifest.name, "Localized");
+  // 見つからなければそのまま
+  assert_eq!(manifest.description, "__MSG_missing__");
+
+  // _localesがあるのにdefault_localeがない
+  write_extension(&dir, r#"{ "manifest_version": 3, "name": "a", "version": "1" }"#);
+  assert!(Manifest::read(&dir).is_err());
+  assert!(Manifest::read(&root.join("none")).is_err());
+}
+
+#[test]
+fn ids() {
+  assert_eq!(extension_id("Dark Reader").unwrap(), "dark_reader");
+  assert_eq!(extension_id("  uBlock Origin (Lite) ").unwrap(), "ublock_origin_lite");
+  assert_eq!(extension_id("a-b").unwrap(), "a-b");
+  assert!(extension_id("日本語").is_err());
+}
+
+#[test]
+fn install_and_persist() {
+  let root = temp_root("extension", "install");
+  let source = root.join("source");
+  write_extension(&source, MANIFEST);
+
+  let mut registry = ExtensionRegistry::load(&root).unwrap();
+  assert!(registry.extensions.is_empty());
+
+  let entry = registry.install(&root, &source).unwrap();
+  assert_eq!(entry.id, "dark_reader");
+  assert!(entry.enabled.is_empty());
+  let dir = extension_dir(&root, "dark_reader");
+  assert!(dir.join("manifest.json").exists());
+  assert!(dir.join("js/content.js").exists());
+
+  registry.set_enabled("dark_reader", None, true).unwrap();
+  registry.set_enabled("dark_reader", Some("work"), true).unwrap();
+  registry.set_enabled("dark_reader", Some("work"), false).unwrap();
+  assert!(registry.set_enabled("missing", None, true).is_err());
+  let entry = registry.get("dark_reader").unwrap();
+  assert!(entry.is_enabled(None));
+  assert!(!entry.is_enabled(Some("work")));
+
+  registry.save(&root).unwrap();
+  assert!(root.join(REGISTRY_FILE).exists());
+  assert_eq!(ExtensionRegistry::load(&root).unwrap(), registry);
+
+  // インストールしたディレクトリ自体は入れ直せない
+  assert!(registry.install(&root, &dir).is_err());
+}
+
+#[test]
+fn update_and_uninstall() {
+  let root = temp_root("extension", "update");
+  let source = root.join("source");
+  write_extension(&source, MANIFEST);
+
+  let mut registry = ExtensionRegistry::default();
+  registry.install(&root, &source).unwrap();
+  registry.set_enabled("dark_reader", Some("work"), true).unwrap();
+
+  // 同じ名前なら置き換えて有効にしたプロファイルは残す
+  fs::write(source.join("manifest.json"), MANIFEST.replace("4.9.92", "4.9.93")).unwrap();
+  fs::remove_file(source.join("js/content.js")).unwrap();
+  let entry = registry.install(&root, &source).unwrap();
+  assert_eq!(entry.version, "4.9.93");
+  assert!(entry.is_enabled(Some("work")));
+  assert_eq!(registry.extensions.len(), 1);
+  assert!(!extension_dir(&root, "dark_reader").join("js/content.js").exists());
+
+  registry.remove_profile("work");
+  assert!(!registry.get("dark_reader").unwrap().is_enabled(Some("work")));
+
+  registry.uninstall(&root, "dark_reader").unwrap();
+  assert!(registry.extensions.is_empty());
+  assert!(!extension_dir(&root, "dark_reader").exists());
+  assert!(registry.uninstall(&root, "dark_reader").is_err());
+}
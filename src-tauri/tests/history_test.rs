@@ -0,0 +1,119 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use app_lib::view::history::store::{Bookmark, BookmarkList, HistoryStore, BOOKMARKS_FILE, HISTORY_FILE};
+use common::temp_root;
+
+fn lines(root: &Path) -> usize {
+  fs::read_to_string(root.join(HISTORY_FILE)).unwrap().lines().count()
+}
+
+fn bookmark(id: &str, url: &str, title: &str, tags: &[&str]) -> Bookmark<()> {
+  Bookmark {
+    id: id.into(),
+    url: url.into(),
+    title: title.into(),
+    tags: tags.iter().map(ToString::to_string).collect(),
+    settings: None,
+  }
+}
+
+#[test]
+fn visits() {
+  let root = temp_root("history", "visits");
+  let mut store = HistoryStore::open(&root).unwrap();
+  assert!(store.is_empty());
+
+  store.visit("https://example.com/live", "Live", 100).unwrap();
+  store.visit("https://example.com/live", "", 200).unwrap();
+  store.visit("https://chat.test/", "Chat", 150).unwrap();
+
+  let entry = store.get("https://example.com/live").unwrap();
+  assert_eq!((entry.visits, entry.visited_at, entry.title.as_str()), (2, 200, "Live"));
+  let urls = store.entries().into_iter().map(|v| v.url).collect::<Vec<_>>();
+  assert_eq!(urls, ["https://example.com/live", "https://chat.test/"]);
+
+  // 読み直しても同じになる
+  let reopened = HistoryStore::open(&root).unwrap();
+  assert_eq!(reopened.entries(), store.entries());
+  assert_eq!(lines(&root), 3);
+}
+
+#[test]
+fn compaction() {
+  let root = temp_root("history", "compaction");
+  let mut store = HistoryStore::open(&root).unwrap();
+  for i in 0..150 {
+    store.visit(&format!("https://example.com/{}", i % 3), "", i).unwrap();
+  }
+  assert!(lines(&root) < 150);
+  assert_eq!(store.len(), 3);
+
+  store.compact().unwrap();
+  assert_eq!(lines(&root), 3);
+  let entry = store.get("https://example.com/0").unwrap();
+  assert_eq!((entry.visits, entry.visited_at), (50, 147));
+  assert_eq!(HistoryStore::open(&root).unwrap().entries(), store.entries());
+
+  // 壊れた行は読み飛ばす
+  fs::write(
+    root.join(HISTORY_FILE),
+    "{\"url\":\"https://a.test/\",\"visited_at\":1,\"visits\":1}\n{\"url\":\"https://b.te",
+  )
+  .unwrap();
+  let store = HistoryStore::open(&root).unwrap();
+  assert_eq!(store.len(), 1);
+  assert_eq!(store.get("https://a.test/").unwrap().title, "");
+
+  let mut store = store;
+  store.clear().unwrap();
+  assert!(HistoryStore::open(&root).unwrap().is_empty());
+}
+
+#[test]
+fn search_history() {
+  let root = temp_root("history", "search");
+  let mut store = HistoryStore::open(&root).unwrap();
+  store.visit("https://www.example.com/chat", "Stream chat", 100).unwrap();
+  store.visit("https://news.test/example", "News", 200).unwrap();
+  store.visit("https://news.test/example", "News", 300).unwrap();
+  store.visit("https://other.test/", "Other", 400).unwrap();
+
+  let search = |query: &str, limit: usize| store.search(query, limit).into_iter().map(|v| v.url).collect::<Vec<_>>();
+  // urlの先頭に一致するものが回数より優先される
+  assert_eq!(search("exam", 10), ["https://www.example.com/chat", "https://news.test/example"]);
+  assert_eq!(search("CHAT stream", 10), ["https://www.example.com/chat"]);
+  assert_eq!(search("", 2), ["https://news.test/example", "https://other.test/"]);
+  assert!(search("missing", 10).is_empty());
+}
+
+#[test]
+fn bookmarks() {
+  let root = temp_root("history", "bookmarks");
+  let mut list = BookmarkList::<()>::load(&root).unwrap();
+  list
+    .add(bookmark("a", "https://example.com/chat", "Chat", &["stream", " Live ", "live", ""]))
+    .unwrap();
+  list.add(bookmark("b", "https://news.test/", "News", &["news"])).unwrap();
+  assert!(list.add(bookmark("a", "https://dup.test/", "", &[])).is_err());
+  assert_eq!(list.get("a").unwrap().tags, ["stream", "Live"]);
+
+  let ids = |found: Vec<&Bookmark<()>>| found.into_iter().map(|v| v.id.clone()).collect::<Vec<_>>();
+  assert_eq!(ids(list.search("", Some("LIVE"))), ["a"]);
+  assert_eq!(ids(list.search("news", None)), ["b"]);
+  assert_eq!(ids(list.search("stream", None)), ["a"]);
+  assert!(list.search("chat", Some("news")).is_empty());
+  assert_eq!(list.tags(), ["Live", "news", "stream"]);
+
+  list.set(bookmark("b", "https://news.test/", "Headlines", &[])).unwrap();
+  assert_eq!(list.get("b").unwrap().title, "Headlines");
+  assert!(list.set(bookmark("c", "https://c.test/", "", &[])).is_err());
+
+  list.save(&root).unwrap();
+  assert!(root.join(BOOKMARKS_FILE).exists());
+  assert_eq!(BookmarkList::<()>::load(&root).unwrap(), list);
+
+  assert_eq!(list.remove("a").unwrap().id, "a");
+  assert!(list.remove("a").is_err());
+}
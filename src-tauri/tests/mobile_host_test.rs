@@ -0,0 +1,79 @@
+use app_lib::view::ctrl::user_agent::mobile_host::{rewrite_url, HostRewrite};
+use url::Url;
+
+fn rewrite(custom: &[HostRewrite], url: &str, mobile: bool) -> Option<String> {
+  rewrite_url(custom, &Url::parse(url).unwrap(), mobile).map(String::from)
+}
+
+#[test]
+fn to_mobile() {
+  assert_eq!(
+    rewrite(&[], "https://www.youtube.com/watch?v=abc#t=1", true).as_deref(),
+    Some("https://m.youtube.com/watch?v=abc#t=1")
+  );
+  assert_eq!(
+    rewrite(&[], "https://twitter.com/home", true).as_deref(),
+    Some("https://mobile.twitter.com/home")
+  );
+  assert_eq!(
+    rewrite(&[], "https://en.wikipedia.org/wiki/Rust", true).as_deref(),
+    Some("https://en.m.wikipedia.org/wiki/Rust")
+  );
+}
+
+#[test]
+fn to_desktop() {
+  assert_eq!(
+    rewrite(&[], "https://m.youtube.com/watch?v=abc", false).as_deref(),
+    Some("https://www.youtube.com/watch?v=abc")
+  );
+  assert_eq!(
+    rewrite(&[], "https://mobile.twitter.com/home", false).as_deref(),
+    Some("https://twitter.com/home")
+  );
+  assert_eq!(
+    rewrite(&[], "https://ja.m.wikipedia.org/wiki/Rust", false).as_deref(),
+    Some("https://ja.wikipedia.org/wiki/Rust")
+  );
+}
+
+#[test]
+fn no_rewrite() {
+  // 既に切り替え先のホスト
+  assert_eq!(rewrite(&[], "https://m.youtube.com/", true), None);
+  assert_eq!(rewrite(&[], "https://en.m.wikipedia.org/", true), None);
+  assert_eq!(rewrite(&[], "https://www.youtube.com/", false), None);
+  // 対応がない
+  assert_eq!(rewrite(&[], "https://example.com/", true), None);
+  assert_eq!(rewrite(&[], "https://music.youtube.com/", true), None);
+  // `*`はサブドメイン1つのみ
+  assert_eq!(rewrite(&[], "https://a.b.wikipedia.org/", true), None);
+}
+
+#[test]
+fn custom_table() {
+  let custom = vec![
+    HostRewrite {
+      desktop: "www.youtube.com".into(),
+      mobile: "youtube.example.com".into(),
+    },
+    HostRewrite {
+      desktop: "*.example.org".into(),
+      mobile: "*.mobile.example.org".into(),
+    },
+  ];
+
+  // 組み込みより優先される
+  assert_eq!(
+    rewrite(&custom, "https://www.youtube.com/", true).as_deref(),
+    Some("https://youtube.example.com/")
+  );
+  assert_eq!(
+    rewrite(&custom, "https://News.Example.org/a", true).as_deref(),
+    Some("https://news.mobile.example.org/a")
+  );
+  assert_eq!(
+    rewrite(&custom, "https://news.mobile.example.org/a", false).as_deref(),
+    Some("https://news.example.org/a")
+  );
+}
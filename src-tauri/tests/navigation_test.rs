@@ -0,0 +1,117 @@
+use app_lib::view::ctrl::navigation::policy::{
+  matches_pattern, same_site, site_of, wildcard, LinkAction, NavigationMode, NavigationPolicy,
+};
+use url::Url;
+
+fn url(v: &str) -> Url {
+  Url::parse(v).unwrap()
+}
+
+#[test]
+fn wildcard_matching() {
+  assert!(wildcard("abc", "abc"));
+  assert!(!wildcard("abc", "abcd"));
+  assert!(wildcard("a*", "abcd"));
+  assert!(wildcard("*d", "abcd"));
+  assert!(wildcard("a*c*e", "abcde"));
+  assert!(!wildcard("a*c*e", "abcdf"));
+  assert!(wildcard("https://example.com/*", "https://example.com/a/b"));
+  assert!(!wildcard("https://example.com/*", "https://example.org/a"));
+}
+
+#[test]
+fn site_of_host() {
+  assert_eq!(site_of("www.youtube.com"), "youtube.com");
+  assert_eq!(site_of("youtube.com"), "youtube.com");
+  assert_eq!(site_of("a.b.example.co.jp"), "example.co.jp");
+  assert_eq!(site_of("example.com.au"), "example.com.au");
+  assert_eq!(site_of("localhost"), "localhost");
+}
+
+#[test]
+fn same_site_urls() {
+  assert!(same_site(&url("https://www.youtube.com/"), &url("https://m.youtube.com/watch")));
+  assert!(!same_site(&url("https://www.youtube.com/"), &url("https://youtube.example.com/")));
+  assert!(same_site(&url("http://127.0.0.1:8080/"), &url("http://127.0.0.1/")));
+  assert!(!same_site(&url("http://127.0.0.1/"), &url("http://127.0.0.2/")));
+}
+
+#[test]
+fn pattern_kinds() {
+  let target = url("https://chat.example.com/room");
+
+  assert!(matches_pattern("example.com", &target));
+  assert!(matches_pattern("Chat.Example.com", &target));
+  assert!(matches_pattern("*.example.com", &target));
+  assert!(!matches_pattern("*.example.com", &url("https://example.com/")));
+  assert!(!matches_pattern("ample.com", &target));
+  assert!(matches_pattern("chat.*.com", &target));
+  assert!(matches_pattern("https://chat.example.com/*", &target));
+  assert!(!matches_pattern("https://chat.example.com/other*", &target));
+  assert!(!matches_pattern("", &target));
+}
+
+#[test]
+fn allow_all_by_default() {
+  let policy = NavigationPolicy::default();
+  let origin = url("https://example.com/");
+
+  assert_eq!(policy.navigation(&origin, &url("https://other.org/")), LinkAction::Current);
+  assert_eq!(policy.new_window(&origin, &url("https://other.org/")), LinkAction::Browser);
+}
+
+#[test]
+fn same_domain_mode() {
+  let policy = NavigationPolicy {
+    mode: NavigationMode::SameDomain,
+    blocked: LinkAction::Overlay,
+    popup: LinkAction::Current,
+  };
+  let origin = url("https://www.example.com/");
+
+  assert_eq!(policy.navigation(&origin, &url("https://login.example.com/")), LinkAction::Current);
+  assert_eq!(policy.navigation(&origin, &url("https://other.org/")), LinkAction::Overlay);
+  assert_eq!(policy.new_window(&origin, &url("https://example.com/popup")), LinkAction::Current);
+  assert_eq!(policy.new_window(&origin, &url("https://other.org/")), LinkAction::Overlay);
+  // ページ内のスキーム
+  assert_eq!(policy.navigation(&origin, &url("about:blank")), LinkAction::Current);
+}
+
+#[test]
+fn allowlist_and_blocklist() {
+  let origin = url("https://example.com/");
+  let allow = NavigationPolicy {
+    mode: NavigationMode::Allowlist(vec!["*.auth.io".into()]),
+    blocked: LinkAction::Current,
+    popup: LinkAction::Browser,
+  };
+
+  assert!(allow.allows(&origin, &url("https://example.com/next")));
+  assert!(allow.allows(&origin, &url("https://sso.auth.io/")));
+  assert!(!allow.allows(&origin, &url("https://tracker.net/")));
+  // blockedにCurrentを指定してもDropになる
+  assert_eq!(allow.navigation(&origin, &url("https://tracker.net/")), LinkAction::Drop);
+
+  let block = NavigationPolicy {
+    mode: NavigationMode::Blocklist(vec!["ads.example.net".into(), "https://example.com/logout*".into()]),
+    blocked: LinkAction::Drop,
+    popup: LinkAction::Overlay,
+  };
+
+  assert!(block.allows(&origin, &url("https://news.org/")));
+  assert!(!block.allows(&origin, &url("https://x.ads.example.net/")));
+  assert!(!block.allows(&origin, &url("https://example.com/logout?next=/")));
+  assert_eq!(block.new_window(&origin, &url("https://news.org/")), LinkAction::Overlay);
+}
+
+#[test]
+fn policy_serde_roundtrip() {
+  let policy = NavigationPolicy {
+    mode: NavigationMode::Allowlist(vec!["example.com".into()]),
+    blocked: LinkAction::Drop,
+    popup: LinkAction::Overlay,
+  };
+  let text = toml::to_string(&policy).unwrap();
+
+  assert_eq!(toml::from_str::<NavigationPolicy>(&text).unwrap(), policy);
+}
@@ -0,0 +1,48 @@
+use app_lib::util::{find_url, normalize_url};
+
+#[test]
+fn normalize() {
+  assert_eq!(normalize_url("example.com").unwrap().as_str(), "https://example.com/");
+  assert_eq!(
+    normalize_url("  http://localhost:8080/a ").unwrap().as_str(),
+    "http://localhost:8080/a"
+  );
+  assert_eq!(
+    normalize_url("example.com/?next=https://other.test").unwrap().host_str(),
+    Some("example.com")
+  );
+
+  for url in ["file:///C:/a.html", "javascript:alert(1)", "ftp://example.com", "", "https://"] {
+    assert!(normalize_url(url).is_err(), "{}", url);
+  }
+}
+
+#[test]
+fn find() {
+  let find = |text: &str| find_url(text).map(|v| v.to_string());
+
+  assert_eq!(
+    find("see https://example.com/live, thanks!").as_deref(),
+    Some("https://example.com/live")
+  );
+  assert_eq!(
+    find("配信はこちら「https://example.com/live」。").as_deref(),
+    Some("https://example.com/live")
+  );
+  assert_eq!(find("(www.example.com/a)").as_deref(), Some("https://www.example.com/a"));
+  assert_eq!(
+    find("https://en.wikipedia.org/wiki/Rust_(language).").as_deref(),
+    Some("https://en.wikipedia.org/wiki/Rust_(language)")
+  );
+  assert_eq!(
+    find("<a href=\"http://example.com/?a=1&b=2\">").as_deref(),
+    Some("http://example.com/?a=1&b=2")
+  );
+  assert_eq!(find("first http://a.test second https://b.test").as_deref(), Some("http://a.test/"));
+  assert_eq!(find("  example.com/chat\n").as_deref(), Some("https://example.com/chat"));
+
+  assert_eq!(find(""), None);
+  assert_eq!(find("hello world"), None);
+  assert_eq!(find("version 1.2 released"), None);
+  assert_eq!(find("file:///C:/a.html"), None);
+}
@@ -0,0 +1,57 @@
+use app_lib::view::obs::scene::{parse_scene_collection, BrowserSource, DEFAULT_CSS, DEFAULT_HEIGHT, DEFAULT_WIDTH};
+
+const STREAMING: &str = include_str!("data/obs/streaming.json");
+const EMPTY: &str = include_str!("data/obs/empty.json");
+
+#[test]
+fn browser_sources() {
+  let collection = parse_scene_collection(STREAMING).unwrap();
+  assert_eq!(collection.name, "Streaming");
+  // カメラなどブラウザソース以外は含まない
+  let names = collection.sources.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+  assert_eq!(names, ["Chat", "Alerts", "Countdown"]);
+
+  assert_eq!(
+    collection.sources[0],
+    BrowserSource {
+      name: "Chat".into(),
+      scenes: vec!["Gameplay".into()],
+      url: "https://chat.example.com/popout?channel=relais".into(),
+      width: 400,
+      height: 900,
+      css: "body { background-color: rgba(0, 0, 0, 0); }\n.message { font-size: 20px; }".into(),
+      unsupported: None,
+    }
+  );
+}
+
+#[test]
+fn defaults() {
+  let collection = parse_scene_collection(STREAMING).unwrap();
+  // 保存されていない設定はOBSの既定値にする
+  let alerts = &collection.sources[1];
+  assert_eq!(alerts.scenes, ["Gameplay", "Starting Soon"]);
+  assert_eq!((alerts.width, alerts.height), (DEFAULT_WIDTH, DEFAULT_HEIGHT));
+  assert_eq!(alerts.css, DEFAULT_CSS);
+  assert_eq!(alerts.unsupported, None);
+
+  let countdown = &collection.sources[2];
+  assert_eq!((countdown.width, countdown.height), (1920, 1080));
+  assert_eq!(countdown.url, "C:/Users/streamer/overlays/countdown.html");
+  assert!(countdown.unsupported.is_some());
+}
+
+#[test]
+fn invalid_collections() {
+  assert!(parse_scene_collection(EMPTY).unwrap().sources.is_empty());
+
+  let collection =
+    parse_scene_collection(r#"{"sources": [{"id": "browser_source", "name": "Script", "settings": {"url": "javascript:alert(1)"}}]}"#)
+      .unwrap();
+  assert_eq!(collection.name, "");
+  assert!(collection.sources[0].unsupported.is_some());
+
+  for text in ["", "[]", "{}", r#"{"sources": {}}"#, "{\"sources\": ["] {
+    assert!(parse_scene_collection(text).is_err(), "{}", text);
+  }
+}
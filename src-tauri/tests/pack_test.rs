@@ -0,0 +1,146 @@
+use app_lib::view::pack::format::{
+  parse_pack, ConflictResolution, ImportStep, Pack, PackOverlay, PackSelection, DEFAULT_ZOOM, PACK_VERSION,
+};
+
+const PACK: &str = r#"
+version = 1
+name = "Stream"
+
+[[overlays]]
+url = "https://chat.example.com/popout"
+title = "Chat"
+position = [1500, 100]
+size = [400, 900]
+opacity = 200
+pin = true
+click_through = true
+agent_preset = "android_chrome"
+zoom = 120
+css = "body { font-size: 20px; }"
+
+[[overlays]]
+url = "alerts.example.com/widget"
+position = [0, 0]
+size = [800, 600]
+script = "document.body.dataset.relais = '1';"
+"#;
+
+fn open(urls: &[(&str, &str)]) -> Vec<(String, String)> {
+  urls.iter().map(|(label, url)| (label.to_string(), url.to_string())).collect()
+}
+
+fn select(index: u32, resolution: ConflictResolution) -> PackSelection {
+  PackSelection { index, resolution }
+}
+
+#[test]
+fn parse() {
+  let pack = parse_pack(PACK).unwrap();
+  assert_eq!(pack.name, "Stream");
+  assert_eq!(
+    pack.overlays[0],
+    PackOverlay {
+      url: "https://chat.example.com/popout".into(),
+      title: "Chat".into(),
+      position: (1500, 100),
+      size: (400, 900),
+      opacity: Some(200),
+      pin: true,
+      click_through: true,
+      agent_preset: Some("android_chrome".into()),
+      zoom: 120,
+      css: "body { font-size: 20px; }".into(),
+      script: String::new(),
+    }
+  );
+
+  // 省略した設定は既定値、urlは正規化する
+  let alerts = &pack.overlays[1];
+  assert_eq!(alerts.url, "https://alerts.example.com/widget");
+  assert_eq!((alerts.opacity, alerts.pin, alerts.click_through), (None, false, false));
+  assert_eq!((alerts.agent_preset.as_deref(), alerts.zoom), (None, DEFAULT_ZOOM));
+  assert!(alerts.has_script());
+  assert!(pack.has_scripts());
+}
+
+#[test]
+fn round_trip() {
+  let pack = parse_pack(PACK).unwrap();
+  let exported = Pack::new(pack.name.clone(), pack.overlays.clone());
+  assert_eq!(exported.version, PACK_VERSION);
+  assert_eq!(parse_pack(&exported.to_toml().unwrap()).unwrap(), pack);
+
+  let empty = Pack::new(String::new(), Vec::new());
+  let parsed = parse_pack(&empty.to_toml().unwrap()).unwrap();
+  assert!(parsed.overlays.is_empty());
+  assert!(!parsed.has_scripts());
+}
+
+#[test]
+fn invalid_packs() {
+  // 新しい版は中身が読めても断る
+  let newer = PACK.replacen("version = 1", &format!("version = {}", PACK_VERSION + 1), 1);
+  assert!(parse_pack(&newer).unwrap_err().to_string().contains("not supported"));
+  assert!(parse_pack(&PACK.replacen("version = 1", "version = 0", 1)).is_err());
+  assert!(parse_pack(&PACK.replacen("version = 1\n", "", 1)).is_err());
+
+  assert!(parse_pack(&PACK.replace("alerts.example.com/widget", "javascript:alert(1)")).is_err());
+  assert!(parse_pack(&PACK.replace("size = [800, 600]", "size = [0, 600]")).is_err());
+  assert!(parse_pack(&PACK.replace("position = [0, 0]\n", "")).is_err());
+  assert!(parse_pack("version = \"1\"").is_err());
+
+  // 範囲外の倍率は丸める
+  let pack = parse_pack(&PACK.replace("zoom = 120", "zoom = 5000")).unwrap();
+  assert_eq!(pack.overlays[0].zoom, 500);
+}
+
+#[test]
+fn preview_conflicts() {
+  let pack = parse_pack(PACK).unwrap();
+  let open = open(&[
+    ("overlay-1", "https://example.com/"),
+    ("overlay-2", "https://alerts.example.com/widget"),
+  ]);
+
+  let preview = pack.preview(&open);
+  assert_eq!(preview.len(), 2);
+  assert_eq!(preview[0].conflict, None);
+  assert_eq!(preview[1].conflict.as_deref(), Some("overlay-2"));
+  assert_eq!(preview[1].overlay, pack.overlays[1]);
+}
+
+#[test]
+fn plan() {
+  let pack = parse_pack(PACK).unwrap();
+  let open = open(&[("overlay-2", "https://alerts.example.com/widget")]);
+  let step = |index: usize, replace: Option<&str>| ImportStep {
+    overlay: pack.overlays[index].clone(),
+    replace: replace.map(Into::into),
+  };
+
+  // 重複していなければresolutionに関係なく開く、選んだ順に並べる
+  let steps = pack
+    .plan(&[select(1, ConflictResolution::Skip), select(0, ConflictResolution::Skip)], &open)
+    .unwrap();
+  assert_eq!(steps, [step(0, None)]);
+
+  let steps = pack
+    .plan(
+      &[select(1, ConflictResolution::Replace), select(0, ConflictResolution::Replace)],
+      &open,
+    )
+    .unwrap();
+  assert_eq!(steps, [step(1, Some("overlay-2")), step(0, None)]);
+
+  let steps = pack.plan(&[select(1, ConflictResolution::Duplicate)], &open).unwrap();
+  assert_eq!(steps, [step(1, None)]);
+
+  assert!(pack.plan(&[], &open).unwrap().is_empty());
+  assert!(pack.plan(&[select(2, ConflictResolution::Duplicate)], &open).is_err());
+  assert!(pack
+    .plan(
+      &[select(0, ConflictResolution::Duplicate), select(0, ConflictResolution::Skip)],
+      &open
+    )
+    .is_err());
+}
@@ -0,0 +1,76 @@
+use app_lib::view::permission::table::{normalize_origin, PermissionKind, PermissionState, PermissionTable};
+
+#[test]
+fn normalize() {
+  assert_eq!(normalize_origin("https://Meet.Example.com/room/1?x=y"), "https://meet.example.com");
+  assert_eq!(normalize_origin("http://localhost:8080/"), "http://localhost:8080");
+  assert_eq!(normalize_origin("https://example.com:443/"), "https://example.com");
+  assert_eq!(normalize_origin(" Example.com/ "), "example.com");
+}
+
+#[test]
+fn ask_by_default() {
+  let table = PermissionTable::default();
+
+  assert_eq!(table.get("https://example.com", PermissionKind::Camera), PermissionState::Ask);
+}
+
+#[test]
+fn set_and_override() {
+  let mut table = PermissionTable::default();
+  table.set("https://meet.example.com/room", PermissionKind::Camera, PermissionState::Allow);
+  table.set("https://meet.example.com", PermissionKind::Microphone, PermissionState::Deny);
+
+  assert_eq!(
+    table.get("https://meet.example.com/other", PermissionKind::Camera),
+    PermissionState::Allow
+  );
+  assert_eq!(
+    table.get("https://meet.example.com", PermissionKind::Microphone),
+    PermissionState::Deny
+  );
+  assert_eq!(table.get("https://other.example.com", PermissionKind::Camera), PermissionState::Ask);
+
+  table.set("https://meet.example.com", PermissionKind::Camera, PermissionState::Deny);
+  assert_eq!(table.get("https://meet.example.com", PermissionKind::Camera), PermissionState::Deny);
+  assert_eq!(table.list().len(), 2);
+
+  // Askに戻すとエントリが消える
+  table.set("https://meet.example.com", PermissionKind::Camera, PermissionState::Ask);
+  assert_eq!(table.list().len(), 1);
+}
+
+#[test]
+fn reset() {
+  let mut table = PermissionTable::default();
+  table.set("https://a.example.com", PermissionKind::Notifications, PermissionState::Allow);
+  table.set("https://a.example.com", PermissionKind::Clipboard, PermissionState::Allow);
+  table.set("https://b.example.com", PermissionKind::Geolocation, PermissionState::Deny);
+
+  table.reset(Some("https://a.example.com/"));
+  assert_eq!(table.list().len(), 1);
+  assert_eq!(
+    table.get("https://b.example.com", PermissionKind::Geolocation),
+    PermissionState::Deny
+  );
+
+  table.reset(None);
+  assert!(table.list().is_empty());
+}
+
+#[test]
+fn toml_roundtrip() {
+  #[derive(serde::Serialize, serde::Deserialize)]
+  struct Wrapper {
+    permissions: PermissionTable,
+  }
+
+  let mut table = PermissionTable::default();
+  table.set("https://example.com", PermissionKind::Camera, PermissionState::Allow);
+  let text = toml::to_string(&Wrapper {
+    permissions: table.clone(),
+  })
+  .unwrap();
+
+  assert_eq!(toml::from_str::<Wrapper>(&text).unwrap().permissions, table);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::fs;
+
+use app_lib::util::{clear_profile, create_profile, delete_profile, profile_dir, validate_profile_name};
+use common::temp_root;
+
+#[test]
+fn names() {
+  assert!(validate_profile_name("work").is_ok());
+  assert!(validate_profile_name("slack_personal-2").is_ok());
+  assert!(validate_profile_name("").is_err());
+  assert!(validate_profile_name("../escape").is_err());
+  assert!(validate_profile_name("a b").is_err());
+  assert!(validate_profile_name("Default").is_err());
+  assert!(validate_profile_name(&"a".repeat(65)).is_err());
+}
+
+#[test]
+fn create_and_delete() {
+  let root = temp_root("profile", "create");
+  let mut profiles = Vec::new();
+
+  let dir = create_profile(&root, &mut profiles, "work").unwrap();
+  assert_eq!(dir, profile_dir(&root, "work"));
+  assert!(dir.is_dir());
+  assert_eq!(profiles, vec!["work".to_string()]);
+  assert!(create_profile(&root, &mut profiles, "work").is_err());
+
+  delete_profile(&root, &mut profiles, "work").unwrap();
+  assert!(!dir.exists());
+  assert!(profiles.is_empty());
+  assert!(delete_profile(&root, &mut profiles, "work").is_err());
+
+  fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn clear_keeps_profile() {
+  let root = temp_root("profile", "clear");
+  let mut profiles = Vec::new();
+
+  let dir = create_profile(&root, &mut profiles, "kiosk").unwrap();
+  fs::write(dir.join("Cookies"), "data").unwrap();
+  clear_profile(&root, &profiles, "kiosk").unwrap();
+
+  assert!(dir.is_dir());
+  assert!(!dir.join("Cookies").exists());
+  assert!(clear_profile(&root, &profiles, "missing").is_err());
+
+  fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn names_ignore_case() {
+  let root = temp_root("profile", "case");
+  let mut profiles = Vec::new();
+
+  let dir = create_profile(&root, &mut profiles, "Work").unwrap();
+  assert!(create_profile(&root, &mut profiles, "work").is_err());
+  assert!(create_profile(&root, &mut profiles, "WORK").is_err());
+  assert_eq!(profiles, vec!["Work".to_string()]);
+
+  fs::write(dir.join("Cookies"), "data").unwrap();
+  clear_profile(&root, &profiles, "work").unwrap();
+  assert!(dir.is_dir());
+  assert!(!dir.join("Cookies").exists());
+
+  delete_profile(&root, &mut profiles, "work").unwrap();
+  assert!(!dir.exists());
+  assert!(profiles.is_empty());
+
+  fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,207 @@
+use std::{
+  io::{BufRead, BufReader, Read, Write},
+  net::TcpStream,
+  time::Duration,
+};
+
+use app_lib::remote::{
+  route::{Route, Zoom},
+  server::{ApiError, ApiRequest, RemoteServer, EVENTS_PATH},
+};
+use serde_json::{json, Value};
+
+const TOKEN: &str = "secret-token";
+
+/// ルートをそのまま返すサーバー
+fn start() -> RemoteServer {
+  RemoteServer::start(0, TOKEN, |request| {
+    let route = Route::parse(request)?;
+    match route {
+      Route::List => Ok(json!([])),
+      Route::Close(label) if label == "missing" => Err(ApiError::not_found("overlay missing is not found")),
+      route => Ok(json!(format!("{:?}", route))),
+    }
+  })
+  .unwrap()
+}
+
+/// (ステータス, 本文)
+fn send(server: &RemoteServer, head: &str, body: &str) -> (u16, Value) {
+  let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+  write!(stream, "{}\r\nContent-Length: {}\r\n\r\n{}", head, body.len(), body).unwrap();
+  let mut response = String::new();
+  stream.read_to_string(&mut response).unwrap();
+
+  let (head, body) = response.split_once("\r\n\r\n").unwrap();
+  let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+  (status, serde_json::from_str(body).unwrap())
+}
+
+fn request(method: &str, path: &str, body: Value) -> ApiRequest {
+  ApiRequest {
+    method: method.to_string(),
+    path: path.to_string(),
+    body,
+  }
+}
+
+#[test]
+fn routes() {
+  assert_eq!(Route::parse(&request("GET", "/overlays", Value::Null)), Ok(Route::List));
+  assert_eq!(
+    Route::parse(&request("POST", "/overlays", json!({ "url": "example.com" }))),
+    Ok(Route::Create {
+      url: "https://example.com".parse().unwrap(),
+      profile: None
+    })
+  );
+  assert_eq!(
+    Route::parse(&request("DELETE", "/overlays/window_1", Value::Null)),
+    Ok(Route::Close("window_1".into()))
+  );
+  assert_eq!(
+    Route::parse(&request("POST", "/overlays/window_1/pin", Value::Null)),
+    Ok(Route::Pin("window_1".into(), None))
+  );
+  assert_eq!(
+    Route::parse(&request("POST", "/overlays/window_1/click-through", json!({ "value": true }))),
+    Ok(Route::ClickThrough("window_1".into(), Some(true)))
+  );
+  assert_eq!(
+    Route::parse(&request("POST", "/overlays/window_1/opacity", json!({ "value": 128 }))),
+    Ok(Route::Opacity("window_1".into(), 128))
+  );
+  assert_eq!(
+    Route::parse(&request("POST", "/overlays/window_1/zoom", json!({ "diff": -10 }))),
+    Ok(Route::Zoom("window_1".into(), Zoom::Diff(-10)))
+  );
+  assert_eq!(
+    Route::parse(&request("POST", "/overlays/window_1/navigate", json!({ "url": "http://a.test/x" }))),
+    Ok(Route::Navigate("window_1".into(), "http://a.test/x".parse().unwrap()))
+  );
+  assert_eq!(
+    Route::parse(&request("POST", "/overlays/hide", Value::Null)),
+    Ok(Route::SetVisible(false))
+  );
+}
+
+#[test]
+fn invalid_routes() {
+  let status = |method: &str, path: &str, body: Value| Route::parse(&request(method, path, body)).unwrap_err().status;
+
+  assert_eq!(status("GET", "/unknown", Value::Null), 404);
+  assert_eq!(status("POST", "/overlays/window_1/unknown", Value::Null), 404);
+  assert_eq!(status("PUT", "/overlays", Value::Null), 405);
+  assert_eq!(status("POST", "/overlays", Value::Null), 400);
+  assert_eq!(status("POST", "/overlays", json!({ "url": "file:///C:/a" })), 400);
+  assert_eq!(status("POST", "/overlays/window_1/opacity", json!({ "value": 300 })), 400);
+  assert_eq!(status("POST", "/overlays/window_1/zoom", json!({ "value": 1000 })), 400);
+  assert_eq!(status("POST", "/overlays/window_1/zoom", json!({})), 400);
+}
+
+#[test]
+fn auth() {
+  let server = start();
+
+  let (status, body) = send(&server, "GET /overlays HTTP/1.1\r\nHost: 127.0.0.1", "");
+  assert_eq!(status, 401);
+  assert_eq!(body, json!({ "error": "invalid token" }));
+
+  let (status, _) = send(
+    &server,
+    "GET /overlays HTTP/1.1\r\nHost: 127.0.0.1\r\nAuthorization: Bearer wrong",
+    "",
+  );
+  assert_eq!(status, 401);
+
+  let head = format!("GET /overlays HTTP/1.1\r\nHost: 127.0.0.1\r\nAuthorization: Bearer {}", TOKEN);
+  assert_eq!(send(&server, &head, ""), (200, json!([])));
+
+  let head = format!("GET /overlays?token={} HTTP/1.1\r\nHost: localhost:38120", TOKEN);
+  assert_eq!(send(&server, &head, ""), (200, json!([])));
+
+  // DNS rebinding
+  let head = format!(
+    "GET /overlays HTTP/1.1\r\nHost: evil.example:38120\r\nAuthorization: Bearer {}",
+    TOKEN
+  );
+  assert_eq!(send(&server, &head, "").0, 403);
+}
+
+#[test]
+fn handler() {
+  let server = start();
+  let head = |method: &str, path: &str| {
+    format!(
+      "{} {} HTTP/1.1\r\nHost: 127.0.0.1\r\nAuthorization: Bearer {}\r\nContent-Type: application/json",
+      method, path, TOKEN
+    )
+  };
+
+  let (status, body) = send(&server, &head("POST", "/overlays/window_1/opacity"), r#"{"value":64}"#);
+  assert_eq!(status, 200);
+  assert_eq!(body, json!(r#"Opacity("window_1", 64)"#));
+
+  let (status, body) = send(&server, &head("DELETE", "/overlays/missing"), "");
+  assert_eq!(status, 404);
+  assert_eq!(body, json!({ "error": "overlay missing is not found" }));
+
+  let (status, _) = send(&server, &head("POST", "/overlays"), "{not json");
+  assert_eq!(status, 400);
+}
+
+#[test]
+fn events() {
+  let server = start();
+  let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+  stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+  write!(
+    stream,
+    "GET {} HTTP/1.1\r\nHost: 127.0.0.1\r\nAuthorization: Bearer {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+     Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+    EVENTS_PATH, TOKEN
+  )
+  .unwrap();
+
+  let mut reader = BufReader::new(stream.try_clone().unwrap());
+  let mut head = Vec::new();
+  loop {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    if line == "\r\n" {
+      break;
+    }
+    head.push(line.trim_end().to_string());
+  }
+  assert_eq!(head[0], "HTTP/1.1 101 Switching Protocols");
+  // RFC 6455の例
+  assert!(head.contains(&"Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_string()));
+
+  server.broadcast("UpdateState", &vec![json!({ "label": "window_1" })]);
+
+  let mut frame = [0; 2];
+  reader.read_exact(&mut frame).unwrap();
+  assert_eq!(frame[0], 0x81);
+  let mut payload = vec![0; frame[1] as usize];
+  reader.read_exact(&mut payload).unwrap();
+  let message = serde_json::from_slice::<Value>(&payload).unwrap();
+  assert_eq!(message, json!({ "event": "UpdateState", "payload": [{ "label": "window_1" }] }));
+
+  // マスクしたcloseを送るとcloseが返る
+  stream.write_all(&[0x88, 0x80, 1, 2, 3, 4]).unwrap();
+  reader.read_exact(&mut frame).unwrap();
+  assert_eq!(frame[0], 0x88);
+}
+
+#[test]
+fn stop() {
+  let server = start();
+  let addr = server.local_addr();
+  drop(server);
+  assert!(TcpStream::connect(addr).is_err());
+
+  // 止めたらすぐに同じポートで起動し直せる
+  let server = RemoteServer::start(addr.port(), TOKEN, |_| Ok(Value::Null)).unwrap();
+  server.stop();
+  assert!(RemoteServer::start(addr.port(), TOKEN, |_| Ok(Value::Null)).is_ok());
+}
@@ -0,0 +1,140 @@
+use app_lib::view::ctrl::request_rule::rules::{resolve, HeaderAction, RequestRule};
+use url::Url;
+
+fn url(v: &str) -> Url {
+  Url::parse(v).unwrap()
+}
+
+fn rule(domain: &str, headers: Vec<HeaderAction>) -> RequestRule {
+  RequestRule {
+    domain: domain.into(),
+    user_agent: None,
+    headers,
+  }
+}
+
+fn set(name: &str, value: &str) -> HeaderAction {
+  HeaderAction::Set {
+    name: name.into(),
+    value: value.into(),
+  }
+}
+
+#[test]
+fn matching() {
+  let target = url("https://www.example.com/watch?v=1");
+
+  assert!(rule("*", vec![]).matches(&target));
+  assert!(rule("example.com", vec![]).matches(&target));
+  assert!(rule("*.example.com", vec![]).matches(&target));
+  assert!(rule("www.example.com", vec![]).matches(&target));
+  assert!(rule("https://www.example.com/watch*", vec![]).matches(&target));
+  assert!(!rule("api.example.com", vec![]).matches(&target));
+  assert!(!rule("example.org", vec![]).matches(&target));
+  assert!(!rule("", vec![]).matches(&target));
+}
+
+#[test]
+fn specificity() {
+  let target = url("https://www.example.com/");
+  let order =
+    ["*", "*.example.com", "example.com", "www.example.com", "https://www.example.com/*"].map(|v| rule(v, vec![]).specificity(&target));
+
+  assert!(order.windows(2).all(|v| v[0] < v[1]));
+  // 同じ段階なら長い方
+  assert!(
+    rule("example.com", vec![]).specificity(&target) < rule("www.example.com", vec![]).specificity(&url("https://a.www.example.com/"))
+  );
+}
+
+#[test]
+fn more_specific_wins() {
+  let global = vec![
+    rule("example.com", vec![set("Accept-Language", "en-US")]),
+    rule("*", vec![set("Accept-Language", "ja-JP"), set("X-Global", "1")]),
+  ];
+
+  assert_eq!(
+    resolve(&global, &[], &url("https://example.com/")),
+    vec![set("Accept-Language", "en-US"), set("X-Global", "1")]
+  );
+  assert_eq!(
+    resolve(&global, &[], &url("https://example.org/")),
+    vec![set("Accept-Language", "ja-JP"), set("X-Global", "1")]
+  );
+}
+
+#[test]
+fn overlay_wins_over_global() {
+  let global = vec![rule("www.example.com", vec![set("Referer", "https://global.test/")])];
+  let overlay = vec![rule("*", vec![HeaderAction::Remove { name: "referer".into() }])];
+
+  assert_eq!(
+    resolve(&global, &overlay, &url("https://www.example.com/")),
+    vec![HeaderAction::Remove { name: "referer".into() }]
+  );
+}
+
+#[test]
+fn later_rule_wins_on_tie() {
+  let overlay = vec![
+    rule("example.com", vec![set("Authorization", "Bearer a")]),
+    rule("example.com", vec![set("Authorization", "Bearer b")]),
+  ];
+
+  assert_eq!(
+    resolve(&[], &overlay, &url("https://example.com/")),
+    vec![set("Authorization", "Bearer b")]
+  );
+}
+
+#[test]
+fn user_agent() {
+  let mut mobile = rule("m.example.com", vec![set("X-Device", "phone")]);
+  mobile.user_agent = Some("Mobile UA".into());
+  let mut desktop = rule("*", vec![]);
+  desktop.user_agent = Some("Desktop UA".into());
+  let rules = vec![mobile, desktop];
+
+  assert_eq!(
+    resolve(&rules, &[], &url("https://m.example.com/")),
+    vec![set("User-Agent", "Mobile UA"), set("X-Device", "phone")]
+  );
+  assert_eq!(
+    resolve(&rules, &[], &url("https://example.com/")),
+    vec![set("User-Agent", "Desktop UA")]
+  );
+  // ルール内ではheadersの方が後に適用される
+  let mut both = rule("*", vec![set("user-agent", "Header UA")]);
+  both.user_agent = Some("Field UA".into());
+  assert_eq!(
+    resolve(&[both], &[], &url("https://example.com/")),
+    vec![set("user-agent", "Header UA")]
+  );
+}
+
+#[test]
+fn toml_roundtrip() {
+  #[derive(serde::Serialize, serde::Deserialize)]
+  struct Wrapper {
+    request_rules: Vec<RequestRule>,
+  }
+
+  let text = r#"
+    [[request_rules]]
+    domain = "example.com"
+    user_agent = "Custom UA"
+    headers = [
+      { action = "Add", name = "X-Token", value = "abc" },
+      { action = "Remove", name = "Referer" },
+    ]
+  "#;
+  let rules = toml::from_str::<Wrapper>(text).unwrap().request_rules;
+  assert_eq!(rules[0].headers.len(), 2);
+
+  let text = toml::to_string(&Wrapper {
+    request_rules: rules.clone(),
+  })
+  .unwrap();
+  assert_eq!(toml::from_str::<Wrapper>(&text).unwrap().request_rules, rules);
+}
@@ -0,0 +1,110 @@
+use std::{
+  sync::mpsc::{self, Receiver, RecvTimeoutError},
+  time::Duration,
+};
+
+use app_lib::util::{backoff, Scheduler, TaskKind};
+
+const LABEL: &str = "window_test";
+/// 負荷の高いCIでも届くだけの余裕を持たせる
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// テストの間に発火しない長さ
+const NEVER: Duration = Duration::from_secs(3600);
+
+/// 呼ばれるたびに送る
+/// タスクのスレッドが終わるとfと一緒にsenderがdropされ、受け取る側はDisconnectedになる
+fn channel() -> (Receiver<()>, impl FnMut() + Send + 'static) {
+  let (tx, rx) = mpsc::channel();
+  (rx, move || {
+    let _ = tx.send(());
+  })
+}
+
+/// スレッドが終わるまで受け取り、その数を返す
+fn drain(rx: &Receiver<()>) -> usize {
+  let mut count = 0;
+  loop {
+    match rx.recv_timeout(TIMEOUT) {
+      Ok(()) => count += 1,
+      Err(RecvTimeoutError::Disconnected) => return count,
+      Err(RecvTimeoutError::Timeout) => panic!("the task thread did not stop"),
+    }
+  }
+}
+
+#[test]
+fn repeat_until_cancel() {
+  let scheduler = Scheduler::new();
+  let (rx, f) = channel();
+
+  scheduler.repeat(LABEL, TaskKind::Refresh, Duration::from_millis(10), f);
+  for _ in 0..3 {
+    rx.recv_timeout(TIMEOUT).unwrap();
+  }
+  scheduler.cancel(LABEL, TaskKind::Refresh);
+
+  // キャンセルしたときに呼んでいる途中だった1回までは許す
+  assert!(drain(&rx) <= 1);
+  assert!(scheduler.status(LABEL).is_empty());
+}
+
+#[test]
+fn once_fires_once() {
+  let scheduler = Scheduler::new();
+  let (fired_tx, fired) = mpsc::channel();
+  let (release, gate) = mpsc::channel::<()>();
+
+  // fが返るまでは次の予定が残る
+  scheduler.once(LABEL, TaskKind::Watchdog, Duration::from_millis(10), move || {
+    fired_tx.send(()).unwrap();
+    gate.recv().unwrap();
+  });
+  assert!(scheduler.status(LABEL)[0].next_in_ms.is_some());
+  fired.recv_timeout(TIMEOUT).unwrap();
+  release.send(()).unwrap();
+
+  assert_eq!(drain(&fired), 0);
+  assert_eq!(scheduler.status(LABEL)[0].next_in_ms, None);
+}
+
+#[test]
+fn replace_cancels_previous() {
+  let scheduler = Scheduler::new();
+  let (first, f) = channel();
+  let (second, g) = channel();
+
+  scheduler.once(LABEL, TaskKind::Watchdog, NEVER, f);
+  scheduler.once(LABEL, TaskKind::Watchdog, Duration::from_millis(10), g);
+
+  assert_eq!(drain(&first), 0);
+  assert_eq!(drain(&second), 1);
+}
+
+#[test]
+fn cancel_all_only_touches_label() {
+  let scheduler = Scheduler::new();
+  let (count, f) = channel();
+  let (other, g) = channel();
+
+  scheduler.repeat(LABEL, TaskKind::Refresh, NEVER, f);
+  scheduler.repeat("window_other", TaskKind::Refresh, Duration::from_millis(10), g);
+  scheduler.cancel_all(LABEL);
+
+  assert_eq!(drain(&count), 0);
+  other.recv_timeout(TIMEOUT).unwrap();
+  assert_eq!(scheduler.status("window_other").len(), 1);
+  scheduler.cancel_all("window_other");
+  drain(&other);
+}
+
+#[test]
+fn backoff_doubles_and_caps() {
+  let base = Duration::from_secs(2);
+  let max = Duration::from_secs(60);
+
+  assert_eq!(backoff(1, base, max), Duration::from_secs(2));
+  assert_eq!(backoff(2, base, max), Duration::from_secs(4));
+  assert_eq!(backoff(5, base, max), Duration::from_secs(32));
+  assert_eq!(backoff(6, base, max), max);
+  assert_eq!(backoff(u32::MAX, base, max), max);
+}
@@ -0,0 +1,65 @@
+use app_lib::tray::menu::{opacity_to_alpha, overlay_title, OverlayAction, TrayAction, OPACITY_PRESETS};
+
+#[test]
+fn menu_ids() {
+  let label = "window_6f1c2a9e-0000-4000-8000-000000000000";
+  let mut actions = vec![
+    TrayAction::Show,
+    TrayAction::Toggle,
+    TrayAction::HideAll,
+    TrayAction::CloseAll,
+    TrayAction::OpenClipboard,
+    TrayAction::ClearCache,
+    TrayAction::Quit,
+    TrayAction::Reopen("0b7a4c1e-0000-4000-8000-000000000000".into()),
+  ];
+  for action in [
+    OverlayAction::Pin,
+    OverlayAction::ClickThrough,
+    OverlayAction::Mobile,
+    OverlayAction::Hidden,
+    OverlayAction::Reload,
+    OverlayAction::Focus,
+    OverlayAction::Close,
+  ] {
+    actions.push(TrayAction::Overlay(label.into(), action));
+  }
+  for opacity in OPACITY_PRESETS {
+    actions.push(TrayAction::Overlay(label.into(), OverlayAction::Opacity(opacity)));
+  }
+
+  for action in actions {
+    assert_eq!(TrayAction::parse(&action.id()), Some(action.clone()), "{}", action.id());
+  }
+  assert_eq!(
+    TrayAction::Overlay(label.into(), OverlayAction::Opacity(60)).id(),
+    format!("overlay/{}/opacity/60", label)
+  );
+
+  for id in [
+    "",
+    "unknown",
+    "overlay/",
+    "overlay//pin",
+    "overlay/window_a",
+    "overlay/window_a/unknown",
+    "overlay/window_a/opacity/55",
+    "overlay/window_a/opacity/",
+    "reopen/",
+  ] {
+    assert_eq!(TrayAction::parse(id), None, "{}", id);
+  }
+}
+
+#[test]
+fn titles() {
+  assert_eq!(overlay_title("Live Chat", "https://example.com/chat"), "Live Chat");
+  assert_eq!(overlay_title("no title", "https://live.example.com/chat"), "live.example.com");
+  assert_eq!(overlay_title("  ", "https://example.com/"), "example.com");
+  assert_eq!(overlay_title("Q&A", "https://example.com/"), "Q&&A");
+  assert_eq!(overlay_title(&"あ".repeat(50), "https://example.com/").chars().count(), 40);
+
+  assert_eq!(opacity_to_alpha(100), 255);
+  assert_eq!(opacity_to_alpha(60), 153);
+  assert_eq!(opacity_to_alpha(0), 0);
+}
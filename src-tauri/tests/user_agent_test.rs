@@ -0,0 +1,118 @@
+use app_lib::view::ctrl::user_agent::preset::{
+  all_presets, builtin_presets, default_preset, find_preset, migrate_preset, UserAgentPreset, DESKTOP_PRESET, MOBILE_PRESET,
+};
+
+fn custom(id: &str) -> UserAgentPreset {
+  UserAgentPreset {
+    id: id.into(),
+    name: "Custom".into(),
+    user_agent: "Custom UA".into(),
+    mobile: true,
+    viewport_width: 360,
+    viewport_height: 640,
+    device_scale_factor: 2.0,
+    platform: "Linux armv81".into(),
+    client_hints: None,
+  }
+}
+
+#[test]
+fn builtin() {
+  let presets = builtin_presets();
+  for id in [
+    DESKTOP_PRESET,
+    MOBILE_PRESET,
+    "desktop_chrome",
+    "desktop_firefox",
+    "iphone_safari",
+    "ipad_safari",
+  ] {
+    let preset = presets.iter().find(|v| v.id == id).unwrap();
+    assert!(preset.validate().is_ok(), "{}", id);
+  }
+
+  let iphone = find_preset(&[], "iphone_safari").unwrap();
+  assert!(iphone.mobile);
+  assert_eq!(iphone.viewport_width, 393);
+  assert!(iphone.user_agent.contains("CPU iPhone OS"));
+  assert!(find_preset(&[], "missing").is_none());
+}
+
+#[test]
+fn fallback_user_agent() {
+  let desktop = find_preset(&[], DESKTOP_PRESET).unwrap();
+  let mobile = find_preset(&[], MOBILE_PRESET).unwrap();
+  let firefox = find_preset(&[], "desktop_firefox").unwrap();
+
+  assert_eq!(desktop.user_agent("desktop ua", "mobile ua"), "desktop ua");
+  assert_eq!(mobile.user_agent("desktop ua", "mobile ua"), "mobile ua");
+  assert!(firefox.user_agent("desktop ua", "mobile ua").contains("Firefox"));
+}
+
+#[test]
+fn custom_overrides_builtin() {
+  let presets = vec![custom("iphone_safari"), custom("kiosk")];
+
+  assert_eq!(find_preset(&presets, "iphone_safari").unwrap().user_agent, "Custom UA");
+  let all = all_presets(&presets);
+  assert_eq!(all.len(), builtin_presets().len() + 1);
+  assert_eq!(all.iter().find(|v| v.id == "kiosk").unwrap().viewport_width, 360);
+
+  let mut invalid = custom("");
+  assert!(invalid.validate().is_err());
+  invalid.id = "zero".into();
+  invalid.device_scale_factor = 0.0;
+  assert!(invalid.validate().is_err());
+}
+
+#[test]
+fn override_params() {
+  let desktop = find_preset(&[], DESKTOP_PRESET).unwrap();
+  assert_eq!(desktop.override_params("ignored")["userAgent"], "");
+
+  let mobile = find_preset(&[], MOBILE_PRESET).unwrap();
+  let params = mobile.override_params("mobile ua");
+  assert_eq!(params["userAgent"], "mobile ua");
+  assert_eq!(params["userAgentMetadata"]["platform"], "Android");
+  assert_eq!(params["userAgentMetadata"]["mobile"], true);
+  assert_eq!(params["userAgentMetadata"]["brands"][0]["brand"], "Chromium");
+
+  // Safariはクライアントヒントを送らない
+  let iphone = find_preset(&[], "iphone_safari").unwrap();
+  let params = iphone.override_params(&iphone.user_agent);
+  assert_eq!(params["platform"], "iPhone");
+  assert!(params.get("userAgentMetadata").is_none());
+}
+
+#[test]
+fn migrate_mobile_mode() {
+  #[derive(serde::Deserialize)]
+  struct Window {
+    #[serde(alias = "mobile_mode", default = "default_preset", deserialize_with = "migrate_preset")]
+    agent_preset: Box<str>,
+  }
+
+  let parse = |v: &str| toml::from_str::<Window>(v).unwrap().agent_preset;
+  assert_eq!(&*parse("mobile_mode = true"), MOBILE_PRESET);
+  assert_eq!(&*parse("mobile_mode = false"), DESKTOP_PRESET);
+  assert_eq!(&*parse(r#"agent_preset = "ipad_safari""#), "ipad_safari");
+  assert_eq!(&*parse(""), DESKTOP_PRESET);
+}
+
+#[test]
+fn emulation_size() {
+  let iphone = find_preset(&[], "iphone_safari").unwrap();
+  assert_eq!(iphone.window_size(100), Some((393.0, 852.0)));
+  assert_eq!(iphone.window_size(50), Some((196.5, 426.0)));
+
+  let (zoom, height) = iphone.fit_width(786.0).unwrap();
+  assert_eq!(zoom, 200);
+  assert_eq!(height, 1704.0);
+  assert_eq!(iphone.fit_width(10.0).unwrap().0, 20);
+
+  // デスクトップはエミュレーションできない
+  let desktop = find_preset(&[], DESKTOP_PRESET).unwrap();
+  assert!(!desktop.can_emulate());
+  assert_eq!(desktop.window_size(100), None);
+  assert_eq!(desktop.fit_width(800.0), None);
+}
@@ -5,6 +5,22 @@
 
 
 export const commands = {
+async getDeepLinkSettings() : Promise<Result<DeepLinkSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_deep_link_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setDeepLinkSettings(settings: DeepLinkSettings) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_deep_link_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async exit() : Promise<Result<null, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("exit") };
@@ -30,9 +46,61 @@ async getWindows() : Promise<SerDeWindowData[]> {
 async log(value: string[]) : Promise<void> {
     await TAURI_INVOKE("log", { value });
 },
-async viewCreate(url: string) : Promise<Result<null, string>> {
+async getRemote() : Promise<Result<RemoteSettings, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("view_create", { url }) };
+    return { status: "ok", data: await TAURI_INVOKE("get_remote") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 保存してサーバーを起動し直す、tokenを空にすると作り直す
+ */
+async setRemote(settings: RemoteSettings) : Promise<Result<RemoteSettings, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_remote", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async clearClosedWindows() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("clear_closed_windows") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 新しいものが先頭
+ */
+async listClosedWindows() : Promise<Result<ClosedWindow[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_closed_windows") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * id -> list_closed_windowsのid、Noneなら最後に閉じたもの
+ */
+async reopenClosed(id: string | null) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reopen_closed", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * incognito -> trueなら履歴に残さない
+ */
+async viewCreate(url: string, profile: string | null, incognito: boolean | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("view_create", { url, profile, incognito }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -54,7 +122,31 @@ async windowHide() : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
-async getStatus() : Promise<Result<[[boolean, number], boolean, boolean, boolean], string>> {
+/**
+ * domainsが空ならすべてのcookieを書き出す
+ * キャンセルされたらNone、それ以外は書き出した数を返す
+ */
+async exportCookies(label: string, domains: string[]) : Promise<Result<number | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_cookies", { label, domains }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 期限切れのcookieは読み飛ばす
+ * キャンセルされたらNone、それ以外は読み込んだ数を返す
+ */
+async importCookies(label: string) : Promise<Result<number | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_cookies", { label }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getStatus() : Promise<Result<[[boolean, number], boolean, boolean, boolean, RefreshStatus, BlockerStatus], string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_status") };
 } catch (e) {
@@ -102,6 +194,154 @@ async viewZoom(diff: number) : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+async getBlocker() : Promise<Result<BlockerStatus, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_blocker") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getBlockerSettings() : Promise<BlockerSettings> {
+    return await TAURI_INVOKE("get_blocker_settings");
+},
+async reloadFilterLists() : Promise<Result<FilterStats, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reload_filter_lists") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setBlocker(value: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_blocker", { value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 保存してフィルターリストを読み直す
+ */
+async setBlockerSettings(settings: BlockerSettings) : Promise<Result<FilterStats, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_blocker_settings", { settings }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async toggleBlocker() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_blocker") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 開いているサイトを許可リストに加える、既にあれば取り除く
+ */
+async toggleBlockerAllowlist() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_blocker_allowlist") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getChroma() : Promise<Result<[boolean, number, boolean], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_chroma") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * color -> 0xRRGGBB
+ * color_key -> ウィンドウにカラーキーを設定してデスクトップでもその色を透過する
+ */
+async setChroma(value: boolean, color: number, colorKey: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_chroma", { value, color, colorKey }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async toggleChroma() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_chroma") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getCustomCss() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_custom_css") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 読み込み直しても残る
+ */
+async setCustomCss(css: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_custom_css", { css }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getCustomScript() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_custom_script") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * ページを読み込むたびに実行するjs、変更は次に読み込んだときから
+ * 今開いているページのオリジンでだけ実行する
+ */
+async setCustomScript(script: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_custom_script", { script }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getEmulation() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_emulation") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setEmulation(value: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_emulation", { value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async toggleEmulation() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_emulation") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async getIgnoreCursorEvents() : Promise<Result<boolean, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_ignore_cursor_events") };
@@ -126,6 +366,73 @@ async toggleIgnoreCursorEvents() : Promise<Result<boolean, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+async getIncognito() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_incognito") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * true -> 履歴に残さない
+ */
+async setIncognito(value: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_incognito", { value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async toggleIncognito() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_incognito") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNavigationPolicy() : Promise<Result<NavigationPolicy, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_navigation_policy") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setNavigationPolicy(policy: NavigationPolicy) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_navigation_policy", { policy }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getPageTransparent() : Promise<Result<[boolean, boolean], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_page_transparent") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setPageTransparent(value: boolean, forceCss: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_page_transparent", { value, forceCss }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async togglePageTransparent() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_page_transparent") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async getPin() : Promise<Result<boolean, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_pin") };
@@ -150,6 +457,82 @@ async togglePin() : Promise<Result<boolean, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+async getRefreshInterval() : Promise<Result<number, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_refresh_interval") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getWatchdog() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_watchdog") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * secs -> 0で自動更新を止める
+ */
+async setRefreshInterval(secs: number) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_refresh_interval", { secs }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setWatchdog(value: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_watchdog", { value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async toggleWatchdog() : Promise<Result<boolean, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("toggle_watchdog") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getGlobalRequestRules() : Promise<RequestRule[]> {
+    return await TAURI_INVOKE("get_global_request_rules");
+},
+async getRequestRules() : Promise<Result<RequestRule[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_request_rules") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * すべてのオーバーレイに適用するルール
+ */
+async setGlobalRequestRules(rules: RequestRule[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_global_request_rules", { rules }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * オーバーレイだけに適用するルール
+ */
+async setRequestRules(rules: RequestRule[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_request_rules", { rules }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async getTransparent() : Promise<Result<[boolean, number], string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_transparent") };
@@ -174,6 +557,17 @@ async toggleTransparent(alpha: number) : Promise<Result<boolean, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+async getAgentPreset() : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_agent_preset") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * モバイルのプリセットを選択しているか
+ */
 async getUserAgent() : Promise<Result<boolean, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_user_agent") };
@@ -182,6 +576,49 @@ async getUserAgent() : Promise<Result<boolean, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * 組み込みのプリセットとConf.agent_presets
+ */
+async listAgentPresets() : Promise<UserAgentPreset[]> {
+    return await TAURI_INVOKE("list_agent_presets");
+},
+/**
+ * Conf.host_rewritesと組み込みの対応、前にあるものが優先される
+ */
+async listHostRewrites() : Promise<HostRewrite[]> {
+    return await TAURI_INVOKE("list_host_rewrites");
+},
+async setAgentPreset(id: string) : Promise<Result<UserAgentPreset, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_agent_preset", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Conf.agent_presetsを置き換える、組み込みと同じidなら上書きになる
+ */
+async setCustomAgentPresets(presets: UserAgentPreset[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_custom_agent_presets", { presets }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async setCustomHostRewrites(rewrites: HostRewrite[]) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_custom_host_rewrites", { rewrites }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * true -> MOBILE_PRESET
+ * false -> DESKTOP_PRESET
+ */
 async setUserAgent(value: boolean) : Promise<Result<null, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("set_user_agent", { value }) };
@@ -190,6 +627,9 @@ async setUserAgent(value: boolean) : Promise<Result<null, string>> {
     else return { status: "error", error: e  as any };
 }
 },
+/**
+ * モバイルのプリセットならデスクトップに、それ以外ならモバイルに切り替える
+ */
 async toggleUserAgent() : Promise<Result<boolean, string>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("toggle_user_agent") };
@@ -198,9 +638,280 @@ async toggleUserAgent() : Promise<Result<boolean, string>> {
     else return { status: "error", error: e  as any };
 }
 },
-async test() : Promise<Result<null, string>> {
+/**
+ * 展開済みの拡張機能のフォルダーを選んでインストールする、キャンセルしたらNone
+ * 既に読み込んでいるオーバーレイには次に開いたときに反映される
+ */
+async installExtension() : Promise<Result<ExtensionEntry | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("install_extension") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listExtensions() : Promise<Result<ExtensionEntry[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_extensions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * profile -> Noneなら共有のデータディレクトリ
+ */
+async setExtensionEnabled(id: string, profile: string | null, value: boolean) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_extension_enabled", { id, profile, value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 開いているオーバーレイから取り除いてからファイルを消す
+ */
+async uninstallExtension(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("uninstall_extension", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async clearHistory() : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("clear_history") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * label -> 指定すればそのオーバーレイの今の設定も残す
+ */
+async createBookmark(url: string, title: string, tags: string[], label: string | null) : Promise<Result<Bookmark<SerDeWindowData>, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_bookmark", { url, title, tags, label }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteBookmark(id: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_bookmark", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listBookmarkTags() : Promise<Result<string[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("list_bookmark_tags") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 作ったオーバーレイのlabelを返す
+ */
+async openBookmark(id: string) : Promise<Result<string, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("open_bookmark", { id }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * tag -> 指定すればそのタグのものだけ
+ */
+async searchBookmarks(query: string, tag: string | null) : Promise<Result<Bookmark<SerDeWindowData>[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("search_bookmarks", { query, tag }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * urlの入力欄の補完に使う
+ */
+async searchHistory(query: string, limit: number) : Promise<HistoryEntry[]> {
+    return await TAURI_INVOKE("search_history", { query, limit });
+},
+/**
+ * 同じidのブックマークを置き換える
+ */
+async setBookmark(bookmark: Bookmark<SerDeWindowData>) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_bookmark", { bookmark }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 選んだブラウザソースをオーバーレイにする、対応していないものが含まれていれば何も作らない
+ * 作ったオーバーレイのlabelを返す
+ */
+async importObsSources(sources: BrowserSource[]) : Promise<Result<string[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_obs_sources", { sources }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * OBSのシーンコレクションを選んでブラウザソースを一覧にする、キャンセルしたらNone
+ */
+async pickObsSceneCollection() : Promise<Result<SceneCollection | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("pick_obs_scene_collection") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * labelsが空なら開いているすべてのオーバーレイを書き出す
+ * キャンセルされたらNone、それ以外は書き出したファイルのパスを返す
+ */
+async exportPack(name: string, labels: string[]) : Promise<Result<string | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("export_pack", { name, labels }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 選んだオーバーレイだけを開く
+ * パックは他の人が作ったものなので、スクリプトがあれば確認して、許可されなければスクリプトを除いて開く
+ * 作ったオーバーレイのlabelを返す
+ */
+async importPack(path: string, selection: PackSelection[]) : Promise<Result<string[], string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("import_pack", { path, selection }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * パックを選んで中身と開いているオーバーレイとの重複を返す、キャンセルしたらNone
+ */
+async previewPack() : Promise<Result<PackPreview | null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_pack") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getPermissions() : Promise<SitePermission[]> {
+    return await TAURI_INVOKE("get_permissions");
+},
+/**
+ * origin -> Noneならすべてのオリジンの設定を消す
+ */
+async resetPermissions(origin: string | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("reset_permissions", { origin }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Askを指定すると設定を消す
+ */
+async setPermission(origin: string, kind: PermissionKind, value: PermissionState) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_permission", { origin, kind, value }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * cookieやキャッシュ等をすべて消す
+ */
+async clearProfile(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("clear_profile", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createProfile(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_profile", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async deleteProfile(name: string) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_profile", { name }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async listProfiles() : Promise<string[]> {
+    return await TAURI_INVOKE("list_profiles");
+},
+async getProxy() : Promise<ProxySettings> {
+    return await TAURI_INVOKE("get_proxy");
+},
+/**
+ * labelのオーバーレイだけに使うプロキシ、proxy -> Noneならプロファイルかグローバルの設定に戻す
+ * webviewの引数は作るときにしか渡せないので、閉じて開き直すまで反映されない
+ */
+async setOverlayProxy(label: string, proxy: ProxyConfig | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_overlay_proxy", { label, proxy }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * profile -> Noneならグローバルの設定、Someでproxy -> Noneならプロファイルの設定を消す
+ * 開いているオーバーレイには作り直すまで反映されない
+ */
+async setProxy(profile: string | null, proxy: ProxyConfig | null) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_proxy", { profile, proxy }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * 保存せずにプロキシのurlとバイパスリストの書式を確認する
+ */
+async validateProxy(proxy: ProxyConfig) : Promise<Result<null, string>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("validate_proxy", { proxy }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * labelのオーバーレイのオリジンかプロファイルのデータを消す
+ */
+async clearSiteData(label: string, scope: ClearScope, kinds: SiteDataKind[]) : Promise<Result<ClearReport, string>> {
     try {
-    return { status: "ok", data: await TAURI_INVOKE("test") };
+    return { status: "ok", data: await TAURI_INVOKE("clear_site_data", { label, scope, kinds }) };
 } catch (e) {
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
@@ -226,11 +937,76 @@ export const WINDOW_LABEL_PREFIX = "window_" as const;
 
 /** user-defined types **/
 
-export type Conf = { agent_desktop: string; agent_mobile: string; shortcut_key: string; windows: SerDeWindowData[] }
+export type BlockerSettings = { lists: string[]; allowlist: string[] }
+export type BlockerStatus = { enabled: boolean; allowed: boolean; blocked: number; hidden: number }
+export type Bookmark<T> = { id: string; url: string; title: string; tags: string[]; settings: T | null }
+export type Brand = { brand: string; version: string }
+export type BrowserSource = { name: string; scenes: string[]; url: string; width: number; height: number; css: string; unsupported: string | null }
+export type ClearReport = { scope: ClearScope; target: string; cleared: SiteDataKind[] }
+export type ClearScope = "Origin" | "Profile"
+/**
+ * Sec-CH-UA-*とnavigator.userAgentDataに使われる値
+ */
+export type ClientHints = { brands: Brand[]; platform: string; platform_version: string; architecture: string; model: string }
+export type ClosedWindow = { id: string; window: SerDeWindowData; position: [number, number]; size: [number, number] }
+export type Conf = { agent_desktop: string; agent_mobile: string; agent_presets: UserAgentPreset[]; host_rewrites: HostRewrite[]; shortcut_key: string; clipboard_shortcut_key: string; reopen_shortcut_key: string; windows: SerDeWindowData[]; permissions: PermissionTable; profiles: string[]; proxy: ProxySettings; request_rules: RequestRule[]; blocker: BlockerSettings; remote: RemoteSettings; deep_link: DeepLinkSettings }
+/**
+ * 同じurlのオーバーレイが開いているときの扱い
+ */
+export type ConflictResolution = "Skip" | "Replace" | "Duplicate"
+export type DeepLinkSettings = { allowlist: string[] }
+export type ExtensionEntry = { id: string; name: string; version: string; description: string; manifest_version: number; enabled: string[] }
+export type FilterStats = { network: number; cosmetic: number; skipped: number }
+export type HeaderAction = { action: "Add"; name: string; value: string } | { action: "Set"; name: string; value: string } | { action: "Remove"; name: string }
+export type HistoryEntry = { url: string; title: string; visited_at: number; visits: number }
+/**
+ * デスクトップ用とモバイル用のホストの対応
+ * `*.wikipedia.org` <-> `*.m.wikipedia.org`のように先頭の`*`でサブドメイン1つを引き継げる
+ */
+export type HostRewrite = { desktop: string; mobile: string }
+/**
+ * リンクをどこで開くか
+ */
+export type LinkAction = "Current" | "Browser" | "Overlay" | "Drop"
+/**
+ * どのurlへの移動を許可するか
+ */
+export type NavigationMode = { mode: "AllowAll" } | { mode: "SameDomain" } | { mode: "Allowlist"; patterns: string[] } | { mode: "Blocklist"; patterns: string[] }
+export type NavigationPolicy = { mode: NavigationMode; blocked: LinkAction; popup: LinkAction }
+export type PackOverlay = { url: string; title: string; position: [number, number]; size: [number, number]; opacity: number | null; pin: boolean; click_through: boolean; agent_preset: string | null; zoom: number; css: string; script: string }
+export type PackOverlayPreview = { overlay: PackOverlay; conflict: string | null }
+export type PackPreview = { path: string; name: string; overlays: PackOverlayPreview[]; has_scripts: boolean }
+/**
+ * index -> Pack::overlaysの位置
+ */
+export type PackSelection = { index: number; resolution: ConflictResolution }
+export type PermissionKind = "Camera" | "Microphone" | "Notifications" | "Geolocation" | "Clipboard"
+export type PermissionState = "Allow" | "Deny" | "Ask"
+/**
+ * オリジンごとの許可設定、Askは保存しない
+ */
+export type PermissionTable = SitePermission[]
+export type ProxyConfig = { enabled: boolean; http: string | null; https: string | null; socks5: string | null; bypass: string[] }
+/**
+ * Conf.proxyの中身、プロファイルごとの設定はグローバルの設定より優先される
+ */
+export type ProxySettings = { global: ProxyConfig; profiles: { [key in string]: ProxyConfig } }
+export type RefreshStatus = { interval: number; watchdog: boolean; failures: number; tasks: TaskStatus[] }
+export type RemoteSettings = { enabled: boolean; port: number; token: string }
+export type RequestRule = { domain: string; user_agent: string | null; headers: HeaderAction[] }
+export type SceneCollection = { name: string; sources: BrowserSource[] }
 export type SerDeAppState = { config: string; windows: SerDeWindowData[] }
-export type SerDeWindowData = { title: string; label: string; url: string; pointer_ignore: boolean; mobile_mode: boolean; transparent: [boolean, number]; pin: boolean; zoom: number }
+export type SerDeWindowData = { title: string; label: string; url: string; profile: string | null; proxy: ProxyConfig | null; pointer_ignore: boolean; agent_preset: string; emulation: boolean; transparent: [boolean, number]; page_transparent: [boolean, boolean]; chroma: [boolean, number, boolean]; pin: boolean; zoom: number; refresh: number; watchdog: boolean; navigation: NavigationPolicy; request_rules: RequestRule[]; blocker: boolean; incognito: boolean; custom_css: string; custom_script: string; custom_script_origin: string | null }
+export type SiteDataKind = "Cookies" | "Cache" | "LocalStorage" | "ServiceWorkers"
+export type SitePermission = { origin: string; kind: PermissionKind; state: PermissionState }
+/**
+ * ウィンドウごとのタイマーの種類
+ */
+export type TaskKind = "Refresh" | "Watchdog" | "BlankCheck"
+export type TaskStatus = { kind: TaskKind; repeat: boolean; interval_ms: number; next_in_ms: number | null }
 export type UpdateState = SerDeWindowData[]
 export type UpdateWindows = null
+export type UserAgentPreset = { id: string; name: string; user_agent: string; mobile: boolean; viewport_width: number; viewport_height: number; device_scale_factor: number; platform: string; client_hints: ClientHints | null }
 
 /** tauri-specta globals **/
 